@@ -0,0 +1,124 @@
+//! Writes animated PNGs from frames already encoded as PNG.
+//!
+//! The image data of each frame's PNG is reused as is, so the only work is
+//! rewriting the chunks. See https://wiki.mozilla.org/APNG_Specification
+use std::io::Write;
+use super::{
+    AnimationError,
+    Result
+};
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+#[derive(Default)]
+pub struct ApngEncoder {
+    header: Option<Vec<u8>>,
+    /// The concatenated IDAT data of each frame.
+    frames: Vec<Vec<u8>>
+}
+
+impl ApngEncoder {
+    /// Adds a frame from the bytes of a PNG file. Frames must all have the same
+    /// size, bit depth and colour type.
+    pub fn add_frame(&mut self, png: &[u8]) -> Result<()> {
+        let mut header: Option<&[u8]> = None;
+        let mut data = Vec::<u8>::new();
+        for (kind, chunk_data) in chunks(png)? {
+            match &kind {
+                b"IHDR" => header = Some(chunk_data),
+                b"IDAT" => data.extend_from_slice(chunk_data),
+                _ => {}
+            }
+        }
+        let header = header.ok_or(AnimationError::InvalidPng)?;
+        match &self.header {
+            Some(first) if first.as_slice() != header => return Err(AnimationError::FrameMismatch),
+            Some(_) => {},
+            None => self.header = Some(header.to_vec())
+        }
+        self.frames.push(data);
+        Ok(())
+    }
+
+    /// Writes the animation, each frame shown for `delay_num / delay_den` seconds
+    /// and played `loops` times, 0 for forever.
+    pub fn write<W: Write>(&self, writer: &mut W, delay_num: u16, delay_den: u16, loops: u32) -> std::io::Result<()> {
+        let Some(header) = &self.header else {
+            return Ok(());
+        };
+        let width = &header[0..4];
+        let height = &header[4..8];
+
+        writer.write_all(&PNG_SIGNATURE)?;
+        write_chunk(writer, b"IHDR", header)?;
+
+        let mut actl = Vec::with_capacity(8);
+        actl.extend_from_slice(&(self.frames.len() as u32).to_be_bytes());
+        actl.extend_from_slice(&loops.to_be_bytes());
+        write_chunk(writer, b"acTL", &actl)?;
+
+        let mut sequence: u32 = 0;
+        for (i, data) in self.frames.iter().enumerate() {
+            let mut fctl = Vec::with_capacity(26);
+            fctl.extend_from_slice(&sequence.to_be_bytes());
+            fctl.extend_from_slice(width);
+            fctl.extend_from_slice(height);
+            fctl.extend_from_slice(&0u32.to_be_bytes()); // x offset
+            fctl.extend_from_slice(&0u32.to_be_bytes()); // y offset
+            fctl.extend_from_slice(&delay_num.to_be_bytes());
+            fctl.extend_from_slice(&delay_den.to_be_bytes());
+            fctl.push(0); // dispose: none
+            fctl.push(0); // blend: source
+            write_chunk(writer, b"fcTL", &fctl)?;
+            sequence += 1;
+
+            // The first frame doubles as the still image shown by viewers without APNG support.
+            if i == 0 {
+                write_chunk(writer, b"IDAT", data)?;
+            } else {
+                let mut fdat = Vec::with_capacity(data.len() + 4);
+                fdat.extend_from_slice(&sequence.to_be_bytes());
+                fdat.extend_from_slice(data);
+                write_chunk(writer, b"fdAT", &fdat)?;
+                sequence += 1;
+            }
+        }
+        write_chunk(writer, b"IEND", &[])?;
+        writer.flush()
+    }
+}
+
+/// Splits a PNG into its chunk types and data.
+fn chunks(png: &[u8]) -> Result<Vec<([u8; 4], &[u8])>> {
+    let mut rest = png.strip_prefix(&PNG_SIGNATURE[..]).ok_or(AnimationError::InvalidPng)?;
+    let mut chunks = Vec::new();
+    while rest.len() >= 12 {
+        let length = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize;
+        if rest.len() < 12 + length {
+            return Err(AnimationError::InvalidPng);
+        }
+        let kind = [rest[4], rest[5], rest[6], rest[7]];
+        chunks.push((kind, &rest[8..8 + length]));
+        rest = &rest[12 + length..];
+    }
+    Ok(chunks)
+}
+
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> std::io::Result<()> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(crc32(0xffff_ffff, kind), data) ^ 0xffff_ffff;
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// The CRC used by PNG, continued from `crc`.
+fn crc32(mut crc: u32, bytes: &[u8]) -> u32 {
+    for &b in bytes {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { 0xedb8_8320 ^ (crc >> 1) } else { crc >> 1 };
+        }
+    }
+    crc
+}
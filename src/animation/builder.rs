@@ -0,0 +1,255 @@
+//! Turns a sequence of frames into an animated GIF, animated PNG or video.
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::Path
+};
+use opencv::{
+    imgcodecs::imencode,
+    imgproc::{
+        cvt_color,
+        resize,
+        COLOR_BGRA2BGR,
+        COLOR_GRAY2BGR,
+        INTER_AREA
+    },
+    videoio::VideoWriter,
+    prelude::{
+        MatTraitConst,
+        VideoWriterTrait,
+        VideoWriterTraitConst
+    },
+    core::{
+        Mat,
+        Rect,
+        Size,
+        Vector
+    }
+};
+use super::{
+    apng::ApngEncoder,
+    gif::GifEncoder,
+    AnimationError,
+    Result
+};
+
+pub struct AnimationOptions {
+    pub fps: f64,
+    /// How many times to play, 0 for forever. Videos play once.
+    pub loops: u16,
+    /// Applied before resizing.
+    pub crop: Option<Rect>,
+    pub resize: Option<Resize>,
+    /// The four character code of the codec for videos, chosen from the extension if not given.
+    pub codec: Option<String>
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        AnimationOptions {
+            fps: 10.0,
+            loops: 0,
+            crop: None,
+            resize: None,
+            codec: None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resize {
+    To { width: u32, height: u32 },
+    Scale(f64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+    Video
+}
+
+impl AnimationFormat {
+    /// `.gif` and `.png` (or `.apng`) files are animated images, anything else is a video.
+    pub fn from_path(path: &Path) -> Self {
+        let extension = path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        match extension.as_str() {
+            "gif" => AnimationFormat::Gif,
+            "png" | "apng" => AnimationFormat::Apng,
+            _ => AnimationFormat::Video
+        }
+    }
+}
+
+/// Writes the frames to `path` in the format its extension asks for,
+/// returning the number of frames written.
+pub fn write_animation<I>(path: &Path, frames: I, options: &AnimationOptions) -> Result<usize> where
+    I: IntoIterator<Item = Result<Mat>>
+{
+    let mut target_size: Option<Size> = None;
+    let prepared = frames.into_iter()
+        .map(|frame| frame.and_then(|frame| prepare(&frame, options, &mut target_size)));
+
+    match AnimationFormat::from_path(path) {
+        AnimationFormat::Gif => write_gif(path, prepared, options),
+        AnimationFormat::Apng => write_apng(path, prepared, options),
+        AnimationFormat::Video => write_video(path, prepared, options)
+    }
+}
+
+/// Converts to 8 bit BGR, crops and resizes, so every frame comes out the size of the first.
+fn prepare(frame: &Mat, options: &AnimationOptions, target_size: &mut Option<Size>) -> Result<Mat> {
+    let bgr = match frame.channels() {
+        1 => convert(frame, COLOR_GRAY2BGR)?,
+        4 => convert(frame, COLOR_BGRA2BGR)?,
+        _ => frame.try_clone()?
+    };
+    let cropped = match options.crop {
+        Some(crop) => {
+            let size = bgr.size()?;
+            if crop.x < 0 || crop.y < 0 || crop.x + crop.width > size.width || crop.y + crop.height > size.height {
+                return Err(AnimationError::CropOutsideFrame(crop, size.width, size.height));
+            }
+            Mat::roi(&bgr, crop)?
+        },
+        None => bgr
+    };
+
+    let size = cropped.size()?;
+    let target = *target_size.get_or_insert_with(|| match options.resize {
+        Some(Resize::To { width, height }) => Size::new(width as i32, height as i32),
+        Some(Resize::Scale(scale)) => Size::new(
+            ((size.width as f64 * scale).round() as i32).max(1),
+            ((size.height as f64 * scale).round() as i32).max(1)),
+        None => size
+    });
+    if size == target {
+        // Cloning makes the cropped area continuous.
+        return Ok(cropped.try_clone()?);
+    }
+    let mut resized = Mat::default();
+    resize(&cropped, &mut resized, target, 0.0, 0.0, INTER_AREA)?;
+    Ok(resized)
+}
+
+fn convert(frame: &Mat, code: i32) -> Result<Mat> {
+    let mut converted = Mat::default();
+    cvt_color(frame, &mut converted, code, 0)?;
+    Ok(converted)
+}
+
+fn write_gif<I>(path: &Path, frames: I, options: &AnimationOptions) -> Result<usize> where
+    I: Iterator<Item = Result<Mat>>
+{
+    // Hundredths of a second. Many viewers slow anything under 2 down to 10.
+    let delay = (100.0 / options.fps).round().clamp(1.0, u16::MAX as f64) as u16;
+    let mut encoder: Option<GifEncoder<BufWriter<File>>> = None;
+    let mut count = 0;
+    for frame in frames {
+        let frame = frame?;
+        let size = frame.size()?;
+        let encoder = match encoder.as_mut() {
+            Some(encoder) => encoder,
+            None => {
+                let (Ok(width), Ok(height)) = (u16::try_from(size.width), u16::try_from(size.height)) else {
+                    return Err(AnimationError::TooLarge(size.width, size.height));
+                };
+                let file = create_file(path)?;
+                let new_encoder = GifEncoder::new(file, width, height, options.loops)
+                    .map_err(|err| io_error(path, err))?;
+                encoder.insert(new_encoder)
+            }
+        };
+        encoder.add_frame(&bgr_to_rgb(frame.data_bytes()?), delay)
+            .map_err(|err| io_error(path, err))?;
+        count += 1;
+    }
+    let encoder = encoder.ok_or(AnimationError::NoFrames)?;
+    encoder.finish().map_err(|err| io_error(path, err))?;
+    Ok(count)
+}
+
+fn write_apng<I>(path: &Path, frames: I, options: &AnimationOptions) -> Result<usize> where
+    I: Iterator<Item = Result<Mat>>
+{
+    // The number of frames comes before them, so they are gathered as PNG first.
+    let mut encoder = ApngEncoder::default();
+    let mut count = 0;
+    for frame in frames {
+        let mut png = Vector::<u8>::new();
+        imencode(".png", &frame?, &mut png, &Vector::new())?;
+        encoder.add_frame(png.as_slice())?;
+        count += 1;
+    }
+    if count == 0 {
+        return Err(AnimationError::NoFrames);
+    }
+    // Each frame shows for 100 / (fps * 100) seconds.
+    let delay_den = (options.fps * 100.0).round().clamp(1.0, u16::MAX as f64) as u16;
+    let mut file = create_file(path)?;
+    encoder.write(&mut file, 100, delay_den, options.loops as u32)
+        .map_err(|err| io_error(path, err))?;
+    Ok(count)
+}
+
+fn write_video<I>(path: &Path, frames: I, options: &AnimationOptions) -> Result<usize> where
+    I: Iterator<Item = Result<Mat>>
+{
+    let codec = options.codec.clone().unwrap_or_else(|| default_codec(path).to_string());
+    let chars: Vec<char> = codec.chars().collect();
+    let fourcc = match chars[..] {
+        [c1, c2, c3, c4] => VideoWriter::fourcc(c1, c2, c3, c4)?,
+        _ => return Err(AnimationError::InvalidCodec(codec))
+    };
+
+    let mut writer: Option<VideoWriter> = None;
+    let mut count = 0;
+    for frame in frames {
+        let frame = frame?;
+        let writer = match writer.as_mut() {
+            Some(writer) => writer,
+            None => {
+                let new_writer = VideoWriter::new(&path.to_string_lossy(), fourcc, options.fps, frame.size()?, true)?;
+                if !new_writer.is_opened()? {
+                    return Err(AnimationError::NotOpened(path.to_path_buf()));
+                }
+                writer.insert(new_writer)
+            }
+        };
+        writer.write(&frame)?;
+        count += 1;
+    }
+    let mut writer = writer.ok_or(AnimationError::NoFrames)?;
+    writer.release()?;
+    Ok(count)
+}
+
+/// A codec that OpenCV can usually write to the container the extension names.
+fn default_codec(path: &Path) -> &'static str {
+    let extension = path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "mp4" | "m4v" | "mov" => "mp4v",
+        "webm" => "VP80",
+        _ => "MJPG"
+    }
+}
+
+fn bgr_to_rgb(bgr: &[u8]) -> Vec<u8> {
+    bgr.chunks_exact(3)
+        .flat_map(|p| [p[2], p[1], p[0]])
+        .collect()
+}
+
+fn create_file(path: &Path) -> Result<BufWriter<File>> {
+    File::create(path)
+        .map(BufWriter::new)
+        .map_err(|err| io_error(path, err))
+}
+
+fn io_error(path: &Path, source: std::io::Error) -> AnimationError {
+    AnimationError::Io { path: path.to_path_buf(), source }
+}
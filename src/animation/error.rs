@@ -0,0 +1,50 @@
+//! The errors returned while building animations.
+use std::{
+    io,
+    path::PathBuf
+};
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, AnimationError>;
+
+#[derive(Debug, Error)]
+pub enum AnimationError {
+    #[error("{0}")]
+    OpenCv(#[from] opencv::Error),
+
+    #[error("{path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: io::Error
+    },
+
+    #[error("no frames to animate")]
+    NoFrames,
+
+    /// OpenCV couldn't read the image, which it reports without saying why.
+    #[error("failed to read image {0}")]
+    ImageNotRead(PathBuf),
+
+    /// OpenCV encoded something other than a PNG when asked for one.
+    #[error("invalid PNG data")]
+    InvalidPng,
+
+    /// Frames for an animated PNG must have the same size and pixel type.
+    #[error("frames differ in size or type")]
+    FrameMismatch,
+
+    /// The crop rectangle isn't inside the frame.
+    #[error("crop {0:?} is outside the {1}x{2} frame")]
+    CropOutsideFrame(opencv::core::Rect, i32, i32),
+
+    /// GIF limits each side to 65535 pixels.
+    #[error("{0}x{1} is too large for a GIF")]
+    TooLarge(i32, i32),
+
+    #[error("failed to open {0} for writing")]
+    NotOpened(PathBuf),
+
+    /// Codecs are given by a four character code, such as MJPG.
+    #[error("invalid codec {0:?}, expected four characters")]
+    InvalidCodec(String)
+}
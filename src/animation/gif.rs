@@ -0,0 +1,225 @@
+//! Writes animated GIFs.
+//!
+//! Each frame gets its own 256 colour palette chosen by median cut, which suits
+//! webcam frames better than one palette for the whole animation when the
+//! lighting changes. See https://www.w3.org/Graphics/GIF/spec-gif89a.txt
+use std::{
+    collections::HashMap,
+    io::Write
+};
+
+const MAX_COLOURS: usize = 256;
+/// Enough pixels to choose a palette from without looking at every one.
+const MAX_PALETTE_SAMPLES: usize = 20_000;
+const LZW_MIN_CODE_SIZE: u8 = 8;
+const LZW_MAX_CODE: u16 = 4096;
+
+pub struct GifEncoder<W: Write> {
+    writer: W,
+    width: u16,
+    height: u16
+}
+
+impl<W: Write> GifEncoder<W> {
+    /// Writes the header. `loops` is how many times to play, 0 for forever.
+    pub fn new(mut writer: W, width: u16, height: u16, loops: u16) -> std::io::Result<Self> {
+        writer.write_all(b"GIF89a")?;
+        // Logical screen descriptor, without a global colour table as each frame has its own.
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        writer.write_all(&[0x00, 0, 0])?;
+        // Netscape application extension, which sets the number of loops.
+        writer.write_all(&[0x21, 0xff, 0x0b])?;
+        writer.write_all(b"NETSCAPE2.0")?;
+        writer.write_all(&[0x03, 0x01])?;
+        writer.write_all(&loops.to_le_bytes())?;
+        writer.write_all(&[0x00])?;
+        Ok(GifEncoder { writer, width, height })
+    }
+
+    /// Adds a frame of RGB pixels, shown for `delay` hundredths of a second.
+    pub fn add_frame(&mut self, rgb: &[u8], delay: u16) -> std::io::Result<()> {
+        let palette = median_cut(rgb, MAX_COLOURS);
+        let indices = map_to_palette(rgb, &palette);
+
+        // Graphic control extension: leave the frame in place, no transparency.
+        self.writer.write_all(&[0x21, 0xf9, 0x04, 0x04])?;
+        self.writer.write_all(&delay.to_le_bytes())?;
+        self.writer.write_all(&[0x00, 0x00])?;
+
+        // Image descriptor with a local colour table of 256 entries.
+        self.writer.write_all(&[0x2c, 0, 0, 0, 0])?;
+        self.writer.write_all(&self.width.to_le_bytes())?;
+        self.writer.write_all(&self.height.to_le_bytes())?;
+        self.writer.write_all(&[0x80 | 0x07])?;
+        for i in 0..MAX_COLOURS {
+            self.writer.write_all(&palette.get(i).copied().unwrap_or([0, 0, 0]))?;
+        }
+
+        self.writer.write_all(&[LZW_MIN_CODE_SIZE])?;
+        for block in lzw_encode(&indices, LZW_MIN_CODE_SIZE).chunks(255) {
+            self.writer.write_all(&[block.len() as u8])?;
+            self.writer.write_all(block)?;
+        }
+        self.writer.write_all(&[0x00])
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.writer.write_all(&[0x3b])?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Chooses up to `max_colours` colours by repeatedly splitting the box of colours
+/// with the widest range at its median.
+fn median_cut(rgb: &[u8], max_colours: usize) -> Vec<[u8; 3]> {
+    let pixel_count = rgb.len() / 3;
+    let step = (pixel_count / MAX_PALETTE_SAMPLES).max(1);
+    let mut samples: Vec<[u8; 3]> = rgb.chunks_exact(3)
+        .step_by(step)
+        .map(|p| [p[0], p[1], p[2]])
+        .collect();
+    if samples.is_empty() {
+        return vec![[0, 0, 0]];
+    }
+
+    // Each box is a range of samples.
+    let mut boxes = vec![(0, samples.len())];
+    while boxes.len() < max_colours {
+        let widest = boxes.iter()
+            .enumerate()
+            .filter(|(_, (start, end))| end - start > 1)
+            .map(|(i, &(start, end))| {
+                let (channel, range) = widest_channel(&samples[start..end]);
+                (i, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range);
+        let Some((i, channel, range)) = widest else { break };
+        if range == 0 {
+            break;
+        }
+        let (start, end) = boxes[i];
+        samples[start..end].sort_unstable_by_key(|p| p[channel]);
+        let middle = start + (end - start) / 2;
+        boxes[i] = (start, middle);
+        boxes.push((middle, end));
+    }
+
+    boxes.iter()
+        .map(|&(start, end)| mean_colour(&samples[start..end]))
+        .collect()
+}
+
+fn widest_channel(colours: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let min = colours.iter().map(|p| p[c]).min().unwrap_or(0);
+            let max = colours.iter().map(|p| p[c]).max().unwrap_or(0);
+            (c, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+fn mean_colour(colours: &[[u8; 3]]) -> [u8; 3] {
+    let mut sums = [0u64; 3];
+    for p in colours {
+        for c in 0..3 {
+            sums[c] += p[c] as u64;
+        }
+    }
+    let n = colours.len().max(1) as u64;
+    [(sums[0] / n) as u8, (sums[1] / n) as u8, (sums[2] / n) as u8]
+}
+
+/// Finds the nearest palette entry for each pixel. Colours are looked up at 5 bits
+/// per channel, so the search is only done once for each group of similar colours.
+fn map_to_palette(rgb: &[u8], palette: &[[u8; 3]]) -> Vec<u8> {
+    let mut nearest_cache: HashMap<u16, u8> = HashMap::new();
+    rgb.chunks_exact(3)
+        .map(|p| {
+            let key = ((p[0] as u16 >> 3) << 10) | ((p[1] as u16 >> 3) << 5) | (p[2] as u16 >> 3);
+            *nearest_cache.entry(key).or_insert_with(|| nearest(p, palette))
+        })
+        .collect()
+}
+
+fn nearest(pixel: &[u8], palette: &[[u8; 3]]) -> u8 {
+    palette.iter()
+        .enumerate()
+        .min_by_key(|(_, colour)| {
+            (0..3).map(|c| {
+                let d = pixel[c] as i32 - colour[c] as i32;
+                d * d
+            }).sum::<i32>()
+        })
+        .map_or(0, |(i, _)| i as u8)
+}
+
+/// Variable code width LZW, as GIF uses it.
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear_code: u16 = 1 << min_code_size;
+    let end_code: u16 = clear_code + 1;
+    let mut out = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size + 1;
+
+    out.write(clear_code, code_size);
+    let Some((&first, rest)) = indices.split_first() else {
+        out.write(end_code, code_size);
+        return out.finish();
+    };
+    let mut prefix = first as u16;
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+        out.write(prefix, code_size);
+        if next_code == LZW_MAX_CODE {
+            out.write(clear_code, code_size);
+            table.clear();
+            next_code = end_code + 1;
+            code_size = min_code_size + 1;
+        } else {
+            if next_code >= 1 << code_size {
+                code_size += 1;
+            }
+            table.insert((prefix, index), next_code);
+            next_code += 1;
+        }
+        prefix = index as u16;
+    }
+    out.write(prefix, code_size);
+    out.write(end_code, code_size);
+    out.finish()
+}
+
+/// Packs codes least significant bit first.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
@@ -0,0 +1,28 @@
+mod error;
+pub use error::{
+    AnimationError,
+    Result
+};
+
+mod gif;
+mod apng;
+
+mod builder;
+pub use builder::{
+    write_animation,
+    AnimationOptions,
+    Resize
+};
+
+mod sources;
+pub use sources::{
+    image_files,
+    read_images
+};
+
+mod sheet;
+pub use sheet::{
+    contact_sheet,
+    SheetOptions,
+    Tile
+};
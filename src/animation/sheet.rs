@@ -0,0 +1,166 @@
+//! Tiles images into one with a label under each, for comparing them at a glance.
+use opencv::{
+    imgproc::{
+        cvt_color,
+        get_text_size,
+        put_text,
+        resize,
+        COLOR_BGRA2BGR,
+        COLOR_GRAY2BGR,
+        FONT_HERSHEY_SIMPLEX,
+        INTER_AREA,
+        LINE_AA
+    },
+    prelude::MatTraitConst,
+    core::{
+        copy_make_border,
+        hconcat,
+        vconcat,
+        Mat,
+        Point,
+        Scalar,
+        Size,
+        Vector,
+        BORDER_CONSTANT,
+        CV_8UC3
+    }
+};
+use super::{
+    AnimationError,
+    Result
+};
+
+const BACKGROUND: f64 = 32.0;
+const FONT_SCALE: f64 = 0.5;
+const PADDING: i32 = 4;
+
+pub struct Tile {
+    /// `None` leaves the tile empty, for something that gave no image.
+    pub image: Option<Mat>,
+    pub label: String
+}
+
+pub struct SheetOptions {
+    /// Images are scaled to fit within the tile, keeping their shape.
+    pub tile_width: u32,
+    pub tile_height: u32,
+    /// Tiles across, or as near square as possible if `None`.
+    pub columns: Option<u32>
+}
+
+impl Default for SheetOptions {
+    fn default() -> Self {
+        SheetOptions {
+            tile_width: 320,
+            tile_height: 240,
+            columns: None
+        }
+    }
+}
+
+/// Lays the tiles out left to right, top to bottom.
+pub fn contact_sheet(tiles: &[Tile], options: &SheetOptions) -> Result<Mat> {
+    if tiles.is_empty() {
+        return Err(AnimationError::NoFrames);
+    }
+    let columns = options.columns
+        .unwrap_or_else(|| (tiles.len() as f64).sqrt().ceil() as u32)
+        .clamp(1, tiles.len() as u32) as usize;
+
+    let mut rows = Vector::<Mat>::new();
+    for row_tiles in tiles.chunks(columns) {
+        let mut cells = Vector::<Mat>::new();
+        for i in 0..columns {
+            cells.push(match row_tiles.get(i) {
+                Some(tile) => render_tile(tile, options)?,
+                None => blank(options.tile_width as i32 + 2 * PADDING, cell_height(options)?)?
+            });
+        }
+        let mut row = Mat::default();
+        hconcat(&cells, &mut row)?;
+        rows.push(row);
+    }
+    let mut sheet = Mat::default();
+    vconcat(&rows, &mut sheet)?;
+    Ok(sheet)
+}
+
+/// The image fitted into the tile, with padding around it and the label below.
+fn render_tile(tile: &Tile, options: &SheetOptions) -> Result<Mat> {
+    let (width, height) = (options.tile_width as i32, options.tile_height as i32);
+    let picture = match &tile.image {
+        Some(image) if !image.empty() => {
+            let fitted = fit(&to_bgr(image)?, width, height)?;
+            let size = fitted.size()?;
+            let (left, top) = ((width - size.width) / 2, (height - size.height) / 2);
+            let mut bordered = Mat::default();
+            copy_make_border(&fitted, &mut bordered,
+                top + PADDING, height - size.height - top + PADDING,
+                left + PADDING, width - size.width - left + PADDING,
+                BORDER_CONSTANT, Scalar::all(BACKGROUND))?;
+            bordered
+        },
+        _ => blank(width + 2 * PADDING, height + 2 * PADDING)?
+    };
+
+    let mut label = blank(width + 2 * PADDING, label_height()?)?;
+    let text = fit_text(&tile.label, width)?;
+    let mut baseline = 0;
+    let text_size = get_text_size(&text, FONT_HERSHEY_SIMPLEX, FONT_SCALE, 1, &mut baseline)?;
+    put_text(&mut label, &text, Point::new(PADDING, PADDING + text_size.height),
+        FONT_HERSHEY_SIMPLEX, FONT_SCALE, Scalar::all(255.0), 1, LINE_AA, false)?;
+
+    let mut parts = Vector::<Mat>::new();
+    parts.push(picture);
+    parts.push(label);
+    let mut cell = Mat::default();
+    vconcat(&parts, &mut cell)?;
+    Ok(cell)
+}
+
+/// Shrinks or enlarges the image to fit the box, keeping its shape.
+fn fit(image: &Mat, width: i32, height: i32) -> Result<Mat> {
+    let size = image.size()?;
+    let scale = (width as f64 / size.width as f64).min(height as f64 / size.height as f64);
+    let target = Size::new(
+        ((size.width as f64 * scale).round() as i32).clamp(1, width),
+        ((size.height as f64 * scale).round() as i32).clamp(1, height));
+    let mut fitted = Mat::default();
+    resize(image, &mut fitted, target, 0.0, 0.0, INTER_AREA)?;
+    Ok(fitted)
+}
+
+fn to_bgr(image: &Mat) -> Result<Mat> {
+    let code = match image.channels() {
+        1 => COLOR_GRAY2BGR,
+        4 => COLOR_BGRA2BGR,
+        _ => return Ok(image.try_clone()?)
+    };
+    let mut converted = Mat::default();
+    cvt_color(image, &mut converted, code, 0)?;
+    Ok(converted)
+}
+
+/// Cuts the end off text too wide for the tile.
+fn fit_text(text: &str, width: i32) -> Result<String> {
+    let mut text = text.to_string();
+    let mut baseline = 0;
+    while !text.is_empty() && get_text_size(&text, FONT_HERSHEY_SIMPLEX, FONT_SCALE, 1, &mut baseline)?.width > width {
+        text.pop();
+    }
+    Ok(text)
+}
+
+fn label_height() -> Result<i32> {
+    let mut baseline = 0;
+    let size = get_text_size("Xg", FONT_HERSHEY_SIMPLEX, FONT_SCALE, 1, &mut baseline)?;
+    Ok(size.height + baseline + 2 * PADDING)
+}
+
+fn cell_height(options: &SheetOptions) -> Result<i32> {
+    Ok(options.tile_height as i32 + 2 * PADDING + label_height()?)
+}
+
+fn blank(width: i32, height: i32) -> Result<Mat> {
+    Ok(Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(BACKGROUND))?)
+}
@@ -0,0 +1,51 @@
+//! Reads frames for animations from image files.
+use std::{
+    fs::read_dir,
+    path::{
+        Path,
+        PathBuf
+    }
+};
+use opencv::{
+    imgcodecs::{
+        imread,
+        IMREAD_COLOR
+    },
+    prelude::MatTraitConst,
+    core::Mat
+};
+use super::{
+    AnimationError,
+    Result
+};
+
+const IMAGE_EXTENSIONS: [&str; 7] = ["jpg", "jpeg", "png", "webp", "tif", "tiff", "bmp"];
+
+/// The images in a directory in name order, which is capture order for the
+/// names given by time-lapse and burst captures.
+pub fn image_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let io_error = |source| AnimationError::Io { path: dir.to_path_buf(), source };
+    let mut files = Vec::<PathBuf>::new();
+    for entry in read_dir(dir).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        let is_image = path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext.as_str()));
+        if is_image {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Reads each image as it is needed, so long sequences don't have to fit in memory.
+pub fn read_images(paths: &[PathBuf]) -> impl Iterator<Item = Result<Mat>> + '_ {
+    paths.iter().map(|path| {
+        let image = imread(&path.to_string_lossy(), IMREAD_COLOR)?;
+        if image.rows() == 0 {
+            return Err(AnimationError::ImageNotRead(path.clone()));
+        }
+        Ok(image)
+    })
+}
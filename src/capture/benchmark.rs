@@ -0,0 +1,196 @@
+//! Measures what each mode of a device actually delivers.
+//!
+//! Devices advertise modes they can't sustain, particularly over USB 2 or on
+//! slow boards, and the frame rate OpenCV reports is only what was asked for.
+use std::time::{
+    Duration,
+    Instant
+};
+use opencv::{
+    videoio::{
+        CAP_PROP_FOURCC,
+        CAP_PROP_FPS,
+        CAP_PROP_POS_MSEC
+    },
+    prelude::MatTraitConst
+};
+use crate::video_devices::Device;
+use super::{
+    open_capture,
+    read_frame,
+    record::count_dropped,
+    Result,
+    TimestampSource
+};
+
+pub struct BenchmarkOptions {
+    /// How long to stream each mode for, after the first frame.
+    pub duration: Duration,
+    pub first_frame_timeout: Duration,
+    /// Give up on a mode if it stops delivering for this long.
+    pub read_timeout: Duration
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        BenchmarkOptions {
+            duration: Duration::from_secs(5),
+            first_frame_timeout: Duration::from_secs(10),
+            read_timeout: Duration::from_secs(2)
+        }
+    }
+}
+
+pub struct ModeReport {
+    /// The frame size asked for.
+    pub width: u32,
+    pub height: u32,
+    /// The frame size delivered, which can differ when the backend substitutes another.
+    pub actual_width: u32,
+    pub actual_height: u32,
+    /// As returned for `CAP_PROP_FOURCC`.
+    pub fourcc: i32,
+    /// What the backend claims, 0 where it won't say.
+    pub nominal_fps: f64,
+    /// From starting to open the device to having the first frame.
+    pub time_to_first_frame: Option<Duration>,
+    pub frames: u64,
+    pub measured_fps: f64,
+    pub mean_interval: Duration,
+    /// The standard deviation of the intervals between frames.
+    pub jitter: Duration,
+    pub max_interval: Duration,
+    /// Estimated from gaps between frames longer than the nominal frame rate allows.
+    pub dropped_frames: u64,
+    pub timestamp_source: TimestampSource,
+    /// Why the mode couldn't be measured, if it couldn't.
+    pub error: Option<String>
+}
+
+impl ModeReport {
+    fn new(width: u32, height: u32) -> Self {
+        ModeReport {
+            width,
+            height,
+            actual_width: 0,
+            actual_height: 0,
+            fourcc: 0,
+            nominal_fps: 0.0,
+            time_to_first_frame: None,
+            frames: 0,
+            measured_fps: 0.0,
+            mean_interval: Duration::ZERO,
+            jitter: Duration::ZERO,
+            max_interval: Duration::ZERO,
+            dropped_frames: 0,
+            timestamp_source: TimestampSource::ReadTime,
+            error: None
+        }
+    }
+
+    /// Whether the mode delivered at least 95% of the frame rate it claims.
+    pub fn keeps_up(&self) -> bool {
+        self.error.is_none() && self.nominal_fps > 0.0 && self.measured_fps >= self.nominal_fps * 0.95
+    }
+}
+
+pub struct BenchmarkReport {
+    pub device_name: String,
+    pub modes: Vec<ModeReport>
+}
+
+/// Measures each of the device's modes in turn, passing each report to `on_mode`
+/// as it is done. A mode that fails is reported with its error rather than
+/// stopping the benchmark.
+pub fn benchmark_device<F>(device: &Device, options: &BenchmarkOptions, mut on_mode: F) -> BenchmarkReport where
+    F: FnMut(&ModeReport)
+{
+    let mut modes = Vec::new();
+    for (width, height) in device.modes() {
+        let report = benchmark_mode(device, width, height, options).unwrap_or_else(|err| {
+            let mut report = ModeReport::new(width, height);
+            report.error = Some(err.to_string());
+            report
+        });
+        on_mode(&report);
+        modes.push(report);
+    }
+    BenchmarkReport {
+        device_name: device.name.clone(),
+        modes
+    }
+}
+
+pub fn benchmark_mode(device: &Device, width: u32, height: u32, options: &BenchmarkOptions) -> Result<ModeReport> {
+    let mut report = ModeReport::new(width, height);
+    let start = Instant::now();
+    let mut vc = open_capture(device, width, height)?;
+    let Some(first) = read_frame(&mut vc, options.first_frame_timeout)? else {
+        report.error = Some(String::from("no frames"));
+        return Ok(report);
+    };
+    report.time_to_first_frame = Some(start.elapsed());
+    let size = first.size()?;
+    report.actual_width = size.width as u32;
+    report.actual_height = size.height as u32;
+    report.fourcc = vc.get(CAP_PROP_FOURCC)? as i32;
+    report.nominal_fps = vc.get(CAP_PROP_FPS)?;
+
+    let mut positions = vec![vc.get(CAP_PROP_POS_MSEC)?];
+    let mut read_times = vec![Instant::now()];
+    let streaming_started = Instant::now();
+    while streaming_started.elapsed() < options.duration {
+        if read_frame(&mut vc, options.read_timeout)?.is_none() {
+            report.error = Some(String::from("stopped delivering frames"));
+            break;
+        }
+        positions.push(vc.get(CAP_PROP_POS_MSEC)?);
+        read_times.push(Instant::now());
+    }
+    vc.release()?;
+
+    // Backends without timestamps report 0, or the same value for every frame.
+    let intervals: Vec<Duration> = if positions[0] > 0.0 && positions.windows(2).all(|pair| pair[1] > pair[0]) {
+        report.timestamp_source = TimestampSource::OpenCv;
+        positions.windows(2)
+            .map(|pair| Duration::from_secs_f64((pair[1] - pair[0]) / 1000.0))
+            .collect()
+    } else {
+        read_times.windows(2)
+            .map(|pair| pair[1].duration_since(pair[0]))
+            .collect()
+    };
+    report.frames = read_times.len() as u64;
+    if intervals.is_empty() {
+        return Ok(report);
+    }
+
+    let total: Duration = intervals.iter().sum();
+    report.mean_interval = total / intervals.len() as u32;
+    report.measured_fps = intervals.len() as f64 / total.as_secs_f64();
+    report.max_interval = intervals.iter().max().copied().unwrap_or_default();
+    let mean = report.mean_interval.as_secs_f64();
+    let variance = intervals.iter()
+        .map(|interval| (interval.as_secs_f64() - mean).powi(2))
+        .sum::<f64>() / intervals.len() as f64;
+    report.jitter = Duration::from_secs_f64(variance.sqrt());
+
+    // Without a nominal rate the typical interval is the best guess at what was intended.
+    let expected_interval = if report.nominal_fps > 0.0 {
+        Duration::from_secs_f64(1.0 / report.nominal_fps)
+    } else {
+        median(&intervals)
+    };
+    if !expected_interval.is_zero() {
+        report.dropped_frames = intervals.iter()
+            .map(|&gap| count_dropped(gap, expected_interval))
+            .sum();
+    }
+    Ok(report)
+}
+
+fn median(intervals: &[Duration]) -> Duration {
+    let mut sorted = intervals.to_vec();
+    sorted.sort();
+    sorted[sorted.len() / 2]
+}
@@ -0,0 +1,266 @@
+//! Grabs a run of consecutive frames as fast as the mode allows.
+//!
+//! On Linux the frames are read straight from the driver where it can stream,
+//! so each comes with the kernel's timestamp of when it was captured. Otherwise
+//! they are read through OpenCV and timed with `CAP_PROP_POS_MSEC`, or by when
+//! they were read for backends that leave that at 0.
+use std::time::{
+    Duration,
+    Instant
+};
+use opencv::{
+    imgproc::{
+        cvt_color,
+        laplacian,
+        COLOR_BGR2GRAY
+    },
+    videoio::{
+        CAP_PROP_FOURCC,
+        CAP_PROP_POS_MSEC
+    },
+    prelude::MatTraitConst,
+    core::{
+        mean_std_dev,
+        Mat,
+        Vector,
+        BORDER_DEFAULT,
+        CV_64F
+    }
+};
+#[cfg(target_os = "linux")]
+use crate::video_devices::{
+    V4l2Device,
+    VideoDeviceError
+};
+use crate::video_devices::Device;
+use super::{
+    open_capture,
+    read_frame,
+    Camera,
+    CaptureError,
+    Result
+};
+
+pub struct BurstOptions {
+    pub width: u32,
+    pub height: u32,
+    pub count: u32,
+    /// Frames thrown away before the burst while exposure settles.
+    pub discard: u32,
+    /// How long to wait for each frame.
+    pub timeout: Duration,
+    /// Read from the driver rather than through OpenCV where the platform allows it.
+    pub native: bool,
+    /// When reading from the driver, have it capture into Mats in BGR, so
+    /// frames are neither copied nor converted. Frames in other formats are
+    /// converted as they come, which may drop some.
+    pub into_mats: bool
+}
+
+impl Default for BurstOptions {
+    fn default() -> Self {
+        BurstOptions {
+            width: 640,
+            height: 480,
+            count: 10,
+            discard: 5,
+            timeout: Duration::from_secs(2),
+            native: true,
+            into_mats: false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimestampSource {
+    /// When the driver captured the frame.
+    Kernel,
+    /// OpenCV's `CAP_PROP_POS_MSEC`.
+    OpenCv,
+    /// When the frame was read, as the backend gave no timestamp.
+    ReadTime
+}
+
+pub struct BurstFrame {
+    pub image: Mat,
+    /// Since the first frame of the burst.
+    pub timestamp: Duration,
+    /// The driver's frame count where known, so gaps show dropped frames.
+    pub sequence: Option<u32>
+}
+
+pub struct Burst {
+    pub frames: Vec<BurstFrame>,
+    pub timestamp_source: TimestampSource,
+    pub width: u32,
+    pub height: u32,
+    /// As returned for `CAP_PROP_FOURCC`.
+    pub fourcc: i32
+}
+
+impl Burst {
+    /// The time between each frame and the one before it.
+    pub fn intervals(&self) -> Vec<Duration> {
+        self.frames.windows(2)
+            .map(|pair| pair[1].timestamp.saturating_sub(pair[0].timestamp))
+            .collect()
+    }
+
+    /// The index of the frame with the most detail, going by the variance of its
+    /// Laplacian, which drops when the camera or the subject moved.
+    pub fn sharpest(&self) -> Result<Option<usize>> {
+        let mut sharpness_by_frame = Vec::with_capacity(self.frames.len());
+        for frame in &self.frames {
+            sharpness_by_frame.push(sharpness(&frame.image)?);
+        }
+        Ok(sharpness_by_frame.iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i))
+    }
+}
+
+/// Opens the device and grabs a burst from it, reading from the driver where
+/// possible and falling back to OpenCV when the device can't stream.
+pub fn capture_burst(device: &Device, options: &BurstOptions) -> Result<Burst> {
+    #[cfg(target_os = "linux")]
+    if options.native && device.source.is_none() {
+        match V4l2Device::open(device).map_err(CaptureError::from).and_then(|mut stream| burst_v4l2(&mut stream, options)) {
+            Err(CaptureError::VideoDevice(
+                VideoDeviceError::StreamingUnsupported(_) | VideoDeviceError::NoSupportedFormat(_))) => {},
+            result => return result
+        }
+    }
+    let mut vc = open_capture(device, options.width, options.height)?;
+    burst(&mut vc, options)
+}
+
+/// Grabs a burst from a device opened with OpenCV, at whatever size it was opened with.
+pub fn burst(vc: &mut Camera, options: &BurstOptions) -> Result<Burst> {
+    for _ in 0..options.discard {
+        if read_frame(vc, options.timeout)?.is_none() {
+            break;
+        }
+    }
+
+    let mut images = Vec::<Mat>::new();
+    let mut positions = Vec::<f64>::new();
+    let mut read_times = Vec::<Instant>::new();
+    while images.len() < options.count as usize {
+        let Some(image) = read_frame(vc, options.timeout)? else {
+            break;
+        };
+        read_times.push(Instant::now());
+        positions.push(vc.get(CAP_PROP_POS_MSEC)?);
+        images.push(image);
+    }
+    let Some(first) = images.first() else {
+        return Err(CaptureError::NoFrames(vc.backend_name()?));
+    };
+    let size = first.size()?;
+    let fourcc = vc.get(CAP_PROP_FOURCC)? as i32;
+
+    // Backends without timestamps report 0, or the same value for every frame.
+    let has_positions = positions[0] > 0.0 && positions.windows(2).all(|pair| pair[1] > pair[0]);
+    let (timestamp_source, timestamps): (_, Vec<Duration>) = if has_positions {
+        (TimestampSource::OpenCv, positions.iter()
+            .map(|ms| Duration::from_secs_f64((ms - positions[0]) / 1000.0))
+            .collect())
+    } else {
+        (TimestampSource::ReadTime, read_times.iter()
+            .map(|t| t.duration_since(read_times[0]))
+            .collect())
+    };
+
+    Ok(Burst {
+        frames: images.into_iter()
+            .zip(timestamps)
+            .map(|(image, timestamp)| BurstFrame { image, timestamp, sequence: None })
+            .collect(),
+        timestamp_source,
+        width: size.width as u32,
+        height: size.height as u32,
+        fourcc
+    })
+}
+
+/// Grabs a burst straight from the driver. Frames are kept as delivered and only
+/// converted once the burst is over, so conversion can't slow the capture.
+/// Frames captured into Mats are images already.
+#[cfg(target_os = "linux")]
+pub fn burst_v4l2(stream: &mut V4l2Device, options: &BurstOptions) -> Result<Burst> {
+    let format = if options.into_mats {
+        stream.set_native_format(options.width, options.height)?
+    } else {
+        stream.set_format(options.width, options.height)?
+    };
+    // Drivers keep whatever rate was last set, which needn't be the fastest.
+    // Those without one to set carry on at their own.
+    if let Some(&fastest) = stream.frame_rates()?.first() {
+        let _ = stream.set_frame_rate(fastest);
+    }
+    // Enough buffers for the whole burst, within reason, so the driver doesn't
+    // drop frames while they are copied out.
+    let buffer_count = (options.count + 1).clamp(4, 32);
+    if options.into_mats {
+        stream.start_streaming_into_mats(buffer_count, Vec::new())?;
+    } else {
+        stream.start_streaming(buffer_count)?;
+    }
+    for _ in 0..options.discard {
+        if stream.read_frame(options.timeout)?.is_none() {
+            break;
+        }
+    }
+
+    let mut raw_frames = Vec::new();
+    let mut frames = Vec::new();
+    while raw_frames.len() + frames.len() < options.count as usize {
+        let read = if options.into_mats {
+            stream.read_image(options.timeout)?.map(|frame| frames.push(BurstFrame {
+                image: frame.image,
+                timestamp: frame.timestamp,
+                sequence: Some(frame.sequence)
+            }))
+        } else {
+            stream.read_frame(options.timeout)?.map(|frame| raw_frames.push(frame))
+        };
+        if read.is_none() {
+            break;
+        }
+    }
+    stream.stop_streaming()?;
+
+    for raw in &raw_frames {
+        frames.push(BurstFrame {
+            image: raw.to_mat()?,
+            timestamp: raw.timestamp,
+            sequence: Some(raw.sequence)
+        });
+    }
+    let Some(start) = frames.first().map(|frame| frame.timestamp) else {
+        return Err(CaptureError::NoFrames(stream.path().display().to_string()));
+    };
+    for frame in &mut frames {
+        frame.timestamp = frame.timestamp.saturating_sub(start);
+    }
+    Ok(Burst {
+        frames,
+        timestamp_source: TimestampSource::Kernel,
+        width: format.width,
+        height: format.height,
+        fourcc: format.pixel_format as i32
+    })
+}
+
+fn sharpness(image: &Mat) -> Result<f64> {
+    let mut grey = Mat::default();
+    cvt_color(image, &mut grey, COLOR_BGR2GRAY, 0)?;
+    let mut edges = Mat::default();
+    laplacian(&grey, &mut edges, CV_64F, 1, 1.0, 0.0, BORDER_DEFAULT)?;
+    let mut mean = Vector::<f64>::new();
+    let mut std_dev = Vector::<f64>::new();
+    mean_std_dev(&edges, &mut mean, &mut std_dev, &Mat::default())?;
+    let std_dev = std_dev.get(0)?;
+    Ok(std_dev * std_dev)
+}
@@ -0,0 +1,66 @@
+//! A device opened for capture, read through OpenCV or generated here.
+use opencv::{
+    videoio::VideoCapture,
+    prelude::{
+        VideoCaptureTrait,
+        VideoCaptureTraitConst
+    },
+    core::Mat
+};
+use super::{
+    test_pattern::TestPattern,
+    Result
+};
+
+/// The calls mirror `VideoCapture`'s, so code reading from a camera doesn't
+/// need to know which it is.
+pub enum Camera {
+    OpenCv(VideoCapture),
+    TestPattern(TestPattern)
+}
+
+impl Camera {
+    /// Grabs and decodes the next frame, returning false if there wasn't one.
+    pub fn read(&mut self, frame: &mut Mat) -> Result<bool> {
+        Ok(self.grab()? && self.retrieve(frame)?)
+    }
+
+    /// Takes hold of the next frame without decoding it.
+    pub fn grab(&mut self) -> Result<bool> {
+        match self {
+            Camera::OpenCv(vc) => Ok(vc.grab()?),
+            Camera::TestPattern(pattern) => Ok(pattern.grab())
+        }
+    }
+
+    /// Decodes the frame last grabbed.
+    pub fn retrieve(&mut self, frame: &mut Mat) -> Result<bool> {
+        match self {
+            Camera::OpenCv(vc) => Ok(vc.retrieve(frame, 0)?),
+            Camera::TestPattern(pattern) => pattern.retrieve(frame)
+        }
+    }
+
+    /// A `CAP_PROP_*` property, 0 where it isn't known.
+    pub fn get(&self, property: i32) -> Result<f64> {
+        match self {
+            Camera::OpenCv(vc) => Ok(vc.get(property)?),
+            Camera::TestPattern(pattern) => Ok(pattern.get(property))
+        }
+    }
+
+    /// Which OpenCV backend is reading the camera, for messages.
+    pub fn backend_name(&self) -> Result<String> {
+        match self {
+            Camera::OpenCv(vc) => Ok(vc.get_backend_name()?),
+            Camera::TestPattern(_) => Ok(String::from("test pattern"))
+        }
+    }
+
+    pub fn release(&mut self) -> Result<()> {
+        if let Camera::OpenCv(vc) = self {
+            vc.release()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,113 @@
+//! Crops frames to a region of interest, by having the device crop its sensor
+//! where it can, and by cutting the region out of each frame where it can't.
+use opencv::{
+    prelude::MatTraitConst,
+    core::{
+        Mat,
+        Rect
+    }
+};
+#[cfg(target_os = "linux")]
+use crate::video_devices::V4l2Device;
+use crate::video_devices::Device;
+use super::{
+    CaptureError,
+    Result
+};
+
+pub struct Cropper {
+    region: Rect,
+    /// The area of the sensor the frames show, which the region is measured
+    /// within, if the device has crop bounds.
+    frame_area: Option<Rect>,
+    /// The device is cropping, so frames come already cropped.
+    by_device: bool,
+    /// The crop to put back on the device when done.
+    #[cfg(target_os = "linux")]
+    restore: Option<(Device, Rect)>
+}
+
+impl Cropper {
+    /// Asks the device to crop to the region, which is in the sensor's pixels
+    /// for devices that give their crop bounds, and the frame's otherwise.
+    /// Call it before opening the device, as most drivers won't change the
+    /// crop once it's open for capture. Frames from devices that can't crop
+    /// to the region are cropped here instead.
+    pub fn new(device: &Device, region: Rect) -> Cropper {
+        let cropper = Cropper {
+            region,
+            // Frames show the default crop, unless the device says otherwise.
+            frame_area: device.crop_bounds.as_ref().map(|crop| crop.default),
+            by_device: false,
+            #[cfg(target_os = "linux")]
+            restore: None
+        };
+        #[cfg(target_os = "linux")]
+        let cropper = if device.source.is_none() && device.crop_bounds.is_some() {
+            cropper.crop_by_device(device)
+        } else {
+            cropper
+        };
+        cropper
+    }
+
+    #[cfg(target_os = "linux")]
+    fn crop_by_device(mut self, device: &Device) -> Cropper {
+        let Ok(mut v4l2) = V4l2Device::open(device) else {
+            return self;
+        };
+        let Ok(previous) = v4l2.crop() else {
+            return self;
+        };
+        self.frame_area = Some(previous);
+        if previous == self.region {
+            self.by_device = true;
+        } else if v4l2.set_crop(self.region).is_ok_and(|crop| crop == self.region) {
+            self.by_device = true;
+            self.restore = Some((device.clone(), previous));
+        } else {
+            // The driver may have rounded it, which would be cropped again here.
+            let _ = v4l2.set_crop(previous);
+        }
+        self
+    }
+
+    /// The region of the frame, cut out and made continuous.
+    pub fn crop(&self, frame: Mat) -> Result<Mat> {
+        if self.by_device {
+            return Ok(frame);
+        }
+        let size = frame.size()?;
+        let region = match self.frame_area {
+            // Scaled from the sensor to the frame, as devices scale the area they crop to the format.
+            Some(area) if area.width > 0 && area.height > 0 => {
+                let scale_x = size.width as f64 / area.width as f64;
+                let scale_y = size.height as f64 / area.height as f64;
+                Rect::new(
+                    ((self.region.x - area.x) as f64 * scale_x).round() as i32,
+                    ((self.region.y - area.y) as f64 * scale_y).round() as i32,
+                    (self.region.width as f64 * scale_x).round() as i32,
+                    (self.region.height as f64 * scale_y).round() as i32)
+            },
+            _ => self.region
+        };
+        if region.x < 0 || region.y < 0 || region.width <= 0 || region.height <= 0 ||
+           region.x + region.width > size.width || region.y + region.height > size.height {
+            return Err(CaptureError::CropOutsideFrame(self.region, size.width, size.height));
+        }
+        Ok(Mat::roi(&frame, region)?.try_clone()?)
+    }
+}
+
+/// The device keeps its crop after being closed, so the crop from before is
+/// put back for whatever uses it next.
+impl Drop for Cropper {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some((device, previous)) = self.restore.take() {
+            if let Ok(mut v4l2) = V4l2Device::open(&device) {
+                let _ = v4l2.set_crop(previous);
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! The errors returned while capturing and saving frames.
+use std::{
+    io,
+    path::PathBuf
+};
+use thiserror::Error;
+use opencv::core::Rect;
+use crate::video_devices::VideoDeviceError;
+use super::FrameProblem;
+
+pub type Result<T> = std::result::Result<T, CaptureError>;
+
+#[derive(Debug, Error)]
+pub enum CaptureError {
+    #[error("{0}")]
+    OpenCv(#[from] opencv::Error),
+
+    #[error("{0}")]
+    VideoDevice(#[from] VideoDeviceError),
+
+    #[error("{path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: io::Error
+    },
+
+    #[error("{0}")]
+    Regex(#[from] regex::Error),
+
+    /// The filename template contains a `{placeholder}` we don't know how to fill.
+    #[error("unknown placeholder {{{0}}} in filename template")]
+    UnknownPlaceholder(String),
+
+    /// An image format given as text wasn't recognised.
+    #[error("unknown image format {0:?}")]
+    UnknownImageFormat(String),
+
+    /// OpenCV declined to write the image, usually because it lacks the codec.
+    #[error("failed to write image {0}")]
+    ImageNotWritten(PathBuf),
+
+    /// OpenCV failed writing the image, such as for a full disk, so it's kept
+    /// apart from the errors reading frames from the device.
+    #[error("failed to write image {path}: {source}")]
+    ImageWriteFailed {
+        path: PathBuf,
+        source: opencv::Error
+    },
+
+    /// OpenCV couldn't open the device or file, which it reports without saying why.
+    #[error("failed to open {0}")]
+    NotOpened(String),
+
+    /// The device opened but never delivered a frame.
+    #[error("no frames from {0}")]
+    NoFrames(String),
+
+    /// Only devices read through OpenCV's V4L backend can be waited on together.
+    #[error("{0} can't be waited on with the others")]
+    WaitAnyUnsupported(String),
+
+    /// The device kept delivering frames with nothing useful in them.
+    #[error("{0}")]
+    BadFrame(FrameProblem),
+
+    /// Codecs are given by a four character code, such as MJPG.
+    #[error("invalid codec {0:?}, expected four characters")]
+    InvalidCodec(String),
+
+    /// The crop region doesn't fit in the frames the device delivers.
+    #[error("crop {}x{} at {},{} is outside the {1}x{2} frame", .0.width, .0.height, .0.x, .0.y)]
+    CropOutsideFrame(Rect, i32, i32)
+}
@@ -0,0 +1,226 @@
+//! Spots frames that were delivered but show nothing useful.
+//!
+//! Cameras regularly return black or green frames, or the same buffer over
+//! and over, and `VideoCapture::read` still succeeds.
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::Hasher
+};
+use opencv::{
+    prelude::MatTraitConst,
+    core::{
+        mean_std_dev,
+        Mat,
+        Rect,
+        Vector
+    }
+};
+use super::Result;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthOptions {
+    /// Frames whose every channel averages no more than this (0 to 255) are black.
+    /// 0 accepts any frame that isn't completely black.
+    pub dark_level: f64,
+    /// Frames whose every channel varies less than this are a solid colour.
+    pub min_std_dev: f64,
+    /// Whether a frame identical to the one before it counts as a problem.
+    pub detect_frozen: bool
+}
+
+impl Default for HealthOptions {
+    fn default() -> Self {
+        HealthOptions {
+            dark_level: 4.0,
+            min_std_dev: 2.0,
+            detect_frozen: true
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameProblem {
+    Empty,
+    Black,
+    /// Such as the green frames of an unconfigured colour converter,
+    /// with the colour as BGR.
+    Solid { colour: [u8; 3] },
+    /// The bottom of the frame is flat grey, which is how OpenCV decodes
+    /// a JPEG that was cut short.
+    Truncated,
+    /// Exactly the same as the frame before, which sensor noise never allows.
+    Frozen
+}
+
+impl FrameProblem {
+    /// A one word name, for reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrameProblem::Empty => "empty",
+            FrameProblem::Black => "black",
+            FrameProblem::Solid { .. } => "solid",
+            FrameProblem::Truncated => "truncated",
+            FrameProblem::Frozen => "frozen"
+        }
+    }
+}
+
+impl fmt::Display for FrameProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameProblem::Empty => write!(f, "empty frame"),
+            FrameProblem::Black => write!(f, "black frame"),
+            FrameProblem::Solid { colour: [b, g, r] } => write!(f, "solid colour frame (BGR {}, {}, {})", b, g, r),
+            FrameProblem::Truncated => write!(f, "corrupt frame, the bottom is missing"),
+            FrameProblem::Frozen => write!(f, "frozen frame, the same as the one before")
+        }
+    }
+}
+
+/// Checks a stream of frames, remembering the last to spot repeats.
+pub struct HealthChecker {
+    options: HealthOptions,
+    last_hash: Option<u64>
+}
+
+impl HealthChecker {
+    pub fn new(options: HealthOptions) -> Self {
+        HealthChecker { options, last_hash: None }
+    }
+
+    /// Looks for problems with the frame, returning the first found.
+    pub fn check(&mut self, frame: &Mat) -> Result<Option<FrameProblem>> {
+        if frame.empty() {
+            return Ok(Some(FrameProblem::Empty));
+        }
+        // Hashed before anything else so a bad frame still counts as the last one.
+        let hash = hash_frame(frame)?;
+        let repeated = self.last_hash == Some(hash);
+        self.last_hash = Some(hash);
+
+        let (means, std_devs) = channel_stats(frame)?;
+        if means.iter().all(|&mean| mean <= self.options.dark_level) {
+            return Ok(Some(FrameProblem::Black));
+        }
+        if std_devs.iter().all(|&std_dev| std_dev < self.options.min_std_dev) {
+            let colour = |c: usize| means.get(c).or(means.first()).map_or(0, |&mean| mean.round() as u8);
+            return Ok(Some(FrameProblem::Solid { colour: [colour(0), colour(1), colour(2)] }));
+        }
+        if is_truncated(frame)? {
+            return Ok(Some(FrameProblem::Truncated));
+        }
+        if repeated && self.options.detect_frozen {
+            return Ok(Some(FrameProblem::Frozen));
+        }
+        Ok(None)
+    }
+}
+
+/// The mean and standard deviation of each channel.
+fn channel_stats(frame: &Mat) -> Result<(Vec<f64>, Vec<f64>)> {
+    let mut means = Vector::<f64>::new();
+    let mut std_devs = Vector::<f64>::new();
+    mean_std_dev(frame, &mut means, &mut std_devs, &Mat::default())?;
+    Ok((means.to_vec(), std_devs.to_vec()))
+}
+
+/// Whether the bottom eighth of the frame is the flat mid grey that libjpeg
+/// fills in for data it never received. OpenCV decodes the JPEG before we see
+/// it, so this guess is the only check for corrupt frames it reads. Frames read
+/// through v4l2 directly have their markers checked before decoding instead.
+fn is_truncated(frame: &Mat) -> Result<bool> {
+    let size = frame.size()?;
+    let strip_height = size.height / 8;
+    if strip_height == 0 {
+        return Ok(false);
+    }
+    let strip = Mat::roi(frame, Rect::new(0, size.height - strip_height, size.width, strip_height))?;
+    let (means, std_devs) = channel_stats(&strip)?;
+    Ok(std_devs.iter().all(|&std_dev| std_dev < 1.0) &&
+        means.iter().all(|&mean| (mean - 128.0).abs() < 2.0))
+}
+
+fn hash_frame(frame: &Mat) -> Result<u64> {
+    // Rows of a region aren't contiguous, but a clone's are.
+    let continuous;
+    let frame = if frame.is_continuous() {
+        frame
+    } else {
+        continuous = frame.try_clone()?;
+        &continuous
+    };
+    let mut hasher = DefaultHasher::new();
+    hasher.write(frame.data_bytes()?);
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::{
+        prelude::MatTrait,
+        core::{
+            Scalar,
+            CV_8UC3
+        }
+    };
+    use super::*;
+
+    const WIDTH: i32 = 64;
+    const HEIGHT: i32 = 48;
+
+    fn solid(b: f64, g: f64, r: f64) -> Mat {
+        Mat::new_rows_cols_with_default(HEIGHT, WIDTH, CV_8UC3, Scalar::new(b, g, r, 0.0)).unwrap()
+    }
+
+    /// Noise that no check objects to, different for each seed.
+    fn picture(seed: u32) -> Mat {
+        let mut frame = solid(0.0, 0.0, 0.0);
+        let mut state = seed.wrapping_mul(2654435761).max(1);
+        for byte in frame.data_bytes_mut().unwrap() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            *byte = (state >> 24) as u8;
+        }
+        frame
+    }
+
+    fn check(frames: &[Mat]) -> Vec<Option<FrameProblem>> {
+        let mut checker = HealthChecker::new(HealthOptions::default());
+        frames.iter().map(|frame| checker.check(frame).unwrap()).collect()
+    }
+
+    #[test]
+    fn pictures_are_fine() {
+        assert_eq!(check(&[picture(1), picture(2)]), [None, None]);
+    }
+
+    #[test]
+    fn empty_and_black_frames_are_found() {
+        assert_eq!(check(&[Mat::default(), solid(3.0, 4.0, 2.0)]), [Some(FrameProblem::Empty), Some(FrameProblem::Black)]);
+    }
+
+    #[test]
+    fn solid_frames_give_their_colour() {
+        assert_eq!(check(&[solid(0.0, 135.0, 0.0)]), [Some(FrameProblem::Solid { colour: [0, 135, 0] })]);
+    }
+
+    #[test]
+    fn grey_at_the_bottom_is_truncated() {
+        let mut frame = picture(1);
+        let grey_from = ((HEIGHT - HEIGHT / 8) * WIDTH * 3) as usize;
+        frame.data_bytes_mut().unwrap()[grey_from..].fill(128);
+        assert_eq!(check(&[frame]), [Some(FrameProblem::Truncated)]);
+    }
+
+    #[test]
+    fn repeated_frames_are_frozen_unless_allowed() {
+        let frames = [picture(1), picture(1), picture(2), picture(1)];
+        assert_eq!(check(&frames), [None, Some(FrameProblem::Frozen), None, None]);
+
+        let mut checker = HealthChecker::new(HealthOptions { detect_frozen: false, ..HealthOptions::default() });
+        assert_eq!(checker.check(&frames[0]).unwrap(), None);
+        assert_eq!(checker.check(&frames[1]).unwrap(), None);
+    }
+}
@@ -0,0 +1,92 @@
+mod error;
+pub use error::{
+    CaptureError,
+    Result
+};
+
+mod output;
+pub use output::{
+    create_output_dir,
+    fourcc_to_string,
+    to_path,
+    FrameInfo,
+    OutputOptions
+};
+
+mod camera;
+pub use camera::Camera;
+
+mod test_pattern;
+
+mod open;
+pub use open::{
+    delivered_size,
+    open_capture,
+    open_capture_mode,
+    StillMode
+};
+
+mod health;
+pub use health::{
+    FrameProblem,
+    HealthChecker,
+    HealthOptions
+};
+
+mod crop;
+pub use crop::Cropper;
+
+mod policy;
+pub use policy::{
+    capture_still,
+    read_frame,
+    CapturePolicy,
+    Combine,
+    WarmUp
+};
+
+mod snapshot;
+pub use snapshot::take_snap;
+
+mod record;
+pub use record::{
+    record,
+    RecordLimit,
+    RecordOptions
+};
+
+mod timelapse;
+pub use timelapse::{
+    run_timelapse,
+    TimelapseEvent,
+    TimelapseOptions
+};
+
+mod burst;
+pub use burst::{
+    capture_burst,
+    BurstOptions,
+    TimestampSource
+};
+
+mod multi;
+pub use multi::{
+    MultiCapture,
+    SyncStrategy
+};
+
+mod benchmark;
+pub use benchmark::{
+    benchmark_device,
+    benchmark_mode,
+    BenchmarkOptions,
+    ModeReport
+};
+
+mod probe;
+pub use probe::{
+    probe_device,
+    DeviceProbe,
+    ProbeOptions,
+    ProbeResult
+};
@@ -0,0 +1,219 @@
+//! Captures from several devices together, for rigs where the frames need to
+//! show the same moment.
+//!
+//! `grab` only takes hold of the latest frame, while `retrieve` does the slow
+//! work of decoding it, so grabbing from every device before retrieving
+//! anything keeps the frames much closer together than reading each in turn.
+use std::time::{
+    Duration,
+    Instant
+};
+use opencv::{
+    videoio::{
+        VideoCapture,
+        CAP_PROP_FOURCC,
+        CAP_PROP_POS_MSEC
+    },
+    core::{
+        Mat,
+        Vector
+    }
+};
+use crate::video_devices::Device;
+use super::{
+    open_capture,
+    Camera,
+    CaptureError,
+    Result,
+    TimestampSource
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyncStrategy {
+    /// Grab from each device in turn, then retrieve from each.
+    RoundRobin,
+    /// Wait on all the devices at once with `VideoCapture::wait_any`, taking
+    /// a frame from each as soon as it has one. Only the V4L backend supports it,
+    /// so it can't be used with test patterns.
+    WaitAny
+}
+
+pub struct MultiCapture {
+    names: Vec<String>,
+    cameras: Vec<Camera>,
+    strategy: SyncStrategy
+}
+
+pub struct SyncedFrame {
+    pub image: Mat,
+    /// As returned for `CAP_PROP_FOURCC`.
+    pub fourcc: i32,
+    /// How long after the earliest frame of the set this one was captured.
+    pub offset: Duration
+}
+
+pub struct FrameSet {
+    /// One for each device, in the order they were given. `None` where a device
+    /// didn't deliver a frame in time.
+    pub frames: Vec<Option<SyncedFrame>>,
+    pub timestamp_source: TimestampSource
+}
+
+impl FrameSet {
+    /// The time between the earliest and latest frames of the set.
+    pub fn skew(&self) -> Duration {
+        self.frames.iter()
+            .flatten()
+            .map(|frame| frame.offset)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// When a frame was grabbed, by OpenCV's timestamp and by the clock.
+struct Grabbed {
+    position: f64,
+    at: Instant
+}
+
+impl MultiCapture {
+    /// Opens all the devices at the same frame size.
+    pub fn open(devices: &[Device], width: u32, height: u32, strategy: SyncStrategy) -> Result<Self> {
+        let mut cameras = Vec::<Camera>::new();
+        for device in devices {
+            cameras.push(open_capture(device, width, height)?);
+        }
+        Ok(MultiCapture {
+            names: devices.iter().map(|d| d.name.clone()).collect(),
+            cameras,
+            strategy
+        })
+    }
+
+    /// Grabs and throws away frames from every device while exposure settles.
+    pub fn discard(&mut self, frames: u32) -> Result<()> {
+        for _ in 0..frames {
+            for camera in &mut self.cameras {
+                camera.grab()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Captures a frame from each device, waiting up to `timeout` for them.
+    pub fn capture(&mut self, timeout: Duration) -> Result<FrameSet> {
+        let grabbed = match self.strategy {
+            SyncStrategy::RoundRobin => self.grab_round_robin()?,
+            SyncStrategy::WaitAny => self.grab_wait_any(timeout)?
+        };
+
+        let mut images = Vec::with_capacity(grabbed.len());
+        for (i, grab) in grabbed.iter().enumerate() {
+            let camera = &mut self.cameras[i];
+            let mut image = Mat::default();
+            let retrieved = grab.is_some() && camera.retrieve(&mut image)?;
+            images.push(if retrieved { Some((image, camera.get(CAP_PROP_FOURCC)? as i32)) } else { None });
+        }
+        if images.iter().all(Option::is_none) {
+            return Err(CaptureError::NoFrames(self.names.join(", ")));
+        }
+
+        // The V4L backend gives every device's frames the kernel's timestamp, which
+        // can be compared between devices. Others give 0, so fall back to the clock.
+        let times: Vec<&Grabbed> = grabbed.iter().flatten().collect();
+        let timestamp_source = if times.iter().all(|t| t.position > 0.0) {
+            TimestampSource::OpenCv
+        } else {
+            TimestampSource::ReadTime
+        };
+        let offset = |grab: &Grabbed| match timestamp_source {
+            TimestampSource::OpenCv => {
+                let earliest = times.iter().map(|t| t.position).fold(f64::INFINITY, f64::min);
+                Duration::from_secs_f64((grab.position - earliest) / 1000.0)
+            },
+            _ => {
+                let earliest = times.iter().map(|t| t.at).min().unwrap_or(grab.at);
+                grab.at.duration_since(earliest)
+            }
+        };
+
+        Ok(FrameSet {
+            frames: images.into_iter()
+                .zip(&grabbed)
+                .map(|(image, grab)| match (image, grab) {
+                    (Some((image, fourcc)), Some(grab)) => Some(SyncedFrame { image, fourcc, offset: offset(grab) }),
+                    _ => None
+                })
+                .collect(),
+            timestamp_source
+        })
+    }
+
+    fn grab_round_robin(&mut self) -> Result<Vec<Option<Grabbed>>> {
+        let mut grabbed = Vec::with_capacity(self.cameras.len());
+        for camera in &mut self.cameras {
+            grabbed.push(if camera.grab()? {
+                Some(Grabbed { position: camera.get(CAP_PROP_POS_MSEC)?, at: Instant::now() })
+            } else {
+                None
+            });
+        }
+        Ok(grabbed)
+    }
+
+    fn grab_wait_any(&mut self, timeout: Duration) -> Result<Vec<Option<Grabbed>>> {
+        // Taking some of the captures and then failing on another would close the ones
+        // already taken, so every camera is checked first.
+        if let Some(i) = self.cameras.iter().position(|camera| !matches!(camera, Camera::OpenCv(_))) {
+            return Err(CaptureError::WaitAnyUnsupported(self.names[i].clone()));
+        }
+        let mut grabbed: Vec<Option<Grabbed>> = (0..self.cameras.len()).map(|_| None).collect();
+        let deadline = Instant::now() + timeout;
+        let mut ready = Vector::<i32>::new();
+        loop {
+            // Waiting on a device again would grab over the frame it already gave.
+            let pending: Vec<usize> = (0..grabbed.len()).filter(|&i| grabbed[i].is_none()).collect();
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if pending.is_empty() || remaining.is_zero() {
+                break;
+            }
+            let empty = pending.iter()
+                .map(|_| VideoCapture::default())
+                .collect::<opencv::Result<Vec<_>>>()?;
+            let mut waiting = Vector::<VideoCapture>::new();
+            for (&i, empty) in pending.iter().zip(empty) {
+                waiting.push(self.take_video_capture(i, empty));
+            }
+            let waited = VideoCapture::wait_any(&waiting, &mut ready, remaining.as_nanos() as i64);
+            for (&i, vc) in pending.iter().zip(waiting) {
+                self.cameras[i] = Camera::OpenCv(vc);
+            }
+            if !waited? {
+                break;
+            }
+            let at = Instant::now();
+            for index in &ready {
+                let i = pending[index as usize];
+                let position = self.cameras[i].get(CAP_PROP_POS_MSEC)?;
+                grabbed[i] = Some(Grabbed { position, at });
+            }
+        }
+        Ok(grabbed)
+    }
+
+    /// Moves the camera's `VideoCapture` out to be waited on, leaving an empty one behind.
+    /// Every camera has been checked to be an OpenCV one by then.
+    fn take_video_capture(&mut self, i: usize, empty: VideoCapture) -> VideoCapture {
+        match std::mem::replace(&mut self.cameras[i], Camera::OpenCv(empty)) {
+            Camera::OpenCv(vc) => vc,
+            Camera::TestPattern(_) => unreachable!("checked before waiting")
+        }
+    }
+
+    pub fn release(&mut self) -> Result<()> {
+        for camera in &mut self.cameras {
+            camera.release()?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+//! Opens devices with OpenCV, or as test patterns.
+use opencv::{
+    videoio::{
+        VideoCapture,
+        CAP_ANY,
+        CAP_PROP_FOURCC,
+        CAP_PROP_FPS,
+        CAP_PROP_FRAME_WIDTH,
+        CAP_PROP_FRAME_HEIGHT
+    },
+    prelude::{
+        MatTraitConst,
+        VideoCaptureTraitConst
+    },
+    core::{
+        Mat,
+        Vector
+    }
+};
+#[cfg(target_os = "linux")]
+use crate::video_devices::V4l2Device;
+use crate::video_devices::{
+    Device,
+    TEST_PATTERN_SCHEME
+};
+use super::{
+    test_pattern::TestPattern,
+    Camera,
+    CaptureError,
+    Result
+};
+
+/// What test patterns are opened at when not asked for anything else.
+const DEFAULT_TEST_PATTERN_MODE: (u32, u32, f64) = (640, 480, 30.0);
+
+/// Opens the device asking for the frame size. The backend may choose a different
+/// size if the device can't provide it, so check the frames that come back.
+/// Ask for 0x0 to take whatever the device gives.
+pub fn open_capture(device: &Device, width: u32, height: u32) -> Result<Camera> {
+    open_capture_mode(device, width, height, None, None)
+}
+
+/// As `open_capture`, also asking for a pixel format and frame rate where given.
+/// These are requests too, and backends quietly ignore what they can't do. On
+/// Linux a frame rate given is also set on the driver directly.
+/// Network cameras, files and image sequences come as they are, and are opened
+/// without asking for anything. Test patterns give whatever size and rate are asked.
+pub fn open_capture_mode(device: &Device, width: u32, height: u32, fourcc: Option<i32>, fps: Option<f64>) -> Result<Camera> {
+    if device.source.as_deref().is_some_and(|source| source.starts_with(TEST_PATTERN_SCHEME)) {
+        let (default_width, default_height, default_fps) = DEFAULT_TEST_PATTERN_MODE;
+        let (width, height) = if width > 0 && height > 0 { (width, height) } else { (default_width, default_height) };
+        let fps = fps.filter(|&fps| fps > 0.0).unwrap_or(default_fps);
+        return Ok(Camera::TestPattern(TestPattern::new(width, height, fps)));
+    }
+    if let Some(source) = &device.source {
+        // The FFmpeg backend refuses to open at all when given properties it doesn't support.
+        return opened(VideoCapture::from_file(source, CAP_ANY)?, device);
+    }
+
+    // Many drivers ignore the rate OpenCV asks for, or OpenCV doesn't ask at all.
+    // Failing that, the device is still opened, as the rate is only a request.
+    #[cfg(target_os = "linux")]
+    if let Some(fps) = fps {
+        let _ = apply_frame_rate(device, width, height, fourcc, fps);
+    }
+
+    let mut params = Vector::<i32>::new();
+    if width > 0 && height > 0 {
+        params.push(CAP_PROP_FRAME_WIDTH);
+        params.push(width as i32);
+        params.push(CAP_PROP_FRAME_HEIGHT);
+        params.push(height as i32);
+    }
+    if let Some(fourcc) = fourcc {
+        params.push(CAP_PROP_FOURCC);
+        params.push(fourcc);
+    }
+    if let Some(fps) = fps {
+        // Parameters are integers, so fractional rates such as 29.97 are rounded.
+        params.push(CAP_PROP_FPS);
+        params.push(fps.round() as i32);
+    }
+    opened(VideoCapture::new_with_params(device.opencv_id, CAP_ANY, &params)?, device)
+}
+
+/// Sets the rate asked for on the driver. It's done before OpenCV opens the
+/// device, as UVC refuses parameters from another handle once OpenCV has set
+/// the format. The mode is set first, as drivers round the rate to what the
+/// mode set can do. UVC goes back to its default rate whenever the format is
+/// set, which is why the rate is also passed on to OpenCV, to set again through
+/// its own handle.
+#[cfg(target_os = "linux")]
+fn apply_frame_rate(device: &Device, width: u32, height: u32, fourcc: Option<i32>, fps: f64) -> Result<()> {
+    let mut v4l2 = V4l2Device::open(device)?;
+    match v4l2.stream_parameters() {
+        Ok(parameters) if parameters.can_set_frame_rate => (),
+        Ok(_) => return Ok(()),
+        // Drivers without streaming parameters can't set a rate either.
+        Err(err) if err.is_unsupported() => return Ok(()),
+        Err(err) => return Err(err.into())
+    }
+    if width > 0 && height > 0 {
+        match fourcc {
+            Some(fourcc) => v4l2.set_pixel_format(width, height, fourcc as u32)?,
+            None => v4l2.set_format(width, height)?
+        };
+    }
+    v4l2.set_frame_rate(fps)?;
+    Ok(())
+}
+
+/// Keeps the driver's high quality mode for stills on until dropped, for
+/// devices that have one. Others, and other platforms, carry on as they are.
+pub struct StillMode {
+    /// The device the mode was turned on for, to turn it off again.
+    #[cfg(target_os = "linux")]
+    device: Option<Device>
+}
+
+impl StillMode {
+    /// Turns the mode on, which has to be done before the device is opened,
+    /// as some drivers refuse parameters from another handle once it has been.
+    #[cfg_attr(not(target_os = "linux"), allow(unused_variables))]
+    pub fn new(device: &Device) -> Result<StillMode> {
+        Ok(StillMode {
+            #[cfg(target_os = "linux")]
+            device: set_high_quality(device, true)?.then(|| device.clone())
+        })
+    }
+}
+
+impl Drop for StillMode {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(device) = self.device.take() {
+            let _ = set_high_quality(&device, false);
+        }
+    }
+}
+
+/// Turns the mode on or off, returning whether it took.
+#[cfg(target_os = "linux")]
+fn set_high_quality(device: &Device, on: bool) -> Result<bool> {
+    if device.source.is_some() {
+        return Ok(false);
+    }
+    match V4l2Device::open(device)?.set_high_quality(on) {
+        Ok(parameters) => Ok(parameters.high_quality == on),
+        // Drivers without streaming parameters don't have the mode either.
+        Err(err) if err.is_unsupported() => Ok(false),
+        Err(err) => Err(err.into())
+    }
+}
+
+/// The size asked for, or for sources asked for 0x0, the size of the frame they gave.
+pub fn delivered_size(frame: &Mat, width: u32, height: u32) -> Result<(u32, u32)> {
+    if width > 0 && height > 0 {
+        return Ok((width, height));
+    }
+    let size = frame.size()?;
+    Ok((size.width as u32, size.height as u32))
+}
+
+fn opened(vc: VideoCapture, device: &Device) -> Result<Camera> {
+    if !vc.is_opened()? {
+        return Err(CaptureError::NotOpened(device.name.clone()));
+    }
+    Ok(Camera::OpenCv(vc))
+}
@@ -0,0 +1,197 @@
+//! Where and how captured frames are saved.
+use std::{
+    fs::create_dir_all,
+    path::{
+        Path,
+        PathBuf
+    },
+    str::FromStr
+};
+use chrono::{
+    DateTime,
+    Local
+};
+use opencv::{
+    imgcodecs::{
+        imwrite,
+        ImwriteFlags
+    },
+    core::{
+        Mat,
+        Vector
+    }
+};
+use regex::{
+    Captures,
+    Regex
+};
+use crate::video_devices::Device;
+use super::{
+    CaptureError,
+    Result
+};
+
+/// Gives the same names as earlier versions, which always wrote JPEGs.
+pub const DEFAULT_TEMPLATE: &str = "img-{name}-{mode}";
+
+/// The quality earlier versions wrote their JPEGs at, for `jpeg` without one too.
+const DEFAULT_JPEG_QUALITY: u8 = 100;
+
+const TIMESTAMP_FORMAT: &str = "%Y%m%dT%H%M%S%.3f";
+
+pub struct OutputOptions {
+    /// Created if it doesn't already exist.
+    pub directory: PathBuf,
+    /// Filename without the extension, which comes from the format.
+    /// These placeholders are replaced:
+    /// `{id}` device unique id, `{name}` device name, `{mode}` frame size as WxH,
+    /// `{fourcc}` pixel format, `{timestamp}` local time of capture
+    /// and `{seq}` the sequence number.
+    pub template: String,
+    pub format: ImageFormat
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            directory: PathBuf::from("."),
+            template: String::from(DEFAULT_TEMPLATE),
+            format: ImageFormat::Jpeg { quality: DEFAULT_JPEG_QUALITY }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    /// Quality 0 to 100.
+    Jpeg { quality: u8 },
+    /// Lossless, compression 0 (fastest) to 9 (smallest).
+    Png { compression: u8 },
+    /// Quality 1 to 100, above 100 is lossless.
+    WebP { quality: u8 },
+    Tiff
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg { .. } => "jpg",
+            ImageFormat::Png { .. } => "png",
+            ImageFormat::WebP { .. } => "webp",
+            ImageFormat::Tiff => "tiff"
+        }
+    }
+
+    /// The parameters for `imwrite` and `imencode`.
+    pub fn imwrite_params(&self) -> Vector<i32> {
+        let mut params = Vector::<i32>::new();
+        match self {
+            ImageFormat::Jpeg { quality } => {
+                params.push(ImwriteFlags::IMWRITE_JPEG_QUALITY as i32);
+                params.push(*quality as i32);
+            },
+            ImageFormat::Png { compression } => {
+                params.push(ImwriteFlags::IMWRITE_PNG_COMPRESSION as i32);
+                params.push(*compression as i32);
+            },
+            ImageFormat::WebP { quality } => {
+                params.push(ImwriteFlags::IMWRITE_WEBP_QUALITY as i32);
+                params.push(*quality as i32);
+            },
+            ImageFormat::Tiff => {}
+        };
+        params
+    }
+}
+
+/// Parses `jpeg`, `png`, `webp` or `tiff`, optionally followed by a colon and
+/// the quality or compression level, such as `jpeg:90` or `png:9`.
+impl FromStr for ImageFormat {
+    type Err = CaptureError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let unknown = || CaptureError::UnknownImageFormat(s.to_string());
+        let (name, level) = match s.split_once(':') {
+            Some((name, level)) => (name, Some(level.parse::<u8>().map_err(|_| unknown())?)),
+            None => (s, None)
+        };
+        match name.to_ascii_lowercase().as_str() {
+            "jpeg" | "jpg" => Ok(ImageFormat::Jpeg { quality: level.unwrap_or(DEFAULT_JPEG_QUALITY).min(100) }),
+            "png" => Ok(ImageFormat::Png { compression: level.unwrap_or(3).min(9) }),
+            "webp" => Ok(ImageFormat::WebP { quality: level.unwrap_or(101) }),
+            "tiff" | "tif" if level.is_none() => Ok(ImageFormat::Tiff),
+            _ => Err(unknown())
+        }
+    }
+}
+
+/// What is known about a captured frame when choosing its filename.
+pub struct FrameInfo<'a> {
+    pub device: &'a Device,
+    pub width: u32,
+    pub height: u32,
+    /// As returned for `CAP_PROP_FOURCC`.
+    pub fourcc: i32,
+    pub sequence: u32,
+    pub timestamp: DateTime<Local>
+}
+
+impl OutputOptions {
+    pub fn to_path(&self, info: &FrameInfo) -> Result<PathBuf> {
+        to_path(&self.directory, &self.template, self.format.extension(), info)
+    }
+
+    /// Writes the frame to the file named for it, returning the path.
+    pub fn save(&self, frame: &Mat, info: &FrameInfo) -> Result<PathBuf> {
+        create_output_dir(&self.directory)?;
+        let path = self.to_path(info)?;
+        let filename = path.to_string_lossy();
+        let written = imwrite(&filename, frame, &self.format.imwrite_params())
+            .map_err(|source| CaptureError::ImageWriteFailed { path: path.clone(), source })?;
+        if !written {
+            return Err(CaptureError::ImageNotWritten(path));
+        }
+        Ok(path)
+    }
+}
+
+/// Fills in the placeholders of a filename template (see `OutputOptions::template`)
+/// and adds the directory and extension.
+pub fn to_path(directory: &Path, template: &str, extension: &str, info: &FrameInfo) -> Result<PathBuf> {
+    let placeholder_re = Regex::new(r"\{([a-z]*)\}")?;
+    let cleaning_re = Regex::new(r"[^a-zA-Z0-9_.-]")?;
+
+    let mut unknown: Option<String> = None;
+    let filename = placeholder_re.replace_all(template, |caps: &Captures| {
+        let value = match &caps[1] {
+            "id" => info.device.unique_id.clone(),
+            "name" => info.device.name.clone(),
+            "mode" => format!("{}x{}", info.width, info.height),
+            "fourcc" => fourcc_to_string(info.fourcc),
+            "timestamp" => info.timestamp.format(TIMESTAMP_FORMAT).to_string(),
+            "seq" => format!("{:06}", info.sequence),
+            other => {
+                unknown.get_or_insert(other.to_string());
+                String::new()
+            }
+        };
+        cleaning_re.replace_all(value.as_str(), "").into_owned()
+    });
+    if let Some(placeholder) = unknown {
+        return Err(CaptureError::UnknownPlaceholder(placeholder));
+    }
+    Ok(directory.join(format!("{}.{}", filename, extension)))
+}
+
+pub fn create_output_dir(directory: &Path) -> Result<()> {
+    create_dir_all(directory)
+        .map_err(|source| CaptureError::Io { path: directory.to_path_buf(), source })
+}
+
+/// Turns the integer OpenCV uses for a pixel format back into its four characters.
+pub fn fourcc_to_string(fourcc: i32) -> String {
+    fourcc.to_le_bytes()
+        .iter()
+        .map(|&b| if b.is_ascii_graphic() { b as char } else { '_' })
+        .collect()
+}
@@ -0,0 +1,258 @@
+//! Chooses which frames from a freshly opened device make up a still.
+//!
+//! Many webcams deliver dark or green frames until auto-exposure and
+//! white balance have settled, so the first frame is rarely the one wanted.
+use std::{
+    thread::sleep,
+    time::{
+        Duration,
+        Instant
+    }
+};
+use opencv::{
+    videoio::CAP_PROP_EXPOSURE,
+    prelude::{
+        MatTrait,
+        MatTraitConst
+    },
+    core::{
+        add,
+        mean,
+        Mat,
+        Rect,
+        CV_32F
+    }
+};
+use super::{
+    Camera,
+    CaptureError,
+    FrameProblem,
+    HealthChecker,
+    HealthOptions,
+    Result
+};
+
+pub struct CapturePolicy {
+    pub warm_up: WarmUp,
+    pub combine: Combine,
+    /// How long to wait for the device to deliver its first frame.
+    pub first_frame_timeout: Duration,
+    /// How long to wait for the picture to settle before taking it anyway.
+    pub warm_up_timeout: Duration,
+    /// What counts as a bad frame, or `None` to take frames as they come.
+    pub health: Option<HealthOptions>,
+    /// How many more frames to read when the one chosen is bad, before giving up.
+    pub bad_frame_retries: u32,
+    /// The region to keep, in the sensor's pixels where the device gives its
+    /// crop bounds, or `None` for the whole frame.
+    pub crop: Option<Rect>,
+    /// Ask the driver for its high quality mode for stills where it has one.
+    pub high_quality: bool
+}
+
+impl Default for CapturePolicy {
+    fn default() -> Self {
+        CapturePolicy {
+            warm_up: WarmUp::DiscardFrames(5),
+            combine: Combine::Single,
+            first_frame_timeout: Duration::from_secs(10),
+            warm_up_timeout: Duration::from_secs(5),
+            health: Some(HealthOptions::default()),
+            bad_frame_retries: 10,
+            crop: None,
+            high_quality: false
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarmUp {
+    /// Use the first frame.
+    None,
+    /// Throw away this many frames before using one.
+    DiscardFrames(u32),
+    /// Wait until the mean brightness (0 to 255) of consecutive frames
+    /// changes by no more than `tolerance` for `stable_frames` frames.
+    StableBrightness { tolerance: f64, stable_frames: u32 },
+    /// As `StableBrightness`, but watching the exposure the driver reports.
+    /// Only useful where the backend supports `CAP_PROP_EXPOSURE`.
+    StableExposure { tolerance: f64, stable_frames: u32 }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Combine {
+    /// Use one frame as it is.
+    Single,
+    /// Average this many frames, which reduces sensor noise.
+    Average(u32),
+    /// Take the per-pixel median of this many 8 bit frames, which also removes
+    /// anything that only appears in a minority of them.
+    Median(u32)
+}
+
+/// Delay between attempts to read when the device has no frame ready.
+const READ_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Reads frames from an open device according to the policy, returning `None`
+/// if the device didn't deliver a frame within the first frame timeout.
+/// A frame that is still bad after the retries is a `CaptureError::BadFrame`.
+pub fn capture_still(vc: &mut Camera, policy: &CapturePolicy) -> Result<Option<Mat>> {
+    let mut checker = policy.health.clone().map(HealthChecker::new);
+    let mut frame = match read_frame(vc, policy.first_frame_timeout)? {
+        Some(frame) => frame,
+        None => return Ok(None)
+    };
+    // Every frame is checked so that the last is known when looking for repeats.
+    let mut problem = check(&mut checker, &frame)?;
+
+    let warm_up_deadline = Instant::now() + policy.warm_up_timeout;
+    let mut discarded = 0;
+    let mut stable = 0;
+    let mut last_level = level(vc, &frame, &policy.warm_up)?;
+    while !is_warmed_up(&policy.warm_up, discarded, stable) && Instant::now() < warm_up_deadline {
+        frame = match read_frame(vc, warm_up_deadline.saturating_duration_since(Instant::now()))? {
+            Some(frame) => frame,
+            None => break
+        };
+        discarded += 1;
+        problem = check(&mut checker, &frame)?;
+        let level = level(vc, &frame, &policy.warm_up)?;
+        if (level - last_level).abs() <= tolerance(&policy.warm_up) {
+            stable += 1;
+        } else {
+            stable = 0;
+        }
+        last_level = level;
+    }
+
+    let mut retries = 0;
+    while let Some(found) = problem {
+        if retries >= policy.bad_frame_retries {
+            return Err(CaptureError::BadFrame(found));
+        }
+        retries += 1;
+        frame = match read_frame(vc, policy.first_frame_timeout)? {
+            Some(frame) => frame,
+            None => return Err(CaptureError::BadFrame(found))
+        };
+        problem = check(&mut checker, &frame)?;
+    }
+
+    match policy.combine {
+        Combine::Single => Ok(Some(frame)),
+        Combine::Average(count) => {
+            let frames = read_more_frames(vc, frame, count, policy.first_frame_timeout)?;
+            Ok(Some(average(&frames)?))
+        },
+        Combine::Median(count) => {
+            let frames = read_more_frames(vc, frame, count, policy.first_frame_timeout)?;
+            Ok(Some(median(&frames)?))
+        }
+    }
+}
+
+/// Keeps trying to read a frame until the timeout passes.
+pub fn read_frame(vc: &mut Camera, timeout: Duration) -> Result<Option<Mat>> {
+    let deadline = Instant::now() + timeout;
+    let mut frame = Mat::default();
+    loop {
+        if vc.read(&mut frame)? && frame.rows() > 0 {
+            return Ok(Some(frame));
+        }
+        if Instant::now() + READ_RETRY_DELAY > deadline {
+            return Ok(None);
+        }
+        sleep(READ_RETRY_DELAY);
+    }
+}
+
+fn check(checker: &mut Option<HealthChecker>, frame: &Mat) -> Result<Option<FrameProblem>> {
+    match checker {
+        Some(checker) => checker.check(frame),
+        None => Ok(None)
+    }
+}
+
+fn is_warmed_up(warm_up: &WarmUp, discarded: u32, stable: u32) -> bool {
+    match *warm_up {
+        WarmUp::None => true,
+        WarmUp::DiscardFrames(count) => discarded >= count,
+        WarmUp::StableBrightness { stable_frames, .. } |
+        WarmUp::StableExposure { stable_frames, .. } => stable >= stable_frames
+    }
+}
+
+fn tolerance(warm_up: &WarmUp) -> f64 {
+    match *warm_up {
+        WarmUp::StableBrightness { tolerance, .. } |
+        WarmUp::StableExposure { tolerance, .. } => tolerance,
+        _ => 0.0
+    }
+}
+
+/// The value watched for settling, or zero when the policy doesn't watch one.
+fn level(vc: &Camera, frame: &Mat, warm_up: &WarmUp) -> Result<f64> {
+    match warm_up {
+        WarmUp::StableBrightness { .. } => brightness(frame),
+        WarmUp::StableExposure { .. } => Ok(vc.get(CAP_PROP_EXPOSURE)?),
+        _ => Ok(0.0)
+    }
+}
+
+/// Mean over all pixels and channels, 0 to 255 for 8 bit images.
+pub fn brightness(frame: &Mat) -> Result<f64> {
+    let channel_means = mean(frame, &Mat::default())?;
+    let channels = frame.channels().clamp(1, 4) as usize;
+    Ok((0..channels).map(|c| channel_means[c]).sum::<f64>() / channels as f64)
+}
+
+/// Adds frames to the first until there are `count`, stopping early if the device stops delivering.
+/// Frames that differ in size or type from the first are skipped.
+fn read_more_frames(vc: &mut Camera, first: Mat, count: u32, timeout: Duration) -> Result<Vec<Mat>> {
+    let first_size = first.size()?;
+    let first_typ = first.typ();
+    let mut frames = vec![first];
+    while frames.len() < count as usize {
+        match read_frame(vc, timeout)? {
+            Some(frame) if frame.size()? == first_size && frame.typ() == first_typ => frames.push(frame),
+            Some(_) => {},
+            None => break
+        }
+    }
+    Ok(frames)
+}
+
+fn average(frames: &[Mat]) -> Result<Mat> {
+    let mut sum = Mat::default();
+    frames[0].convert_to(&mut sum, CV_32F, 1.0, 0.0)?;
+    for frame in &frames[1..] {
+        let mut frame_f32 = Mat::default();
+        frame.convert_to(&mut frame_f32, CV_32F, 1.0, 0.0)?;
+        let mut total = Mat::default();
+        add(&sum, &frame_f32, &mut total, &Mat::default(), -1)?;
+        sum = total;
+    }
+    let mut averaged = Mat::default();
+    sum.convert_to(&mut averaged, frames[0].typ(), 1.0 / frames.len() as f64, 0.0)?;
+    Ok(averaged)
+}
+
+fn median(frames: &[Mat]) -> Result<Mat> {
+    // Clones are continuous, so the bytes of each line up.
+    let mut result = frames[0].try_clone()?;
+    let copies = frames.iter()
+        .map(|frame| frame.try_clone())
+        .collect::<opencv::Result<Vec<Mat>>>()?;
+    let sources = copies.iter()
+        .map(|frame| frame.data_bytes())
+        .collect::<opencv::Result<Vec<&[u8]>>>()?;
+
+    let mut values = Vec::<u8>::with_capacity(sources.len());
+    for (i, out) in result.data_bytes_mut()?.iter_mut().enumerate() {
+        values.clear();
+        values.extend(sources.iter().map(|bytes| bytes[i]));
+        values.sort_unstable();
+        *out = values[values.len() / 2];
+    }
+    Ok(result)
+}
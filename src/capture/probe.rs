@@ -0,0 +1,300 @@
+//! Tries every mode a device advertises and records what really happens, to
+//! build up a picture of what each model of camera can actually do.
+use std::{
+    path::PathBuf,
+    time::{
+        Duration,
+        Instant
+    }
+};
+use chrono::Local;
+use opencv::{
+    videoio::{
+        CAP_PROP_FOURCC,
+        CAP_PROP_FPS,
+        CAP_PROP_FRAME_WIDTH,
+        CAP_PROP_FRAME_HEIGHT
+    },
+    imgproc::{
+        resize,
+        INTER_AREA
+    },
+    prelude::MatTraitConst,
+    core::{
+        Mat,
+        Size
+    }
+};
+use crate::{
+    json::{
+        array,
+        JsonObject
+    },
+    video_devices::{
+        Device,
+        FrameSizeType
+    }
+};
+use super::{
+    fourcc_to_string,
+    open_capture_mode,
+    read_frame,
+    Camera,
+    FrameInfo,
+    FrameProblem,
+    HealthChecker,
+    HealthOptions,
+    OutputOptions,
+    Result
+};
+
+pub struct ProbeOptions {
+    pub first_frame_timeout: Duration,
+    /// How long to keep reading after the first frame to measure the frame rate.
+    pub duration: Duration,
+    /// Where to save the first good frame of each mode, if anywhere.
+    pub output: Option<OutputOptions>,
+    /// Keep the first frame of each mode, good or not, scaled down to this width,
+    /// such as for a contact sheet.
+    pub thumbnail_width: Option<u32>
+}
+
+impl Default for ProbeOptions {
+    fn default() -> Self {
+        ProbeOptions {
+            first_frame_timeout: Duration::from_secs(10),
+            duration: Duration::from_secs(2),
+            output: None,
+            thumbnail_width: None
+        }
+    }
+}
+
+/// A combination of settings the device advertises.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProbeMode {
+    pub width: u32,
+    pub height: u32,
+    /// `None` where the device didn't list its pixel formats.
+    pub fourcc: Option<i32>,
+    /// `None` where the device didn't list its frame rates.
+    pub fps: Option<f64>
+}
+
+pub struct ProbeResult {
+    pub asked: ProbeMode,
+    pub opened: bool,
+    /// What the backend negotiated, from the frames themselves where there were any.
+    pub actual_width: u32,
+    pub actual_height: u32,
+    pub actual_fourcc: i32,
+    /// What the backend claims, 0 where it won't say.
+    pub actual_fps: f64,
+    /// From starting to open the device to having the first frame.
+    pub time_to_first_frame: Option<Duration>,
+    pub measured_fps: f64,
+    /// The first problem seen with the frames, if any.
+    pub problem: Option<FrameProblem>,
+    /// The first frame, if `ProbeOptions::thumbnail_width` asked for it.
+    pub thumbnail: Option<Mat>,
+    pub saved: Option<PathBuf>,
+    /// Why the mode couldn't be used, if it couldn't.
+    pub error: Option<String>
+}
+
+impl ProbeResult {
+    fn new(asked: ProbeMode) -> Self {
+        ProbeResult {
+            asked,
+            opened: false,
+            actual_width: 0,
+            actual_height: 0,
+            actual_fourcc: 0,
+            actual_fps: 0.0,
+            time_to_first_frame: None,
+            measured_fps: 0.0,
+            problem: None,
+            thumbnail: None,
+            saved: None,
+            error: None
+        }
+    }
+
+    /// Whether the mode delivered good frames at the size and format asked for.
+    pub fn works(&self) -> bool {
+        self.error.is_none() &&
+            self.time_to_first_frame.is_some() &&
+            self.problem.is_none() &&
+            (self.asked.width == 0 || self.actual_width == self.asked.width) &&
+            (self.asked.height == 0 || self.actual_height == self.asked.height) &&
+            self.asked.fourcc.is_none_or(|fourcc| fourcc == self.actual_fourcc)
+    }
+
+    /// "valid", the name of the problem with the frames, or `None` if there were none.
+    pub fn image_name(&self) -> Option<&'static str> {
+        self.time_to_first_frame?;
+        Some(self.problem.as_ref().map_or("valid", FrameProblem::name))
+    }
+
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .raw("asked", JsonObject::new()
+                .num("width", self.asked.width as f64)
+                .num("height", self.asked.height as f64)
+                .opt_str("fourcc", self.asked.fourcc.map(fourcc_to_string).as_deref())
+                .opt_num("fps", self.asked.fps)
+                .build())
+            .bool("opened", self.opened)
+            .raw("negotiated", JsonObject::new()
+                .num("width", self.actual_width as f64)
+                .num("height", self.actual_height as f64)
+                .str("fourcc", &fourcc_to_string(self.actual_fourcc))
+                .num("fps", self.actual_fps)
+                .build())
+            .opt_num("first_frame_seconds", self.time_to_first_frame.map(|t| t.as_secs_f64()))
+            .num("measured_fps", self.measured_fps)
+            .opt_str("image", self.image_name())
+            .bool("works", self.works())
+            .opt_str("saved", self.saved.as_ref().map(|path| path.to_string_lossy()).as_deref())
+            .opt_str("error", self.error.as_deref())
+            .build()
+    }
+}
+
+pub struct DeviceProbe {
+    pub name: String,
+    pub unique_id: String,
+    pub results: Vec<ProbeResult>
+}
+
+impl DeviceProbe {
+    pub fn to_json(&self) -> String {
+        JsonObject::new()
+            .str("name", &self.name)
+            .str("unique_id", &self.unique_id)
+            .raw("modes", array(self.results.iter().map(ProbeResult::to_json)))
+            .build()
+    }
+}
+
+/// Every combination of pixel format, frame size and frame rate the device
+/// advertises, taking the largest and smallest of stepwise sizes.
+pub fn probe_modes(device: &Device) -> Vec<ProbeMode> {
+    if device.formats.is_empty() {
+        return device.modes().into_iter()
+            .map(|(width, height)| ProbeMode { width, height, fourcc: None, fps: None })
+            .collect();
+    }
+    let mut modes = Vec::new();
+    for format in &device.formats {
+        let sizes = match format.frame_size_type {
+            FrameSizeType::Discrete { width, height } => vec![(width, height)],
+            FrameSizeType::Stepwise { max_width, max_height, min_width, min_height, .. } =>
+                vec![(max_width, max_height), (min_width, min_height)]
+        };
+        let frame_rates: Vec<Option<f64>> = if format.frame_rates.is_empty() {
+            vec![None]
+        } else {
+            format.frame_rates.iter().copied().map(Some).collect()
+        };
+        for (width, height) in sizes {
+            for &fps in &frame_rates {
+                modes.push(ProbeMode { width, height, fourcc: Some(format.fourcc), fps });
+            }
+        }
+    }
+    modes
+}
+
+/// Probes each of the device's modes in turn, passing each result to `on_result`
+/// as it is done. A mode that fails is recorded with its error rather than
+/// stopping the probe.
+pub fn probe_device<F>(device: &Device, options: &ProbeOptions, mut on_result: F) -> DeviceProbe where
+    F: FnMut(&ProbeResult)
+{
+    let mut results = Vec::new();
+    for (sequence, mode) in probe_modes(device).into_iter().enumerate() {
+        let mut result = ProbeResult::new(mode);
+        if let Err(err) = probe_mode(device, options, sequence as u32 + 1, &mut result) {
+            result.error = Some(err.to_string());
+        }
+        on_result(&result);
+        results.push(result);
+    }
+    DeviceProbe {
+        name: device.name.clone(),
+        unique_id: device.unique_id.clone(),
+        results
+    }
+}
+
+fn probe_mode(device: &Device, options: &ProbeOptions, sequence: u32, result: &mut ProbeResult) -> Result<()> {
+    let mode = result.asked;
+    let start = Instant::now();
+    let mut vc = open_capture_mode(device, mode.width, mode.height, mode.fourcc, mode.fps)?;
+    result.opened = true;
+    result.actual_width = vc.get(CAP_PROP_FRAME_WIDTH)? as u32;
+    result.actual_height = vc.get(CAP_PROP_FRAME_HEIGHT)? as u32;
+    result.actual_fourcc = vc.get(CAP_PROP_FOURCC)? as i32;
+    result.actual_fps = vc.get(CAP_PROP_FPS)?;
+
+    let Some(first) = read_frame(&mut vc, options.first_frame_timeout)? else {
+        result.error = Some(String::from("no frames"));
+        vc.release()?;
+        return Ok(());
+    };
+    result.time_to_first_frame = Some(start.elapsed());
+    let size = first.size()?;
+    result.actual_width = size.width as u32;
+    result.actual_height = size.height as u32;
+
+    let mut checker = HealthChecker::new(HealthOptions::default());
+    result.problem = checker.check(&first)?;
+    if let (None, Some(output)) = (&result.problem, &options.output) {
+        let info = FrameInfo {
+            device,
+            width: mode.width,
+            height: mode.height,
+            fourcc: result.actual_fourcc,
+            sequence,
+            timestamp: Local::now()
+        };
+        result.saved = Some(output.save(&first, &info)?);
+    }
+    if let Some(width) = options.thumbnail_width {
+        result.thumbnail = Some(thumbnail(&first, width)?);
+    }
+
+    result.measured_fps = measure_fps(&mut vc, options.duration, &mut checker, &mut result.problem)?;
+    vc.release()?;
+    Ok(())
+}
+
+/// Reads for the duration and counts the frames, giving up early if they stop.
+/// Any problem with the frames is kept unless one was already found.
+fn measure_fps(vc: &mut Camera, duration: Duration, checker: &mut HealthChecker, problem: &mut Option<FrameProblem>) -> Result<f64> {
+    let start = Instant::now();
+    let mut frames = 0;
+    while start.elapsed() < duration {
+        let Some(frame) = read_frame(vc, duration)? else {
+            break;
+        };
+        frames += 1;
+        if let Some(found) = checker.check(&frame)? {
+            problem.get_or_insert(found);
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    Ok(if elapsed > 0.0 { frames as f64 / elapsed } else { 0.0 })
+}
+
+fn thumbnail(frame: &Mat, width: u32) -> Result<Mat> {
+    let size = frame.size()?;
+    if size.width <= width as i32 {
+        return Ok(frame.try_clone()?);
+    }
+    let height = (size.height as f64 * width as f64 / size.width as f64).round().max(1.0);
+    let mut scaled = Mat::default();
+    resize(frame, &mut scaled, Size::new(width as i32, height as i32), 0.0, 0.0, INTER_AREA)?;
+    Ok(scaled)
+}
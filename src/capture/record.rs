@@ -0,0 +1,265 @@
+//! Records video from a device to files with OpenCV's `VideoWriter`.
+use std::{
+    path::PathBuf,
+    time::{
+        Duration,
+        Instant
+    }
+};
+use chrono::Local;
+use opencv::{
+    videoio::{
+        VideoWriter,
+        CAP_PROP_FOURCC,
+        CAP_PROP_FPS
+    },
+    prelude::{
+        MatTraitConst,
+        VideoWriterTrait,
+        VideoWriterTraitConst
+    },
+    core::{
+        Mat,
+        Size
+    }
+};
+use crate::video_devices::Device;
+use super::{
+    create_output_dir,
+    open_capture,
+    read_frame,
+    to_path,
+    CaptureError,
+    FrameInfo,
+    Result
+};
+
+pub const DEFAULT_VIDEO_TEMPLATE: &str = "vid-{name}-{mode}-{timestamp}";
+
+pub struct RecordOptions {
+    pub width: u32,
+    pub height: u32,
+    pub limit: RecordLimit,
+    /// Start a new file each time this much has been recorded.
+    pub segment: Option<Duration>,
+    /// The four character code of the codec, which the linked OpenCV must support.
+    pub codec: String,
+    /// Chooses the container, such as `avi` or `mp4`.
+    pub extension: String,
+    /// Created if it doesn't already exist.
+    pub directory: PathBuf,
+    /// As `OutputOptions::template`, with `{seq}` being the segment number.
+    pub template: String
+}
+
+impl Default for RecordOptions {
+    fn default() -> Self {
+        RecordOptions {
+            width: 640,
+            height: 480,
+            limit: RecordLimit::Duration(Duration::from_secs(10)),
+            segment: None,
+            codec: String::from("MJPG"),
+            extension: String::from("avi"),
+            directory: PathBuf::from("."),
+            template: String::from(DEFAULT_VIDEO_TEMPLATE)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordLimit {
+    Duration(Duration),
+    Frames(u64)
+}
+
+#[derive(Debug)]
+pub struct RecordingReport {
+    pub files: Vec<PathBuf>,
+    pub frames_written: u64,
+    /// Estimated from gaps between frames longer than the device's frame interval.
+    pub dropped_frames: u64,
+    /// The rate measured from the first frames, which the files are written with.
+    pub written_fps: f64,
+    /// The rate frames arrived at over the whole recording.
+    pub average_fps: f64,
+    /// What the device claimed it would deliver, zero if it didn't say.
+    pub nominal_fps: f64,
+    pub elapsed: Duration,
+    /// The device stopped delivering frames before the limit was reached.
+    pub stopped_early: bool
+}
+
+/// Frames read while measuring the frame rate, which are kept and written first.
+const FPS_MEASURE_FRAMES: usize = 15;
+const FPS_MEASURE_TIME: Duration = Duration::from_secs(2);
+/// Give up if the device delivers nothing for this long.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+/// Used if the device delivers too few frames to measure and won't say.
+const FALLBACK_FPS: f64 = 15.0;
+
+pub fn record(device: &Device, options: &RecordOptions) -> Result<RecordingReport> {
+    let codec = codec_fourcc(&options.codec)?;
+    create_output_dir(&options.directory)?;
+
+    let mut vc = open_capture(device, options.width, options.height)?;
+    let nominal_fps = vc.get(CAP_PROP_FPS)?;
+    let start = Instant::now();
+
+    // VideoWriter needs the rate up front, so time some frames first.
+    let mut measured = Vec::<(Mat, Instant)>::new();
+    while measured.len() < FPS_MEASURE_FRAMES && start.elapsed() < FPS_MEASURE_TIME {
+        match read_frame(&mut vc, READ_TIMEOUT)? {
+            Some(frame) => measured.push((frame, Instant::now())),
+            None => break
+        }
+    }
+    if measured.is_empty() {
+        return Err(CaptureError::NoFrames(device.name.clone()));
+    }
+    let fps = measure_fps(&measured)
+        .or((nominal_fps > 0.0).then_some(nominal_fps))
+        .unwrap_or(FALLBACK_FPS);
+    // Gaps are compared against what the device should manage, where it says.
+    let expected_interval = Duration::from_secs_f64(1.0 / if nominal_fps > 0.0 { nominal_fps } else { fps });
+
+    let mut recording = Recording {
+        device,
+        options,
+        codec,
+        fourcc: vc.get(CAP_PROP_FOURCC)? as i32,
+        fps,
+        writer: None,
+        segment_started: start,
+        files: Vec::new(),
+        frames_written: 0
+    };
+    let mut dropped_frames = 0;
+    let mut last_frame_at: Option<Instant> = None;
+    let mut stopped_early = false;
+    let mut pending = measured.into_iter();
+
+    while !is_limit_reached(&options.limit, recording.frames_written, start.elapsed()) {
+        let (frame, at) = match pending.next() {
+            Some(frame_at) => frame_at,
+            None => match read_frame(&mut vc, READ_TIMEOUT)? {
+                Some(frame) => (frame, Instant::now()),
+                None => {
+                    stopped_early = true;
+                    break;
+                }
+            }
+        };
+        if let Some(last) = last_frame_at {
+            dropped_frames += count_dropped(at - last, expected_interval);
+        }
+        last_frame_at = Some(at);
+        recording.write(&frame, at)?;
+    }
+    let elapsed = start.elapsed();
+    recording.finish()?;
+    vc.release()?;
+
+    Ok(RecordingReport {
+        written_fps: recording.fps,
+        average_fps: recording.frames_written as f64 / elapsed.as_secs_f64(),
+        files: recording.files,
+        frames_written: recording.frames_written,
+        dropped_frames,
+        nominal_fps,
+        elapsed,
+        stopped_early
+    })
+}
+
+/// Writes frames to a file, starting new files when a segment is complete.
+struct Recording<'a> {
+    device: &'a Device,
+    options: &'a RecordOptions,
+    codec: i32,
+    fourcc: i32,
+    fps: f64,
+    writer: Option<VideoWriter>,
+    segment_started: Instant,
+    files: Vec<PathBuf>,
+    frames_written: u64
+}
+
+impl Recording<'_> {
+    fn write(&mut self, frame: &Mat, at: Instant) -> Result<()> {
+        let segment_full = self.options.segment
+            .is_some_and(|segment| at.duration_since(self.segment_started) >= segment);
+        if segment_full {
+            self.finish()?;
+        }
+        if self.writer.is_none() {
+            self.writer = Some(self.start_segment(frame, at)?);
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write(frame)?;
+            self.frames_written += 1;
+        }
+        Ok(())
+    }
+
+    fn start_segment(&mut self, frame: &Mat, at: Instant) -> Result<VideoWriter> {
+        let size: Size = frame.size()?;
+        let info = FrameInfo {
+            device: self.device,
+            width: size.width as u32,
+            height: size.height as u32,
+            fourcc: self.fourcc,
+            sequence: self.files.len() as u32 + 1,
+            timestamp: Local::now()
+        };
+        let path = to_path(&self.options.directory, &self.options.template, &self.options.extension, &info)?;
+        let writer = VideoWriter::new(&path.to_string_lossy(), self.codec, self.fps, size, frame.channels() > 1)?;
+        if !writer.is_opened()? {
+            return Err(CaptureError::NotOpened(path.to_string_lossy().to_string()));
+        }
+        self.files.push(path);
+        self.segment_started = at;
+        Ok(writer)
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.release()?;
+        }
+        Ok(())
+    }
+}
+
+fn is_limit_reached(limit: &RecordLimit, frames_written: u64, elapsed: Duration) -> bool {
+    match *limit {
+        RecordLimit::Duration(duration) => elapsed >= duration,
+        RecordLimit::Frames(frames) => frames_written >= frames
+    }
+}
+
+/// Frames per second over the timed frames, `None` if there are too few to tell.
+fn measure_fps(frames: &[(Mat, Instant)]) -> Option<f64> {
+    let (_, first) = frames.first()?;
+    let (_, last) = frames.last()?;
+    let secs = last.duration_since(*first).as_secs_f64();
+    (frames.len() > 2 && secs > 0.0).then(|| (frames.len() - 1) as f64 / secs)
+}
+
+/// How many frames are likely to be missing from a gap between two frames.
+pub fn count_dropped(gap: Duration, expected_interval: Duration) -> u64 {
+    let intervals = gap.as_secs_f64() / expected_interval.as_secs_f64();
+    if intervals > 1.5 {
+        intervals.round() as u64 - 1
+    } else {
+        0
+    }
+}
+
+/// Turns a four character code such as `MJPG` into the integer OpenCV uses.
+pub fn codec_fourcc(codec: &str) -> Result<i32> {
+    let chars: Vec<char> = codec.chars().collect();
+    match chars[..] {
+        [c1, c2, c3, c4] => Ok(VideoWriter::fourcc(c1, c2, c3, c4)?),
+        _ => Err(CaptureError::InvalidCodec(codec.to_string()))
+    }
+}
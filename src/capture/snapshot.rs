@@ -0,0 +1,49 @@
+//! Captures single frames from a device and saves them.
+use std::path::PathBuf;
+use chrono::Local;
+use opencv::videoio::CAP_PROP_FOURCC;
+use crate::video_devices::Device;
+use super::{
+    capture_still,
+    delivered_size,
+    open_capture,
+    Camera,
+    CapturePolicy,
+    Cropper,
+    FrameInfo,
+    OutputOptions,
+    Result,
+    StillMode
+};
+
+/// Saves a frame from the device, returning where it was written,
+/// or `None` if the device didn't give us a frame. At 0x0 the frame is
+/// taken and named at whatever size the device gives.
+pub fn take_snap(device: &Device, width: u32, height: u32, output: &OutputOptions, policy: &CapturePolicy, sequence: u32) -> Result<Option<PathBuf>> {
+    let still_mode = policy.high_quality.then(|| StillMode::new(device)).transpose()?;
+    let cropper = policy.crop.map(|region| Cropper::new(device, region));
+    let mut vc: Camera = open_capture(device, width, height)?;
+
+    let mut saved_path = None;
+    if let Some(mut frame) = capture_still(&mut vc, policy)? {
+        if let Some(cropper) = &cropper {
+            frame = cropper.crop(frame)?;
+        }
+        let (width, height) = delivered_size(&frame, width, height)?;
+        let info = FrameInfo {
+            device,
+            width,
+            height,
+            fourcc: vc.get(CAP_PROP_FOURCC)? as i32,
+            sequence,
+            timestamp: Local::now()
+        };
+        saved_path = Some(output.save(&frame, &info)?);
+    }
+    vc.release()?;
+    // Puts the device's crop and mode back once it's no longer capturing.
+    drop(cropper);
+    drop(still_mode);
+    Ok(saved_path)
+}
+
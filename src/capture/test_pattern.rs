@@ -0,0 +1,199 @@
+//! A camera that draws its own frames, for trying things out with no camera.
+//!
+//! Each frame has colour bars, a box that moves from side to side, the frame
+//! number and the time it was drawn, and frames arrive at the frame rate asked
+//! for. Frames that aren't read in time are dropped, as a real camera's are.
+use std::{
+    thread::sleep,
+    time::{
+        Duration,
+        Instant,
+        SystemTime,
+        UNIX_EPOCH
+    }
+};
+use chrono::Local;
+use opencv::{
+    videoio::{
+        CAP_PROP_FOURCC,
+        CAP_PROP_FPS,
+        CAP_PROP_FRAME_HEIGHT,
+        CAP_PROP_FRAME_WIDTH,
+        CAP_PROP_POS_FRAMES,
+        CAP_PROP_POS_MSEC
+    },
+    imgproc::{
+        put_text,
+        rectangle,
+        FILLED,
+        FONT_HERSHEY_SIMPLEX,
+        LINE_8,
+        LINE_AA
+    },
+    core::{
+        Mat,
+        Point,
+        Rect,
+        Scalar,
+        CV_8UC3
+    }
+};
+use super::Result;
+
+/// White, yellow, cyan, green, magenta, red and blue at 75%, as BGR.
+const BARS: [(f64, f64, f64); 7] = [
+    (191.0, 191.0, 191.0),
+    (0.0, 191.0, 191.0),
+    (191.0, 191.0, 0.0),
+    (0.0, 191.0, 0.0),
+    (191.0, 0.0, 191.0),
+    (0.0, 0.0, 191.0),
+    (191.0, 0.0, 0.0)
+];
+
+/// How long the box takes to cross the frame and back.
+const BOX_PERIOD: f64 = 4.0;
+
+pub struct TestPattern {
+    width: i32,
+    height: i32,
+    fps: f64,
+    opened: Instant,
+    /// When it was opened, in milliseconds since the Unix epoch, so that the
+    /// timestamps of several test patterns can be compared.
+    opened_ms: f64,
+    /// The number of the frame last grabbed.
+    grabbed: Option<u64>
+}
+
+impl TestPattern {
+    /// Frames of the given size at the given rate, which must be more than 0.
+    pub fn new(width: u32, height: u32, fps: f64) -> Self {
+        TestPattern {
+            width: width.max(16) as i32,
+            height: height.max(16) as i32,
+            fps,
+            opened: Instant::now(),
+            opened_ms: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64() * 1000.0,
+            grabbed: None
+        }
+    }
+
+    /// Waits until the next frame is due. Where that has already passed, the
+    /// frames missed are dropped.
+    pub fn grab(&mut self) -> bool {
+        let elapsed_frames = (self.opened.elapsed().as_secs_f64() * self.fps) as u64;
+        let next = match self.grabbed {
+            Some(last) => (last + 1).max(elapsed_frames),
+            None => elapsed_frames
+        };
+        let due = self.opened + Duration::from_secs_f64(next as f64 / self.fps);
+        sleep(due.saturating_duration_since(Instant::now()));
+        self.grabbed = Some(next);
+        true
+    }
+
+    pub fn retrieve(&self, frame: &mut Mat) -> Result<bool> {
+        let Some(number) = self.grabbed else {
+            return Ok(false);
+        };
+        *frame = self.draw(number)?;
+        Ok(true)
+    }
+
+    pub fn get(&self, property: i32) -> f64 {
+        match property {
+            CAP_PROP_FRAME_WIDTH => self.width as f64,
+            CAP_PROP_FRAME_HEIGHT => self.height as f64,
+            CAP_PROP_FPS => self.fps,
+            CAP_PROP_FOURCC => i32::from_le_bytes(*b"BGR3") as f64,
+            CAP_PROP_POS_FRAMES => self.grabbed.unwrap_or_default() as f64,
+            CAP_PROP_POS_MSEC => self.grabbed.map_or(0.0, |number| self.opened_ms + number as f64 * 1000.0 / self.fps),
+            _ => 0.0
+        }
+    }
+
+    fn draw(&self, number: u64) -> Result<Mat> {
+        let (width, height) = (self.width, self.height);
+        let mut frame = Mat::new_rows_cols_with_default(height, width, CV_8UC3, Scalar::all(0.0))?;
+
+        let bars_height = height * 2 / 3;
+        for (i, &(b, g, r)) in BARS.iter().enumerate() {
+            let left = width * i as i32 / BARS.len() as i32;
+            let right = width * (i as i32 + 1) / BARS.len() as i32;
+            rectangle(&mut frame, Rect::new(left, 0, right - left, bars_height),
+                Scalar::new(b, g, r, 0.0), FILLED, LINE_8, 0)?;
+        }
+
+        // Bounces between the edges, to show motion blur, tearing and dropped frames.
+        let size = (height - bars_height).min(width) * 3 / 4;
+        let phase = (number as f64 / self.fps / BOX_PERIOD).fract();
+        let travel = (width - size) as f64 * (1.0 - (2.0 * phase - 1.0).abs());
+        let top = bars_height + (height - bars_height - size) / 2;
+        rectangle(&mut frame, Rect::new(travel as i32, top, size, size), Scalar::all(255.0), FILLED, LINE_8, 0)?;
+
+        let scale = height as f64 / 480.0;
+        let thickness = (scale * 2.0).round().max(1.0) as i32;
+        let lines = [
+            format!("frame {}", number),
+            format!("{}x{} {:.2} fps", width, height, self.fps),
+            Local::now().format("%H:%M:%S%.3f").to_string()
+        ];
+        for (i, text) in lines.iter().enumerate() {
+            let baseline = ((i as f64 + 1.0) * 36.0 * scale) as i32;
+            put_text(&mut frame, text, Point::new((12.0 * scale) as i32, baseline), FONT_HERSHEY_SIMPLEX, scale,
+                Scalar::all(0.0), thickness * 3, LINE_AA, false)?;
+            put_text(&mut frame, text, Point::new((12.0 * scale) as i32, baseline), FONT_HERSHEY_SIMPLEX, scale,
+                Scalar::all(255.0), thickness, LINE_AA, false)?;
+        }
+        Ok(frame)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::{
+        prelude::MatTraitConst,
+        core::Size
+    };
+    use super::*;
+
+    #[test]
+    fn frames_are_the_size_asked_for() {
+        let mut pattern = TestPattern::new(320, 240, 100.0);
+        let mut frame = Mat::default();
+        assert!(!pattern.retrieve(&mut frame).unwrap(), "retrieved before grabbing");
+        assert!(pattern.grab());
+        assert!(pattern.retrieve(&mut frame).unwrap());
+        assert_eq!((frame.size().unwrap(), frame.typ()), (Size::new(320, 240), CV_8UC3));
+    }
+
+    #[test]
+    fn properties_describe_the_mode() {
+        let pattern = TestPattern::new(4, 1080, 29.97);
+        assert_eq!(pattern.get(CAP_PROP_FRAME_WIDTH), 16.0);
+        assert_eq!(pattern.get(CAP_PROP_FRAME_HEIGHT), 1080.0);
+        assert_eq!(pattern.get(CAP_PROP_FPS), 29.97);
+        assert_eq!(pattern.get(CAP_PROP_FOURCC), i32::from_le_bytes(*b"BGR3") as f64);
+        assert_eq!(pattern.get(CAP_PROP_POS_FRAMES), 0.0);
+        assert_eq!(pattern.get(CAP_PROP_POS_MSEC), 0.0);
+    }
+
+    #[test]
+    fn frames_grabbed_late_are_dropped() {
+        let mut pattern = TestPattern::new(64, 48, 10.0);
+        pattern.grab();
+        pattern.grab();
+        assert_eq!(pattern.grabbed, Some(1));
+        let first_ms = pattern.get(CAP_PROP_POS_MSEC);
+
+        // Frames 2 and 3 come and go while nobody's grabbing.
+        sleep(Duration::from_millis(350));
+        pattern.grab();
+        let number = pattern.grabbed.unwrap();
+        assert!((3..=5).contains(&number), "grabbed frame {}", number);
+        assert_eq!(pattern.get(CAP_PROP_POS_FRAMES), number as f64);
+        let interval_ms = pattern.get(CAP_PROP_POS_MSEC) - first_ms;
+        assert!((interval_ms - (number - 1) as f64 * 100.0).abs() < 0.01, "{} ms later", interval_ms);
+    }
+}
@@ -0,0 +1,218 @@
+//! Takes stills from a device at regular intervals.
+//!
+//! Shots are scheduled at fixed times from the start, so a slow shot doesn't push
+//! back every shot after it. When the device fails or disappears, the shot is
+//! skipped and the device is looked for again by its unique id, since it may come
+//! back with a different number after being plugged in again.
+use std::{
+    path::{
+        Path,
+        PathBuf
+    },
+    thread::sleep,
+    time::{
+        Duration,
+        Instant
+    }
+};
+use chrono::{
+    DateTime,
+    Local
+};
+use opencv::videoio::CAP_PROP_FOURCC;
+use crate::video_devices::{
+    find_capture_devices,
+    Device
+};
+use super::{
+    capture_still,
+    delivered_size,
+    open_capture,
+    Camera,
+    CaptureError,
+    CapturePolicy,
+    Cropper,
+    FrameInfo,
+    OutputOptions,
+    Result,
+    StillMode
+};
+
+pub const DEFAULT_TIMELAPSE_TEMPLATE: &str = "lapse-{seq}-{timestamp}";
+
+pub struct TimelapseOptions {
+    pub width: u32,
+    pub height: u32,
+    pub interval: Duration,
+    /// Wait until this time before the first shot.
+    pub start: Option<DateTime<Local>>,
+    /// Take no shots after this time.
+    pub stop: Option<DateTime<Local>>,
+    /// Stop after saving this many shots.
+    pub max_count: Option<u32>,
+    /// Keep the device streaming between shots. Re-opening it for each shot
+    /// saves power, but each shot takes longer.
+    pub keep_open: bool,
+    pub output: OutputOptions,
+    pub policy: CapturePolicy
+}
+
+impl Default for TimelapseOptions {
+    fn default() -> Self {
+        TimelapseOptions {
+            width: 640,
+            height: 480,
+            interval: Duration::from_secs(60),
+            start: None,
+            stop: None,
+            max_count: None,
+            keep_open: false,
+            output: OutputOptions {
+                template: String::from(DEFAULT_TIMELAPSE_TEMPLATE),
+                ..OutputOptions::default()
+            },
+            policy: CapturePolicy::default()
+        }
+    }
+}
+
+pub enum TimelapseEvent<'a> {
+    Saved(&'a Path),
+    /// The shot couldn't be taken, and will be tried again at the next interval.
+    Failed(&'a CaptureError),
+    /// The shot was skipped because the previous one overran its interval.
+    Missed
+}
+
+#[derive(Debug, Default)]
+pub struct TimelapseReport {
+    pub saved: u32,
+    pub failed: u32,
+    pub missed: u32
+}
+
+/// Runs the time-lapse until the stop time or count is reached, passing each
+/// event to `on_event` as it happens.
+pub fn run_timelapse<F>(device: &Device, options: &TimelapseOptions, mut on_event: F) -> Result<TimelapseReport> where
+    F: FnMut(TimelapseEvent)
+{
+    let mut device = device.clone();
+    let mut report = TimelapseReport::default();
+    let mut vc: Option<Camera> = None;
+    let mut cropper: Option<Cropper> = None;
+    let mut still_mode: Option<StillMode> = None;
+
+    if let Some(start) = options.start {
+        sleep(until(start));
+    }
+    let mut next_shot = Instant::now();
+    loop {
+        sleep(next_shot.saturating_duration_since(Instant::now()));
+        if is_finished(options, &report) {
+            break;
+        }
+
+        match take_shot(&mut vc, &mut cropper, &mut still_mode, &device, options, report.saved + 1) {
+            Ok(path) => {
+                report.saved += 1;
+                on_event(TimelapseEvent::Saved(&path));
+            },
+            Err(err) if is_device_failure(&err) => {
+                report.failed += 1;
+                on_event(TimelapseEvent::Failed(&err));
+                // Dropping rather than releasing, as the device may be gone.
+                vc = None;
+                cropper = None;
+                still_mode = None;
+                if let Ok(Some(found)) = find_device(&device.unique_id) {
+                    device = found;
+                }
+            },
+            Err(err) => return Err(err)
+        }
+        if !options.keep_open {
+            if let Err(err) = release(&mut vc, &mut cropper, &mut still_mode) {
+                // It's opened again for the next shot either way.
+                report.failed += 1;
+                on_event(TimelapseEvent::Failed(&err));
+            }
+        }
+
+        next_shot += options.interval;
+        while next_shot < Instant::now() {
+            next_shot += options.interval;
+            report.missed += 1;
+            on_event(TimelapseEvent::Missed);
+        }
+    }
+    if let Err(err) = release(&mut vc, &mut cropper, &mut still_mode) {
+        report.failed += 1;
+        on_event(TimelapseEvent::Failed(&err));
+    }
+    Ok(report)
+}
+
+fn is_finished(options: &TimelapseOptions, report: &TimelapseReport) -> bool {
+    options.max_count.is_some_and(|max| report.saved >= max) ||
+        options.stop.is_some_and(|stop| Local::now() >= stop)
+}
+
+fn take_shot(vc: &mut Option<Camera>, cropper: &mut Option<Cropper>, still_mode: &mut Option<StillMode>, device: &Device, options: &TimelapseOptions, sequence: u32) -> Result<PathBuf> {
+    if vc.is_none() {
+        // Anything a failed attempt changed on the device is put back first.
+        *cropper = None;
+        *still_mode = None;
+        *still_mode = options.policy.high_quality.then(|| StillMode::new(device)).transpose()?;
+        *cropper = options.policy.crop.map(|region| Cropper::new(device, region));
+        *vc = Some(open_capture(device, options.width, options.height)?);
+    }
+    let vc = vc.as_mut().expect("device opened above");
+    let mut frame = capture_still(vc, &options.policy)?
+        .ok_or_else(|| CaptureError::NoFrames(device.name.clone()))?;
+    if let Some(cropper) = cropper {
+        frame = cropper.crop(frame)?;
+    }
+    let (width, height) = delivered_size(&frame, options.width, options.height)?;
+    let info = FrameInfo {
+        device,
+        width,
+        height,
+        fourcc: vc.get(CAP_PROP_FOURCC)? as i32,
+        sequence,
+        timestamp: Local::now()
+    };
+    options.output.save(&frame, &info)
+}
+
+/// Problems that may go away if the device is plugged back in, reopened or stops being busy,
+/// rather than ones with the output that will happen every time.
+fn is_device_failure(err: &CaptureError) -> bool {
+    matches!(err,
+        CaptureError::OpenCv(_) |
+        CaptureError::VideoDevice(_) |
+        CaptureError::NotOpened(_) |
+        CaptureError::NoFrames(_) |
+        CaptureError::BadFrame(_))
+}
+
+fn find_device(unique_id: &str) -> Result<Option<Device>> {
+    Ok(find_capture_devices()?
+        .into_iter()
+        .find(|d| d.unique_id == unique_id))
+}
+
+/// Releases the device, then puts back the crop and mode it had before,
+/// even if releasing failed.
+fn release(vc: &mut Option<Camera>, cropper: &mut Option<Cropper>, still_mode: &mut Option<StillMode>) -> Result<()> {
+    let released = match vc.take() {
+        Some(mut vc) => vc.release(),
+        None => Ok(())
+    };
+    *cropper = None;
+    *still_mode = None;
+    released
+}
+
+fn until(time: DateTime<Local>) -> Duration {
+    (time - Local::now()).to_std().unwrap_or(Duration::ZERO)
+}
@@ -0,0 +1,56 @@
+//! Builds an animated GIF, animated PNG or video from a sequence of images.
+use std::path::PathBuf;
+use anyhow::{
+    bail,
+    Result
+};
+use crate::animation::{
+    image_files,
+    read_images,
+    write_animation,
+    AnimationOptions,
+    Resize
+};
+use super::{
+    parse_crop,
+    parse_mode,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs animate --output FILE.gif|FILE.png|FILE.avi|FILE.mp4 (--input DIRECTORY | IMAGE...)
+    [--fps FPS] [--loops COUNT] [--crop X,Y,WIDTHxHEIGHT] [--resize WIDTHxHEIGHT | --scale FACTOR] [--codec FOURCC]
+Images in a directory are used in name order. --loops 0 (the default) loops forever.";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut options = AnimationOptions::default();
+    let mut output: Option<PathBuf> = None;
+    let mut inputs = Vec::<PathBuf>::new();
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--output" => output = Some(PathBuf::from(args.value(&flag)?)),
+            "--input" => inputs.extend(image_files(&PathBuf::from(args.value(&flag)?))?),
+            "--fps" => options.fps = args.parsed(&flag)?,
+            "--loops" => options.loops = args.parsed(&flag)?,
+            "--crop" => options.crop = Some(parse_crop(&args.value(&flag)?)?),
+            "--resize" => {
+                let (width, height) = parse_mode(&args.value(&flag)?)?;
+                options.resize = Some(Resize::To { width, height });
+            },
+            "--scale" => options.resize = Some(Resize::Scale(args.parsed(&flag)?)),
+            "--codec" => options.codec = Some(args.value(&flag)?),
+            _ if !flag.starts_with("--") => inputs.push(PathBuf::from(flag)),
+            _ => return Err(args.unexpected(&flag))
+        }
+    }
+    let Some(output) = output else {
+        bail!("Where to?\n{}", USAGE);
+    };
+    if options.fps <= 0.0 {
+        bail!("--fps must be more than 0");
+    }
+
+    let count = write_animation(&output, read_images(&inputs), &options)?;
+    println!("Wrote {} frames to {}", count, output.display());
+    Ok(())
+}
@@ -0,0 +1,89 @@
+//! Measures the frame rate and latency each mode of a device actually delivers.
+use std::time::Duration;
+use anyhow::Result;
+use crate::capture::{
+    benchmark_device,
+    benchmark_mode,
+    fourcc_to_string,
+    BenchmarkOptions,
+    ModeReport,
+    TimestampSource
+};
+use super::{
+    find_devices,
+    parse_mode,
+    select_device,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs benchmark [--device ID|NUMBER|NAME] [--mode WIDTHxHEIGHT] [--seconds SECONDS]
+Every mode of every device is measured unless --device or --mode narrows it down.";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut options = BenchmarkOptions::default();
+    let mut selector: Option<String> = None;
+    let mut mode: Option<(u32, u32)> = None;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--mode" => mode = Some(parse_mode(&args.value(&flag)?)?),
+            "--seconds" => options.duration = Duration::from_secs_f64(args.parsed(&flag)?),
+            _ => return Err(args.unexpected(&flag))
+        }
+    }
+
+    let devices = match &selector {
+        Some(selector) => vec![select_device(selector)?],
+        None => find_devices()?
+    };
+    for device in &devices {
+        println!("{} ({})", device.name, device.unique_id);
+        print_header();
+        match mode {
+            Some((width, height)) => {
+                print_mode(&benchmark_mode(device, width, height, &options)?);
+            },
+            None => {
+                let report = benchmark_device(device, &options, print_mode);
+                let keeping_up = report.modes.iter().filter(|mode| mode.keeps_up()).count();
+                println!("{}: {} of {} modes kept up with their advertised frame rate",
+                    report.device_name, keeping_up, report.modes.len());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_header() {
+    println!("  {:>11} {:>11} {:>4} {:>7} {:>7} {:>8} {:>8} {:>8} {:>8} {:>7}",
+        "asked", "got", "fmt", "nominal", "fps", "interval", "jitter", "max gap", "first", "dropped");
+}
+
+fn print_mode(report: &ModeReport) {
+    let asked = format!("{}x{}", report.width, report.height);
+    if let Some(error) = &report.error {
+        if report.frames == 0 {
+            println!("  {:>11} {}", asked, error);
+            return;
+        }
+    }
+    // Intervals measured when frames were read include our own delays.
+    let timing_note = match report.timestamp_source {
+        TimestampSource::ReadTime => "~",
+        _ => ""
+    };
+    println!("  {:>11} {:>11} {:>4} {:>7.2} {:>7.2} {:>6.1}ms {:>1}{:>5.1}ms {:>6.1}ms {:>7.2}s {:>7}{}",
+        asked,
+        format!("{}x{}", report.actual_width, report.actual_height),
+        fourcc_to_string(report.fourcc),
+        report.nominal_fps,
+        report.measured_fps,
+        report.mean_interval.as_secs_f64() * 1000.0,
+        timing_note,
+        report.jitter.as_secs_f64() * 1000.0,
+        report.max_interval.as_secs_f64() * 1000.0,
+        report.time_to_first_frame.unwrap_or_default().as_secs_f64(),
+        report.dropped_frames,
+        report.error.as_deref().map(|error| format!(" ({})", error)).unwrap_or_default());
+}
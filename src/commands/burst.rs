@@ -0,0 +1,99 @@
+//! Grabs a burst of consecutive frames from one device.
+use std::time::Duration;
+use anyhow::{
+    bail,
+    Result
+};
+use chrono::Local;
+use crate::capture::{
+    capture_burst,
+    fourcc_to_string,
+    BurstOptions,
+    FrameInfo,
+    OutputOptions,
+    TimestampSource
+};
+use super::{
+    largest_mode,
+    parse_mode,
+    select_device,
+    snap::parse_output_flag,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs burst --device ID|NUMBER|NAME [--mode WIDTHxHEIGHT] [--count FRAMES]
+    [--discard FRAMES] [--timeout SECONDS] [--opencv] [--into-mats] [--sharpest]
+    [--dir DIRECTORY] [--name TEMPLATE] [--format FORMAT]
+--opencv reads through OpenCV even where the driver could be read directly.
+--into-mats has the driver capture straight into images, in BGR where it can, so frames aren't copied (Linux).
+--sharpest saves only the sharpest frame.";
+
+const DEFAULT_TEMPLATE: &str = "burst-{name}-{timestamp}-{seq}";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut options = BurstOptions::default();
+    let mut output = OutputOptions { template: String::from(DEFAULT_TEMPLATE), ..OutputOptions::default() };
+    let mut selector: Option<String> = None;
+    let mut mode: Option<(u32, u32)> = None;
+    let mut sharpest_only = false;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--mode" => mode = Some(parse_mode(&args.value(&flag)?)?),
+            "--count" => options.count = args.parsed(&flag)?,
+            "--discard" => options.discard = args.parsed(&flag)?,
+            "--timeout" => options.timeout = Duration::from_secs_f64(args.parsed(&flag)?),
+            "--opencv" => options.native = false,
+            "--into-mats" => options.into_mats = true,
+            "--sharpest" => sharpest_only = true,
+            _ => if !parse_output_flag(&flag, &mut args, &mut output)? {
+                return Err(args.unexpected(&flag));
+            }
+        }
+    }
+    let Some(selector) = selector else {
+        bail!("Which device?\n{}", USAGE);
+    };
+
+    let device = select_device(&selector)?;
+    (options.width, options.height) = mode.unwrap_or_else(|| largest_mode(&device));
+    let started = Local::now();
+    let burst = capture_burst(&device, &options)?;
+    println!("Burst of {} frames from {} at {}x{} {}, timed {}", burst.frames.len(), device.name,
+        burst.width, burst.height, fourcc_to_string(burst.fourcc), match burst.timestamp_source {
+            TimestampSource::Kernel => "by the driver",
+            TimestampSource::OpenCv => "by OpenCV",
+            TimestampSource::ReadTime => "when read"
+        });
+
+    let sharpest = burst.sharpest()?;
+    for (i, frame) in burst.frames.iter().enumerate() {
+        let is_sharpest = sharpest == Some(i);
+        if sharpest_only && !is_sharpest {
+            continue;
+        }
+        let info = FrameInfo {
+            device: &device,
+            width: burst.width,
+            height: burst.height,
+            fourcc: burst.fourcc,
+            sequence: i as u32,
+            timestamp: started + chrono::Duration::from_std(frame.timestamp)?
+        };
+        let path = output.save(&frame.image, &info)?;
+        print!("  {} at {:.1} ms", path.display(), frame.timestamp.as_secs_f64() * 1000.0);
+        if let Some(sequence) = frame.sequence {
+            print!(", driver frame {}", sequence);
+        }
+        println!("{}", if is_sharpest { ", sharpest" } else { "" });
+    }
+
+    let intervals = burst.intervals();
+    if let (Some(min), Some(max)) = (intervals.iter().min(), intervals.iter().max()) {
+        let mean = intervals.iter().sum::<Duration>() / intervals.len() as u32;
+        println!("  intervals: mean {:.1} ms, min {:.1} ms, max {:.1} ms",
+            mean.as_secs_f64() * 1000.0, min.as_secs_f64() * 1000.0, max.as_secs_f64() * 1000.0);
+    }
+    Ok(())
+}
@@ -0,0 +1,178 @@
+//! Lists the hardware encoders and decoders, and encodes frames from a device
+//! with one to see how much quicker it is than encoding in software.
+use std::{
+    fs::File,
+    io::Write,
+    path::PathBuf,
+    time::{
+        Duration,
+        Instant
+    }
+};
+use anyhow::{
+    bail,
+    Context,
+    Result
+};
+use opencv::{
+    imgcodecs::{
+        imencode,
+        ImwriteFlags
+    },
+    prelude::MatTraitConst,
+    core::Vector
+};
+use crate::{
+    capture::{
+        fourcc_to_string,
+        open_capture,
+        read_frame
+    },
+    video_devices::{
+        find_codec_devices,
+        CodecKind,
+        CodecSession
+    }
+};
+use super::{
+    largest_mode,
+    parse_mode,
+    select_device,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs codec [--device ID|NUMBER|NAME [--mode WIDTHxHEIGHT] [--format FOURCC]
+    [--frames COUNT] [--quality QUALITY] [--output FILE] [--decode]]
+Lists the hardware encoders and decoders, or captures --frames frames (default 30) from the device and encodes
+them as --format (default JPEG) with the first encoder that makes it, reporting how long it took.
+--quality sets the JPEG quality, 1 to 100.
+--output writes the coded frames one after another, which for JPEG is an MJPEG stream.
+--decode decodes each coded frame back with a decoder for the format.";
+
+/// How long to wait for each frame from the device and the codecs.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut selector: Option<String> = None;
+    let mut mode: Option<(u32, u32)> = None;
+    let mut format = String::from("JPEG");
+    let mut frames: u32 = 30;
+    let mut quality: Option<u8> = None;
+    let mut output: Option<PathBuf> = None;
+    let mut decode = false;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--mode" => mode = Some(parse_mode(&args.value(&flag)?)?),
+            "--format" => format = args.value(&flag)?,
+            "--frames" => frames = args.parsed(&flag)?,
+            "--quality" => quality = Some(args.parsed(&flag)?),
+            "--output" => output = Some(PathBuf::from(args.value(&flag)?)),
+            "--decode" => decode = true,
+            _ => return Err(args.unexpected(&flag))
+        }
+    }
+
+    let codecs = find_codec_devices()?;
+    let Some(selector) = selector else {
+        if codecs.is_empty() {
+            println!("No hardware encoders or decoders found");
+        }
+        for codec in &codecs {
+            println!("{} {}: {}", codec.path.display(), codec.name, match codec.kind {
+                CodecKind::Encoder => "encoder",
+                CodecKind::Decoder => "decoder",
+                CodecKind::Converter => "converter"
+            });
+            println!("  takes {}", to_names(&codec.input_formats));
+            println!("  makes {}", to_names(&codec.output_formats));
+        }
+        return Ok(());
+    };
+    let Ok(fourcc) = <[u8; 4]>::try_from(format.as_bytes()).map(u32::from_le_bytes) else {
+        bail!("{} isn't a four character pixel format such as JPEG or H264", format);
+    };
+    let Some(encoder) = codecs.iter().find(|codec| codec.kind == CodecKind::Encoder && codec.output_formats.contains(&fourcc)) else {
+        bail!("No encoder makes {}", format);
+    };
+    let mut decoder = if decode {
+        let Some(decoder) = codecs.iter().find(|codec| codec.kind == CodecKind::Decoder && codec.input_formats.contains(&fourcc)) else {
+            bail!("No decoder takes {}", format);
+        };
+        Some(CodecSession::decoder(decoder, fourcc)?)
+    } else {
+        None
+    };
+
+    let device = select_device(&selector)?;
+    let (width, height) = mode.unwrap_or_else(|| largest_mode(&device));
+    let mut vc = open_capture(&device, width, height)?;
+    let mut session = CodecSession::encoder(encoder, width, height, fourcc)?;
+    if let Some(quality) = quality {
+        session.set_jpeg_quality(quality)?;
+    }
+    let input = session.input_format();
+    println!("Encoding {}x{} {} as {} with {} ({})", input.width, input.height, fourcc_to_string(input.pixel_format as i32),
+        format, encoder.name, encoder.path.display());
+    let mut file = output.as_ref()
+        .map(|path| File::create(path).with_context(|| format!("Failed to create {}", path.display())))
+        .transpose()?;
+
+    let mut params = Vector::<i32>::new();
+    params.push(ImwriteFlags::IMWRITE_JPEG_QUALITY as i32);
+    params.push(quality.unwrap_or(95) as i32);
+    let mut software_jpeg = Vector::<u8>::new();
+    let mut encoded = 0;
+    let mut coded_bytes = 0;
+    let mut encode_time = Duration::ZERO;
+    let mut software_time = Duration::ZERO;
+    let mut decoded = Vec::new();
+    for _ in 0..frames {
+        let Some(image) = read_frame(&mut vc, FRAME_TIMEOUT)? else {
+            bail!("No frame from {} within {:?}", device.name, FRAME_TIMEOUT);
+        };
+        let started = Instant::now();
+        let coded = session.encode(&image, FRAME_TIMEOUT)?;
+        encode_time += started.elapsed();
+        // For comparison, as imwrite would have done it.
+        if format == "JPEG" || format == "MJPG" {
+            let started = Instant::now();
+            imencode(".jpg", &image, &mut software_jpeg, &params)?;
+            software_time += started.elapsed();
+        }
+        if coded.is_empty() {
+            continue;
+        }
+        encoded += 1;
+        coded_bytes += coded.len();
+        if let Some(file) = &mut file {
+            file.write_all(&coded)
+                .with_context(|| format!("Failed to write {}", output.as_ref().unwrap().display()))?;
+        }
+        if let Some(decoder) = &mut decoder {
+            decoded.extend(decoder.decode(&coded, FRAME_TIMEOUT)?);
+        }
+    }
+
+    if encoded > 0 {
+        println!("{} frames, {:.1} ms each, {} bytes each on average", encoded,
+            encode_time.as_secs_f64() * 1000.0 / frames as f64, coded_bytes / encoded);
+    }
+    if software_time > Duration::ZERO {
+        println!("OpenCV took {:.1} ms each", software_time.as_secs_f64() * 1000.0 / frames as f64);
+    }
+    if let Some(first) = decoded.first() {
+        println!("{} decoded back, at {}x{}", decoded.len(), first.cols(), first.rows());
+    } else if decoder.is_some() {
+        println!("None decoded back");
+    }
+    Ok(())
+}
+
+fn to_names(pixel_formats: &[u32]) -> String {
+    pixel_formats.iter()
+        .map(|&pixel_format| fourcc_to_string(pixel_format as i32))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,89 @@
+//! Streams from a v4l2 device sharing each frame as a DMABUF rather than
+//! copying it, to check the zero copy path works with a device and see how
+//! fast it goes.
+use std::{
+    os::fd::AsRawFd,
+    time::Duration
+};
+use anyhow::{
+    bail,
+    Result
+};
+use crate::video_devices::{
+    allocate_dmabufs,
+    V4l2Device
+};
+use super::{
+    largest_mode,
+    parse_mode,
+    select_device,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs dmabuf --device ID|NUMBER|NAME [--mode WIDTHxHEIGHT] [--frames COUNT] [--buffers COUNT] [--import]
+Captures --frames frames (default 100) without copying them, sharing each as a DMABUF, and reports the frame rate.
+--import captures into buffers from /dev/udmabuf rather than the driver's own, as if they came from a display or encoder.";
+
+/// How long to wait for each frame.
+const FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut selector: Option<String> = None;
+    let mut mode: Option<(u32, u32)> = None;
+    let mut frames: u32 = 100;
+    let mut buffers: u32 = 4;
+    let mut import = false;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--mode" => mode = Some(parse_mode(&args.value(&flag)?)?),
+            "--frames" => frames = args.parsed(&flag)?,
+            "--buffers" => buffers = args.parsed(&flag)?,
+            "--import" => import = true,
+            _ => return Err(args.unexpected(&flag))
+        }
+    }
+    let Some(selector) = selector else {
+        bail!("Which device?\n{}", USAGE);
+    };
+
+    let device = select_device(&selector)?;
+    if device.source.is_some() {
+        bail!("{} isn't a v4l2 device", device.name);
+    }
+    let mut v4l2 = V4l2Device::open(&device)?;
+    let (width, height) = mode.unwrap_or_else(|| largest_mode(&device));
+    let format = v4l2.set_format(width, height)?;
+    if import {
+        v4l2.start_streaming_imported(allocate_dmabufs(buffers as usize, format.size_image as usize)?)?;
+    } else {
+        v4l2.start_streaming(buffers)?;
+    }
+    println!("Streaming {}x{} {}", format.width, format.height, if import { "into udmabuf buffers" } else { "from the driver's buffers" });
+
+    let mut first: Option<(u32, Duration)> = None;
+    let mut last: Option<(u32, Duration)> = None;
+    let mut count = 0;
+    while count < frames {
+        let Some(frame) = v4l2.read_shared_frame(FRAME_TIMEOUT)? else {
+            bail!("No frame from {} within {:?}", device.name, FRAME_TIMEOUT);
+        };
+        if count == 0 {
+            println!("frame {}: {} bytes in DMABUF {}", frame.sequence, frame.length, frame.dmabuf().as_raw_fd());
+        }
+        first.get_or_insert((frame.sequence, frame.timestamp));
+        last = Some((frame.sequence, frame.timestamp));
+        count += 1;
+    }
+    v4l2.stop_streaming()?;
+
+    if let (Some((first_sequence, first_time)), Some((last_sequence, last_time))) = (first, last) {
+        let elapsed = last_time.saturating_sub(first_time).as_secs_f64();
+        let dropped = (last_sequence - first_sequence + 1).saturating_sub(count);
+        if elapsed > 0.0 {
+            println!("{} frames at {:.2} fps, {} dropped", count, (count - 1) as f64 / elapsed, dropped);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//! Prints the events a v4l2 device reports as they happen, such as controls
+//! changing value under auto exposure or a privacy shutter being closed.
+use std::time::{
+    Duration,
+    Instant
+};
+use anyhow::{
+    bail,
+    Result
+};
+use crate::video_devices::{
+    Control,
+    DeviceEvent,
+    EventKind,
+    V4l2Device,
+    VideoDeviceError
+};
+use super::{
+    largest_mode,
+    parse_mode,
+    select_device,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs events --device ID|NUMBER|NAME [--stream [--mode WIDTHxHEIGHT]] [--seconds SECONDS]
+Prints control changes, source changes and the end of the stream until stopped, or for --seconds.
+--stream captures meanwhile, as some controls only change while frames are coming, and reports each frame starting too.";
+
+/// How long to wait for each frame or event before checking the time.
+const WAIT: Duration = Duration::from_secs(1);
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut selector: Option<String> = None;
+    let mut mode: Option<(u32, u32)> = None;
+    let mut stream = false;
+    let mut duration: Option<Duration> = None;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--mode" => mode = Some(parse_mode(&args.value(&flag)?)?),
+            "--stream" => stream = true,
+            "--seconds" => duration = Some(Duration::from_secs_f64(args.parsed(&flag)?)),
+            _ => return Err(args.unexpected(&flag))
+        }
+    }
+    let Some(selector) = selector else {
+        bail!("Which device?\n{}", USAGE);
+    };
+
+    let device = select_device(&selector)?;
+    if device.source.is_some() {
+        bail!("{} isn't a v4l2 device", device.name);
+    }
+    let mut v4l2 = V4l2Device::open(&device)?;
+    let controls = v4l2.controls()?;
+    // Not every driver sends every kind of event, so subscribe to what it will take.
+    for control in &controls {
+        let _ = v4l2.subscribe(EventKind::Control(control.id));
+    }
+    let _ = v4l2.subscribe_source_changes();
+    let _ = v4l2.subscribe(EventKind::EndOfStream);
+    if stream {
+        let (width, height) = mode.unwrap_or_else(|| largest_mode(&device));
+        let format = v4l2.set_format(width, height)?;
+        let _ = v4l2.subscribe(EventKind::FrameSync);
+        v4l2.start_streaming(4)?;
+        match v4l2.stream_parameters().ok().and_then(|parameters| parameters.frame_rate) {
+            Some(fps) => println!("Streaming {}x{} at {:.2} fps", format.width, format.height, fps),
+            None => println!("Streaming {}x{}", format.width, format.height)
+        }
+    }
+
+    let deadline = duration.map(|duration| Instant::now() + duration);
+    while deadline.is_none_or(|deadline| Instant::now() < deadline) {
+        if stream {
+            match v4l2.read_frame(WAIT) {
+                Ok(_) => {},
+                Err(VideoDeviceError::SourceChanged(_)) => stream = false,
+                Err(err) => return Err(err.into())
+            }
+            while let Some(event) = v4l2.wait_event(Duration::ZERO)? {
+                print_event(&event, &controls);
+            }
+        } else if let Some(event) = v4l2.wait_event(WAIT)? {
+            print_event(&event, &controls);
+        }
+    }
+    Ok(())
+}
+
+fn print_event(event: &DeviceEvent, controls: &[Control]) {
+    match event {
+        DeviceEvent::Control { id, value, value_changed, flags_changed, inactive } => {
+            let name = controls.iter()
+                .find(|control| control.id == *id)
+                .map_or_else(|| format!("control {:#x}", id), |control| control.name.clone());
+            let change = match (value_changed, flags_changed) {
+                (true, _) => "",
+                (false, true) => " (flags changed)",
+                (false, false) => " (now)"
+            };
+            println!("{} = {}{}{}", name, value, if *inactive { ", inactive" } else { "" }, change);
+        },
+        DeviceEvent::SourceChange { input, resolution } =>
+            println!("source changed on input {}{}", input, if *resolution { ", resolution too" } else { "" }),
+        DeviceEvent::EndOfStream => println!("end of stream"),
+        DeviceEvent::FrameSync { sequence } => println!("frame {} starting", sequence)
+    }
+}
@@ -0,0 +1,124 @@
+//! Lists the inputs of a capture card or TV tuner, and picks the one to capture
+//! from and its analogue standard. For HDMI receivers, sets the timings to
+//! those of the signal. Drivers keep the choice after the device is closed, so
+//! the other commands then capture from it.
+use std::time::Duration;
+use anyhow::{
+    bail,
+    Result
+};
+use crate::video_devices::{
+    DeviceEvent,
+    DvTimings,
+    V4l2Device,
+    VideoInput,
+    VideoStandard
+};
+use super::{
+    select_device,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs input --device ID|NUMBER|NAME [--input NUMBER|NAME] [--standard NAME] [--timings [--watch]]
+Without --input or --standard, lists the inputs and the standards each can receive.
+--timings sets an HDMI receiver to the timings of its signal, and --watch keeps doing so whenever the source changes.";
+
+/// How long --watch waits for an event before waiting again.
+const WATCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut selector: Option<String> = None;
+    let mut input: Option<String> = None;
+    let mut standard: Option<String> = None;
+    let mut timings = false;
+    let mut watch = false;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--input" => input = Some(args.value(&flag)?),
+            "--standard" => standard = Some(args.value(&flag)?),
+            "--timings" => timings = true,
+            "--watch" => watch = true,
+            _ => return Err(args.unexpected(&flag))
+        }
+    }
+    let Some(selector) = selector else {
+        bail!("Which device?\n{}", USAGE);
+    };
+
+    let device = select_device(&selector)?;
+    if device.source.is_some() {
+        bail!("{} isn't a v4l2 device", device.name);
+    }
+    let mut v4l2 = V4l2Device::open(&device)?;
+    if let Some(input) = input {
+        let (inputs, _) = v4l2.inputs()?;
+        let Some(found) = find_input(&inputs, &input) else {
+            bail!("{} has no input {:?}", device.name, input);
+        };
+        v4l2.set_input(found.index)?;
+    }
+    if let Some(standard) = standard {
+        let (inputs, _) = v4l2.inputs()?;
+        let Some(found) = inputs.iter()
+            .filter(|input| input.selected)
+            .flat_map(|input| &input.standards)
+            .find(|s| s.name.eq_ignore_ascii_case(&standard)) else {
+            bail!("The selected input of {} can't receive {:?}", device.name, standard);
+        };
+        v4l2.set_standard(found.id)?;
+    }
+
+    let (inputs, standard) = v4l2.inputs()?;
+    println!("{}", device.name);
+    print_inputs(&inputs, standard.as_ref());
+
+    if timings || watch {
+        let supported = v4l2.supported_dv_timings()?;
+        if !supported.is_empty() {
+            println!("supported timings: {}", supported.iter().map(DvTimings::to_string).collect::<Vec<_>>().join(" "));
+        }
+        apply_timings(&mut v4l2)?;
+    }
+    if watch {
+        v4l2.subscribe_source_changes()?;
+        loop {
+            if let Some(DeviceEvent::SourceChange { input, resolution }) = v4l2.wait_event(WATCH_TIMEOUT)? {
+                println!("source changed on input {}{}", input, if resolution { ", resolution too" } else { "" });
+                apply_timings(&mut v4l2)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn apply_timings(v4l2: &mut V4l2Device) -> Result<()> {
+    match v4l2.apply_detected_timings()? {
+        Some(timings) => println!("timings set to {}", timings),
+        None => println!("no stable signal")
+    }
+    Ok(())
+}
+
+/// By the driver's number for it, or by name.
+fn find_input<'a>(inputs: &'a [VideoInput], selector: &str) -> Option<&'a VideoInput> {
+    inputs.iter().find(|input| input.index.to_string() == selector)
+        .or_else(|| inputs.iter().find(|input| input.name.eq_ignore_ascii_case(selector)))
+}
+
+fn print_inputs(inputs: &[VideoInput], standard: Option<&VideoStandard>) {
+    for input in inputs {
+        let marker = if input.selected { '*' } else { ' ' };
+        let kind = if input.tuner { " (tuner)" } else { "" };
+        let status = input.status.iter().map(|status| status.name()).collect::<Vec<_>>().join(", ");
+        println!("{} {} {}{}  {}", marker, input.index, input.name, kind, status);
+        if !input.standards.is_empty() {
+            let names = input.standards.iter().map(|s| s.name.as_str()).collect::<Vec<_>>().join(" ");
+            println!("      standards: {}", names);
+        }
+    }
+    if let Some(standard) = standard {
+        println!("standard: {} ({} lines, {:.2}fps)", standard.name, standard.lines, standard.frame_rate);
+    }
+}
@@ -0,0 +1,143 @@
+//! The commands that can be run from the command line, and the argument
+//! handling they share.
+pub mod snap;
+pub mod record;
+pub mod timelapse;
+pub mod animate;
+pub mod burst;
+pub mod sync;
+pub mod benchmark;
+pub mod probe;
+pub mod serve;
+#[cfg(target_os = "linux")]
+pub mod input;
+#[cfg(target_os = "linux")]
+pub mod events;
+#[cfg(target_os = "linux")]
+pub mod dmabuf;
+#[cfg(target_os = "linux")]
+pub mod codec;
+
+use std::{
+    str::FromStr,
+    vec::IntoIter
+};
+use anyhow::{
+    anyhow,
+    bail,
+    Result
+};
+use opencv::core::Rect;
+use crate::video_devices::{
+    find_capture_devices,
+    is_source,
+    source_device,
+    Device,
+    FrameSizeType,
+    VideoDeviceError
+};
+
+/// Hands out flags and their values, explaining usage when they are wrong.
+pub struct Args {
+    args: IntoIter<String>,
+    usage: &'static str
+}
+
+impl Args {
+    pub fn new(args: Vec<String>, usage: &'static str) -> Self {
+        Args { args: args.into_iter(), usage }
+    }
+
+    pub fn next_flag(&mut self) -> Option<String> {
+        self.args.next()
+    }
+
+    pub fn value(&mut self, flag: &str) -> Result<String> {
+        self.args.next()
+            .ok_or_else(|| anyhow!("{} needs a value\n{}", flag, self.usage))
+    }
+
+    pub fn parsed<T>(&mut self, flag: &str) -> Result<T> where
+        T: FromStr,
+        T::Err: std::error::Error + Send + Sync + 'static
+    {
+        let value = self.value(flag)?;
+        value.parse::<T>()
+            .map_err(|err| anyhow!("Invalid value {:?} for {}: {}\n{}", value, flag, err, self.usage))
+    }
+
+    pub fn unexpected(&self, flag: &str) -> anyhow::Error {
+        anyhow!("Unexpected argument {}\n{}", flag, self.usage)
+    }
+}
+
+pub fn find_devices() -> Result<Vec<Device>> {
+    find_capture_devices().map_err(explain_device_error)
+}
+
+/// Picks a device by unique id, by its position in the list (starting at 1),
+/// or by part of its name. Failing those, a URL, video file or image sequence
+/// pattern is opened as a device of its own.
+pub fn select_device(selector: &str) -> Result<Device> {
+    let mut devices = find_devices()?;
+    let position = if let Some(position) = devices.iter().position(|d| d.unique_id == selector) {
+        position
+    } else if let Some(number) = selector.parse::<usize>().ok().filter(|n| (1..=devices.len()).contains(n)) {
+        number - 1
+    } else if let Some(position) = devices.iter()
+            .position(|d| d.name.to_lowercase().contains(&selector.to_lowercase())) {
+        position
+    } else if is_source(selector) {
+        return Ok(source_device(selector, None));
+    } else {
+        bail!("No device matches {:?}", selector);
+    };
+    Ok(devices.swap_remove(position))
+}
+
+/// Parses a frame size such as `640x480`.
+pub fn parse_mode(mode: &str) -> Result<(u32, u32)> {
+    let (width, height) = mode.split_once('x')
+        .ok_or_else(|| anyhow!("Expected a frame size such as 640x480, not {:?}", mode))?;
+    Ok((width.parse()?, height.parse()?))
+}
+
+/// Parses a crop rectangle such as `100,50,640x480`.
+pub fn parse_crop(crop: &str) -> Result<Rect> {
+    let invalid = || anyhow!("Expected a crop such as 100,50,640x480, not {:?}", crop);
+    let mut parts = crop.splitn(3, ',');
+    let (Some(x), Some(y), Some(size)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let (width, height) = parse_mode(size)?;
+    Ok(Rect::new(x.parse().map_err(|_| invalid())?, y.parse().map_err(|_| invalid())?, width as i32, height as i32))
+}
+
+/// The largest frame size the device supports, or VGA if it didn't say.
+/// Sources opened by URL or path give 0x0, whatever size they deliver.
+pub fn largest_mode(device: &Device) -> (u32, u32) {
+    if device.source.is_some() && device.frame_size_types.is_empty() {
+        return (0, 0);
+    }
+    // frame_size_types are sorted largest first.
+    match device.frame_size_types.first() {
+        Some(FrameSizeType::Discrete { width, height }) => (*width, *height),
+        Some(FrameSizeType::Stepwise { max_width, max_height, .. }) => (*max_width, *max_height),
+        None => (640, 480)
+    }
+}
+
+fn explain_device_error(err: VideoDeviceError) -> anyhow::Error {
+    let hint = if err.is_permission_denied() {
+        if cfg!(target_os = "windows") {
+            "Not allowed to use the camera - check camera access in the Windows privacy settings"
+        } else {
+            "Not allowed to open the video devices - add yourself to the video group (sudo usermod -aG video $USER) and log in again"
+        }
+    } else if err.is_busy() {
+        "The camera is in use by another program"
+    } else {
+        return err.into();
+    };
+    anyhow::Error::new(err).context(hint)
+}
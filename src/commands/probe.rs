@@ -0,0 +1,185 @@
+//! Tries every mode of every device and reports which really work.
+use std::{
+    fs::write,
+    path::PathBuf,
+    time::Duration
+};
+use anyhow::{
+    Context,
+    Result
+};
+use chrono::Local;
+use opencv::prelude::MatTraitConst;
+use crate::{
+    animation::{
+        contact_sheet,
+        SheetOptions,
+        Tile
+    },
+    capture::{
+        fourcc_to_string,
+        probe_device,
+        DeviceProbe,
+        FrameInfo,
+        OutputOptions,
+        ProbeOptions,
+        ProbeResult
+    },
+    json::{
+        array,
+        JsonObject
+    },
+    video_devices::Device
+};
+use super::{
+    find_devices,
+    parse_mode,
+    select_device,
+    snap::parse_output_flag,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs probe [--device ID|NUMBER|NAME] [--json FILE] [--seconds SECONDS] [--timeout SECONDS]
+    [--save] [--sheet] [--tile WIDTHxHEIGHT] [--columns COLUMNS] [--dir DIRECTORY] [--name TEMPLATE] [--format FORMAT]
+Every mode of every device is tried unless --device narrows it down.
+--save keeps the first good frame of each mode.
+--sheet saves a contact sheet of every mode of each device as sheet-{name}.";
+
+const DEFAULT_TEMPLATE: &str = "probe-{name}-{mode}-{fourcc}-{seq}";
+const SHEET_TEMPLATE: &str = "sheet-{name}";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut options = ProbeOptions::default();
+    let mut output = OutputOptions { template: String::from(DEFAULT_TEMPLATE), ..OutputOptions::default() };
+    let mut selector: Option<String> = None;
+    let mut json_path = PathBuf::from("probe.json");
+    let mut save = false;
+    let mut sheet: Option<SheetOptions> = None;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--json" => json_path = PathBuf::from(args.value(&flag)?),
+            "--seconds" => options.duration = Duration::from_secs_f64(args.parsed(&flag)?),
+            "--timeout" => options.first_frame_timeout = Duration::from_secs_f64(args.parsed(&flag)?),
+            "--save" => save = true,
+            "--sheet" => { sheet.get_or_insert_with(SheetOptions::default); },
+            "--tile" => {
+                let (width, height) = parse_mode(&args.value(&flag)?)?;
+                let sheet = sheet.get_or_insert_with(SheetOptions::default);
+                (sheet.tile_width, sheet.tile_height) = (width, height);
+            },
+            "--columns" => sheet.get_or_insert_with(SheetOptions::default).columns = Some(args.parsed(&flag)?),
+            _ => if !parse_output_flag(&flag, &mut args, &mut output)? {
+                return Err(args.unexpected(&flag));
+            }
+        }
+    }
+    let sheet_output = OutputOptions { template: String::from(SHEET_TEMPLATE), directory: output.directory.clone(), format: output.format };
+    if save {
+        options.output = Some(output);
+    }
+    if let Some(sheet) = &sheet {
+        options.thumbnail_width = Some(sheet.tile_width);
+    }
+
+    let devices = match &selector {
+        Some(selector) => vec![select_device(selector)?],
+        None => find_devices()?
+    };
+    let mut probes = Vec::new();
+    for device in &devices {
+        println!("{} ({})", device.name, device.unique_id);
+        print_header();
+        let mut probe = probe_device(device, &options, print_result);
+        let working = probe.results.iter().filter(|result| result.works()).count();
+        println!("{}: {} of {} modes work as advertised", probe.name, working, probe.results.len());
+        if let Some(sheet) = &sheet {
+            let path = save_sheet(device, &mut probe, sheet, &sheet_output)?;
+            println!("Wrote {}", path.display());
+        }
+        probes.push(probe);
+    }
+
+    let json = JsonObject::new()
+        .str("probed_at", &Local::now().to_rfc3339())
+        .str("os", std::env::consts::OS)
+        .raw("devices", array(probes.iter().map(|probe| probe.to_json())))
+        .build();
+    write(&json_path, json + "\n")
+        .with_context(|| format!("Failed to write {}", json_path.display()))?;
+    println!("Wrote {}", json_path.display());
+    Ok(())
+}
+
+fn print_header() {
+    println!("  {:>4} {:>11} {:>6}   {:>4} {:>11} {:>6} {:>6} {:>6} {:>8}",
+        "fmt", "asked", "fps", "fmt", "got", "fps", "rate", "first", "image");
+}
+
+fn print_result(result: &ProbeResult) {
+    let asked = &result.asked;
+    let asked_fps = asked.fps.map(|fps| format!("{:.2}", fps)).unwrap_or_else(|| String::from("-"));
+    print!("  {:>4} {:>11} {:>6}", asked.fourcc.map(fourcc_to_string).unwrap_or_else(|| String::from("-")),
+        format!("{}x{}", asked.width, asked.height), asked_fps);
+    if !result.opened {
+        println!("   {}", result.error.as_deref().unwrap_or("failed to open"));
+        return;
+    }
+    print!(" {} {:>4} {:>11} {:>6.2} {:>6.2} {:>5.2}s {:>8}",
+        if result.works() { ' ' } else { '!' },
+        fourcc_to_string(result.actual_fourcc),
+        format!("{}x{}", result.actual_width, result.actual_height),
+        result.actual_fps,
+        result.measured_fps,
+        result.time_to_first_frame.unwrap_or_default().as_secs_f64(),
+        result.image_name().unwrap_or("-"));
+    if let Some(error) = &result.error {
+        print!(" ({})", error);
+    }
+    println!();
+}
+
+/// Tiles the first frame of each mode, labelled with what was asked for and
+/// what was measured, and saves the sheet. The frames are moved out of the probe.
+fn save_sheet(device: &Device, probe: &mut DeviceProbe, options: &SheetOptions, output: &OutputOptions) -> Result<PathBuf> {
+    let tiles: Vec<Tile> = probe.results.iter_mut()
+        .map(|result| Tile {
+            image: result.thumbnail.take(),
+            label: tile_label(result)
+        })
+        .collect();
+    let image = contact_sheet(&tiles, options)?;
+    let size = image.size()?;
+    let info = FrameInfo {
+        device,
+        width: size.width as u32,
+        height: size.height as u32,
+        fourcc: 0,
+        sequence: 0,
+        timestamp: Local::now()
+    };
+    Ok(output.save(&image, &info)?)
+}
+
+/// Such as "1280x720 MJPG 29.97 fps", with the format the device chose where it
+/// differs from the one asked for, and any problem.
+fn tile_label(result: &ProbeResult) -> String {
+    let asked = &result.asked;
+    let mut label = format!("{}x{}", asked.width, asked.height);
+    if let Some(fourcc) = asked.fourcc {
+        label += &format!(" {}", fourcc_to_string(fourcc));
+    }
+    if result.time_to_first_frame.is_none() {
+        return label + ": " + result.error.as_deref().unwrap_or("no frames");
+    }
+    if result.actual_width != asked.width || result.actual_height != asked.height ||
+            asked.fourcc.is_some_and(|fourcc| fourcc != result.actual_fourcc) {
+        label += &format!(" as {}x{} {}", result.actual_width, result.actual_height, fourcc_to_string(result.actual_fourcc));
+    }
+    label += &format!(" {:.2} fps", result.measured_fps);
+    if let Some(problem) = &result.problem {
+        label += &format!(" ({})", problem.name());
+    }
+    label
+}
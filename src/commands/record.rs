@@ -0,0 +1,65 @@
+//! Records video from one device.
+use std::{
+    path::PathBuf,
+    time::Duration
+};
+use anyhow::{
+    bail,
+    Result
+};
+use crate::capture::{
+    record,
+    RecordLimit,
+    RecordOptions
+};
+use super::{
+    largest_mode,
+    parse_mode,
+    select_device,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs record --device ID|NUMBER|NAME [--mode WIDTHxHEIGHT]
+    [--seconds SECONDS | --frames FRAMES] [--segment MINUTES] [--codec FOURCC] [--ext EXTENSION]
+    [--dir DIRECTORY] [--name TEMPLATE]";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut options = RecordOptions::default();
+    let mut selector: Option<String> = None;
+    let mut mode: Option<(u32, u32)> = None;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--mode" => mode = Some(parse_mode(&args.value(&flag)?)?),
+            "--seconds" => options.limit = RecordLimit::Duration(Duration::from_secs_f64(args.parsed(&flag)?)),
+            "--frames" => options.limit = RecordLimit::Frames(args.parsed(&flag)?),
+            "--segment" => options.segment = Some(Duration::from_secs_f64(args.parsed::<f64>(&flag)? * 60.0)),
+            "--codec" => options.codec = args.value(&flag)?,
+            "--ext" => options.extension = args.value(&flag)?,
+            "--dir" => options.directory = PathBuf::from(args.value(&flag)?),
+            "--name" => options.template = args.value(&flag)?,
+            _ => return Err(args.unexpected(&flag))
+        }
+    }
+    let Some(selector) = selector else {
+        bail!("Which device?\n{}", USAGE);
+    };
+
+    let device = select_device(&selector)?;
+    (options.width, options.height) = mode.unwrap_or_else(|| largest_mode(&device));
+    println!("Recording {} at {}x{}", device.name, options.width, options.height);
+
+    let report = record(&device, &options)?;
+    for file in &report.files {
+        println!("  wrote {}", file.display());
+    }
+    println!("  {} frames in {:.1}s, {:.2} fps on average, {:.2} fps advertised",
+        report.frames_written, report.elapsed.as_secs_f64(), report.average_fps, report.nominal_fps);
+    println!("  written at {:.2} fps, measured from the first frames", report.written_fps);
+    println!("  {} frames dropped", report.dropped_frames);
+    if report.stopped_early {
+        println!("  the device stopped delivering frames before the end");
+    }
+    Ok(())
+}
@@ -0,0 +1,42 @@
+//! Streams a live preview of a device over HTTP, for aiming and focusing
+//! cameras that have no screen.
+use anyhow::{
+    bail,
+    Result
+};
+use crate::server::{
+    serve,
+    ServeOptions
+};
+use super::{
+    find_devices,
+    parse_mode,
+    select_device,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs serve --device ID|NUMBER|NAME [--mode WIDTHxHEIGHT] [--listen ADDRESS:PORT] [--quality QUALITY]
+Open http://ADDRESS:PORT/ in a browser for the preview. Also serves /stream.mjpg, /snapshot.jpg and /devices.";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut options = ServeOptions::default();
+    let mut selector: Option<String> = None;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--mode" => (options.width, options.height) = parse_mode(&args.value(&flag)?)?,
+            "--listen" => options.address = args.value(&flag)?,
+            "--quality" => options.quality = args.parsed::<u8>(&flag)?.min(100),
+            _ => return Err(args.unexpected(&flag))
+        }
+    }
+    let Some(selector) = selector else {
+        bail!("Which device?\n{}", USAGE);
+    };
+
+    let device = select_device(&selector)?;
+    println!("Serving {} at {}x{} on http://{}/", device.name, options.width, options.height, options.address);
+    serve(device, find_devices()?, &options)?;
+    Ok(())
+}
@@ -0,0 +1,166 @@
+//! Lists the devices and saves a frame from each at each supported frame size.
+use anyhow::Result;
+use opencv::{
+    core::Rect,
+    videoio::{
+        get_camera_backends,
+        get_backend_name
+    }
+};
+use crate::video_devices::{
+    redact_url,
+    Device,
+    FrameSizeType::{*, self}
+};
+use crate::capture::{
+    take_snap,
+    CaptureError,
+    CapturePolicy,
+    Combine,
+    HealthOptions,
+    OutputOptions,
+    WarmUp
+};
+use std::{
+    path::PathBuf,
+    time::Duration
+};
+use super::{
+    find_devices,
+    parse_crop,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs [snap] [--list] [--dir DIRECTORY] [--name TEMPLATE] [--format jpeg[:QUALITY]|png[:COMPRESSION]|webp[:QUALITY]|tiff]
+    [--discard FRAMES | --settle | --settle-exposure] [--average FRAMES | --median FRAMES] [--timeout SECONDS]
+    [--retries FRAMES] [--dark LEVEL] [--allow-frozen | --accept-bad-frames] [--crop X,Y,WIDTHxHEIGHT] [--high-quality]
+Black, solid colour, corrupt and frozen frames are retried up to --retries times, then reported.
+--crop keeps part of the picture, in the sensor's pixels where the device says what they are.
+--high-quality uses the driver's still mode where it has one (Linux).
+--list only lists the devices, without capturing anything.";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut output = OutputOptions::default();
+    let mut policy = CapturePolicy::default();
+    let mut list_only = false;
+    while let Some(flag) = args.next_flag() {
+        if flag == "--list" {
+            list_only = true;
+        } else if !parse_output_flag(&flag, &mut args, &mut output)? &&
+           !parse_policy_flag(&flag, &mut args, &mut policy)? {
+            return Err(args.unexpected(&flag));
+        }
+    }
+
+    let apis = get_camera_backends()?;
+    for be in &apis {
+        let name = get_backend_name(be)?;
+        println!("name: {}", name);
+    }
+
+    let mut sequence = 0;
+    for device in find_devices()? {
+        println!("opencv id: {:?}", device.opencv_id);
+        println!("device unique id: {:?}", device.unique_id);
+        println!("device name: {:?}", device.name);
+        if let Some(source) = &device.source {
+            println!("  source: {}", redact_url(source));
+            if device.frame_size_types.is_empty() && !list_only {
+                snap(&device, 0, 0, &output, &policy, next(&mut sequence))?;
+            }
+        }
+        for input in &device.inputs {
+            let status = input.status.iter().map(|status| status.name()).collect::<Vec<_>>().join(", ");
+            println!("  input {}: {:?}{} {}", input.index, input.name, if input.selected { " (selected)" } else { "" }, status);
+        }
+        if let Some(standard) = &device.standard {
+            println!("  standard: {}", standard.name);
+        }
+        if let Some(timings) = &device.dv_timings {
+            println!("  timings: {}", timings);
+        }
+        if let Some(crop) = &device.crop_bounds {
+            println!("  crop bounds: {}, default {}", to_crop(&crop.bounds), to_crop(&crop.default));
+        }
+        for fz in &device.frame_size_types {
+            println!("  fsz: {:?}", fz);
+            if list_only {
+                continue;
+            }
+            take_snaps(&device, fz, &output, &policy, &mut sequence)?;
+        }
+    }
+    Ok(())
+}
+
+/// Handles the flags for `OutputOptions`, returning false if the flag isn't one of them.
+pub fn parse_output_flag(flag: &str, args: &mut Args, output: &mut OutputOptions) -> Result<bool> {
+    match flag {
+        "--dir" => output.directory = PathBuf::from(args.value(flag)?),
+        "--name" => output.template = args.value(flag)?,
+        "--format" => output.format = args.parsed(flag)?,
+        _ => return Ok(false)
+    }
+    Ok(true)
+}
+
+/// Handles the flags for `CapturePolicy`, returning false if the flag isn't one of them.
+pub fn parse_policy_flag(flag: &str, args: &mut Args, policy: &mut CapturePolicy) -> Result<bool> {
+    match flag {
+        "--discard" => policy.warm_up = match args.parsed(flag)? {
+            0 => WarmUp::None,
+            frames => WarmUp::DiscardFrames(frames)
+        },
+        "--settle" => policy.warm_up = WarmUp::StableBrightness { tolerance: 1.0, stable_frames: 3 },
+        "--settle-exposure" => policy.warm_up = WarmUp::StableExposure { tolerance: 0.0, stable_frames: 3 },
+        "--average" => policy.combine = Combine::Average(args.parsed(flag)?),
+        "--median" => policy.combine = Combine::Median(args.parsed(flag)?),
+        "--timeout" => policy.first_frame_timeout = Duration::from_secs_f64(args.parsed(flag)?),
+        "--retries" => policy.bad_frame_retries = args.parsed(flag)?,
+        "--dark" => health(policy).dark_level = args.parsed(flag)?,
+        "--allow-frozen" => health(policy).detect_frozen = false,
+        "--accept-bad-frames" => policy.health = None,
+        "--crop" => policy.crop = Some(parse_crop(&args.value(flag)?)?),
+        "--high-quality" => policy.high_quality = true,
+        _ => return Ok(false)
+    }
+    Ok(true)
+}
+
+fn health(policy: &mut CapturePolicy) -> &mut HealthOptions {
+    policy.health.get_or_insert_with(HealthOptions::default)
+}
+
+fn take_snaps(device: &Device, fz: &FrameSizeType, output: &OutputOptions, policy: &CapturePolicy, sequence: &mut u32) -> Result<()> {
+    match *fz {
+        Discrete { width, height } => {
+            snap(device, width, height, output, policy, next(sequence))?;
+        },
+        Stepwise { max_width, max_height, min_width, min_height, .. } => {
+            snap(device, max_width, max_height, output, policy, next(sequence))?;
+            snap(device, min_width, min_height, output, policy, next(sequence))?;
+        }
+    };
+    Ok(())
+}
+
+/// Takes a snap, reporting bad frames rather than stopping.
+fn snap(device: &Device, width: u32, height: u32, output: &OutputOptions, policy: &CapturePolicy, sequence: u32) -> Result<()> {
+    match take_snap(device, width, height, output, policy, sequence) {
+        Err(CaptureError::BadFrame(problem)) => println!("  {}x{}: {}", width, height, problem),
+        Err(err @ CaptureError::CropOutsideFrame(..)) => println!("  {}x{}: {}", width, height, err),
+        result => { result?; }
+    }
+    Ok(())
+}
+
+/// A rectangle as --crop takes it.
+fn to_crop(rect: &Rect) -> String {
+    format!("{},{},{}x{}", rect.x, rect.y, rect.width, rect.height)
+}
+
+fn next(sequence: &mut u32) -> u32 {
+    *sequence += 1;
+    *sequence
+}
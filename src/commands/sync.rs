@@ -0,0 +1,101 @@
+//! Takes sets of frames from several devices at the same moment.
+use std::{
+    thread::sleep,
+    time::Duration
+};
+use anyhow::{
+    bail,
+    Result
+};
+use chrono::Local;
+use opencv::prelude::MatTraitConst;
+use crate::capture::{
+    FrameInfo,
+    MultiCapture,
+    OutputOptions,
+    SyncStrategy,
+    TimestampSource
+};
+use super::{
+    parse_mode,
+    select_device,
+    snap::parse_output_flag,
+    Args
+};
+
+const USAGE: &str = "Usage: opencv-example-rs sync --device ID|NUMBER|NAME --device ID|NUMBER|NAME ... [--mode WIDTHxHEIGHT]
+    [--strategy round-robin|wait-any] [--count SETS] [--interval SECONDS] [--discard FRAMES] [--timeout SECONDS]
+    [--dir DIRECTORY] [--name TEMPLATE] [--format FORMAT]
+wait-any only works with the V4L backend.";
+
+const DEFAULT_TEMPLATE: &str = "sync-{seq}-{name}";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut output = OutputOptions { template: String::from(DEFAULT_TEMPLATE), ..OutputOptions::default() };
+    let mut selectors = Vec::<String>::new();
+    let (mut width, mut height) = (640, 480);
+    let mut strategy = SyncStrategy::RoundRobin;
+    let mut count: u32 = 1;
+    let mut interval = Duration::ZERO;
+    let mut discard: u32 = 5;
+    let mut timeout = Duration::from_secs(2);
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selectors.push(args.value(&flag)?),
+            "--mode" => (width, height) = parse_mode(&args.value(&flag)?)?,
+            "--strategy" => strategy = match args.value(&flag)?.as_str() {
+                "round-robin" => SyncStrategy::RoundRobin,
+                "wait-any" => SyncStrategy::WaitAny,
+                other => bail!("Unknown strategy {}\n{}", other, USAGE)
+            },
+            "--count" => count = args.parsed(&flag)?,
+            "--interval" => interval = Duration::from_secs_f64(args.parsed(&flag)?),
+            "--discard" => discard = args.parsed(&flag)?,
+            "--timeout" => timeout = Duration::from_secs_f64(args.parsed(&flag)?),
+            _ => if !parse_output_flag(&flag, &mut args, &mut output)? {
+                return Err(args.unexpected(&flag));
+            }
+        }
+    }
+    if selectors.len() < 2 {
+        bail!("Which devices? Give at least two\n{}", USAGE);
+    }
+
+    let devices = selectors.iter()
+        .map(|selector| select_device(selector))
+        .collect::<Result<Vec<_>>>()?;
+    let mut cameras = MultiCapture::open(&devices, width, height, strategy)?;
+    cameras.discard(discard)?;
+
+    for sequence in 1..=count {
+        if sequence > 1 {
+            sleep(interval);
+        }
+        let timestamp = Local::now();
+        let set = cameras.capture(timeout)?;
+        println!("Set {}, skew {:.1} ms {}", sequence, set.skew().as_secs_f64() * 1000.0, match set.timestamp_source {
+            TimestampSource::OpenCv => "by the frame timestamps",
+            _ => "by when the frames were grabbed"
+        });
+        for (device, frame) in devices.iter().zip(&set.frames) {
+            let Some(frame) = frame else {
+                println!("  {}: no frame", device.name);
+                continue;
+            };
+            let size = frame.image.size()?;
+            let info = FrameInfo {
+                device,
+                width: size.width as u32,
+                height: size.height as u32,
+                fourcc: frame.fourcc,
+                sequence,
+                timestamp
+            };
+            let path = output.save(&frame.image, &info)?;
+            println!("  {} at +{:.1} ms", path.display(), frame.offset.as_secs_f64() * 1000.0);
+        }
+    }
+    cameras.release()?;
+    Ok(())
+}
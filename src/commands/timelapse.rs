@@ -0,0 +1,82 @@
+//! Takes stills from one device at regular intervals.
+use std::time::Duration;
+use anyhow::{
+    anyhow,
+    bail,
+    Result
+};
+use chrono::{
+    DateTime,
+    Local,
+    NaiveDateTime,
+    NaiveTime,
+    TimeZone
+};
+use crate::capture::{
+    run_timelapse,
+    TimelapseEvent,
+    TimelapseOptions
+};
+use super::{
+    largest_mode,
+    parse_mode,
+    select_device,
+    Args,
+    snap::{
+        parse_output_flag,
+        parse_policy_flag
+    }
+};
+
+const USAGE: &str = "Usage: opencv-example-rs timelapse --device ID|NUMBER|NAME --interval SECONDS [--mode WIDTHxHEIGHT]
+    [--start TIME] [--stop TIME] [--count SHOTS] [--keep-open]
+    [--dir DIRECTORY] [--name TEMPLATE] [--format FORMAT] [snap capture options]
+TIME is HH:MM today, or YYYY-MM-DDTHH:MM";
+
+pub fn run(args: Vec<String>) -> Result<()> {
+    let mut args = Args::new(args, USAGE);
+    let mut options = TimelapseOptions::default();
+    let mut selector: Option<String> = None;
+    let mut mode: Option<(u32, u32)> = None;
+    while let Some(flag) = args.next_flag() {
+        match flag.as_str() {
+            "--device" => selector = Some(args.value(&flag)?),
+            "--mode" => mode = Some(parse_mode(&args.value(&flag)?)?),
+            "--interval" => options.interval = Duration::from_secs_f64(args.parsed(&flag)?),
+            "--start" => options.start = Some(parse_time(&args.value(&flag)?)?),
+            "--stop" => options.stop = Some(parse_time(&args.value(&flag)?)?),
+            "--count" => options.max_count = Some(args.parsed(&flag)?),
+            "--keep-open" => options.keep_open = true,
+            _ => if !parse_output_flag(&flag, &mut args, &mut options.output)? &&
+                    !parse_policy_flag(&flag, &mut args, &mut options.policy)? {
+                return Err(args.unexpected(&flag));
+            }
+        }
+    }
+    let Some(selector) = selector else {
+        bail!("Which device?\n{}", USAGE);
+    };
+
+    let device = select_device(&selector)?;
+    (options.width, options.height) = mode.unwrap_or_else(|| largest_mode(&device));
+    println!("Time-lapse of {} at {}x{} every {:?}", device.name, options.width, options.height, options.interval);
+
+    let report = run_timelapse(&device, &options, |event| match event {
+        TimelapseEvent::Saved(path) => println!("  saved {}", path.display()),
+        TimelapseEvent::Failed(err) => println!("  failed: {}", err),
+        TimelapseEvent::Missed => println!("  missed a shot")
+    })?;
+    println!("{} saved, {} failed, {} missed", report.saved, report.failed, report.missed);
+    Ok(())
+}
+
+fn parse_time(time: &str) -> Result<DateTime<Local>> {
+    let naive = if let Ok(time_of_day) = NaiveTime::parse_from_str(time, "%H:%M") {
+        Local::now().date_naive().and_time(time_of_day)
+    } else {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M")?
+    };
+    Local.from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| anyhow!("{} doesn't exist in the local time zone", time))
+}
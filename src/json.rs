@@ -0,0 +1,75 @@
+//! Writes the little JSON the program produces, without pulling in a serializer.
+use std::fmt::Write;
+
+/// Builds a JSON object one field at a time, keeping the fields in order.
+#[derive(Default)]
+pub struct JsonObject {
+    fields: Vec<String>
+}
+
+impl JsonObject {
+    pub fn new() -> Self {
+        JsonObject::default()
+    }
+
+    pub fn str(self, key: &str, value: &str) -> Self {
+        self.raw(key, quote(value))
+    }
+
+    pub fn opt_str(self, key: &str, value: Option<&str>) -> Self {
+        self.raw(key, value.map(quote).unwrap_or_else(|| String::from("null")))
+    }
+
+    /// Numbers that JSON can't represent, infinities and NaN, are written as null.
+    pub fn num(self, key: &str, value: f64) -> Self {
+        self.opt_num(key, Some(value))
+    }
+
+    pub fn opt_num(self, key: &str, value: Option<f64>) -> Self {
+        let value = match value {
+            Some(value) if value.is_finite() => value.to_string(),
+            _ => String::from("null")
+        };
+        self.raw(key, value)
+    }
+
+    pub fn bool(self, key: &str, value: bool) -> Self {
+        self.raw(key, value.to_string())
+    }
+
+    /// Adds a value that is already JSON, such as another object or an array.
+    pub fn raw(mut self, key: &str, json: String) -> Self {
+        self.fields.push(format!("{}: {}", quote(key), json));
+        self
+    }
+
+    pub fn build(self) -> String {
+        format!("{{{}}}", self.fields.join(", "))
+    }
+}
+
+/// Joins values that are already JSON into an array.
+pub fn array<I>(items: I) -> String where
+    I: IntoIterator<Item = String>
+{
+    format!("[{}]", items.into_iter().collect::<Vec<_>>().join(", "))
+}
+
+/// Quotes and escapes a string.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => { let _ = write!(quoted, "\\u{:04x}", c as u32); },
+            c => quoted.push(c)
+        }
+    }
+    quoted.push('"');
+    quoted
+}
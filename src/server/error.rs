@@ -0,0 +1,18 @@
+//! The errors returned while serving previews.
+use std::io;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, ServerError>;
+
+#[derive(Debug, Error)]
+pub enum ServerError {
+    /// Usually because something else is listening on the port.
+    #[error("failed to listen on {address}: {source}")]
+    Bind {
+        address: String,
+        source: io::Error
+    },
+
+    #[error("{0}")]
+    Io(#[from] io::Error)
+}
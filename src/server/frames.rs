@@ -0,0 +1,116 @@
+//! Captures from the device on its own thread, keeping the latest frame as a
+//! JPEG for however many clients are watching.
+use std::{
+    sync::{
+        Arc,
+        Condvar,
+        Mutex
+    },
+    thread::{
+        sleep,
+        spawn
+    },
+    time::{
+        Duration,
+        Instant
+    }
+};
+use opencv::{
+    imgcodecs::{
+        imencode,
+        ImwriteFlags
+    },
+    core::Vector
+};
+use crate::{
+    capture::{
+        open_capture,
+        read_frame,
+        CaptureError
+    },
+    video_devices::Device
+};
+
+/// How long to wait before reopening a device that stopped delivering.
+const REOPEN_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Clone)]
+pub struct Frame {
+    pub jpeg: Arc<Vec<u8>>,
+    /// Counts up from 1 with each frame captured.
+    pub sequence: u64
+}
+
+#[derive(Default)]
+struct State {
+    frame: Option<Frame>,
+    /// Why there are no new frames, while there aren't.
+    error: Option<String>
+}
+
+#[derive(Default)]
+pub struct LatestFrame {
+    state: Mutex<State>,
+    updated: Condvar
+}
+
+impl LatestFrame {
+    fn publish(&self, jpeg: Vec<u8>) {
+        let mut state = self.state.lock().unwrap();
+        let sequence = state.frame.as_ref().map_or(1, |frame| frame.sequence + 1);
+        state.frame = Some(Frame { jpeg: Arc::new(jpeg), sequence });
+        state.error = None;
+        self.updated.notify_all();
+    }
+
+    fn fail(&self, error: String) {
+        self.state.lock().unwrap().error = Some(error);
+    }
+
+    /// Waits for a frame later than `after`, returning the reason if none comes in time.
+    pub fn wait_after(&self, after: u64, timeout: Duration) -> Result<Frame, String> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.state.lock().unwrap();
+        loop {
+            if let Some(frame) = state.frame.as_ref().filter(|frame| frame.sequence > after) {
+                return Ok(frame.clone());
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(state.error.clone().unwrap_or_else(|| String::from("no frames yet")));
+            }
+            state = self.updated.wait_timeout(state, remaining).unwrap().0;
+        }
+    }
+}
+
+/// Starts capturing on a new thread that runs for as long as the program does,
+/// reopening the device whenever it stops delivering.
+pub fn start_capture(device: Device, width: u32, height: u32, quality: u8) -> Arc<LatestFrame> {
+    let latest = Arc::new(LatestFrame::default());
+    let publisher = latest.clone();
+    spawn(move || loop {
+        let error = match capture_frames(&device, width, height, quality, &publisher) {
+            Ok(()) => CaptureError::NoFrames(device.name.clone()),
+            Err(err) => err
+        };
+        publisher.fail(error.to_string());
+        sleep(REOPEN_DELAY);
+    });
+    latest
+}
+
+/// Publishes frames until the device stops delivering them.
+fn capture_frames(device: &Device, width: u32, height: u32, quality: u8, latest: &LatestFrame) -> Result<(), CaptureError> {
+    let mut vc = open_capture(device, width, height)?;
+    let mut params = Vector::<i32>::new();
+    params.push(ImwriteFlags::IMWRITE_JPEG_QUALITY as i32);
+    params.push(quality as i32);
+    let mut jpeg = Vector::<u8>::new();
+    while let Some(frame) = read_frame(&mut vc, Duration::from_secs(5))? {
+        imencode(".jpg", &frame, &mut jpeg, &params)?;
+        latest.publish(jpeg.to_vec());
+    }
+    vc.release()?;
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Just enough HTTP/1.1 to answer a browser or curl.
+use std::{
+    io::{
+        Read,
+        Write
+    },
+    net::TcpStream
+};
+use super::Result;
+
+/// Requests with longer headers than this are refused.
+const MAX_HEADER_BYTES: usize = 8192;
+
+pub struct Request {
+    pub method: String,
+    /// Without any query string.
+    pub path: String
+}
+
+/// Reads the request line and headers, returning `None` if the client hung up
+/// or sent something that isn't HTTP. The body, if any, is ignored.
+pub fn read_request(stream: &mut TcpStream) -> Result<Option<Request>> {
+    let mut header = Vec::new();
+    let mut buffer = [0u8; 1024];
+    while !header.windows(4).any(|end| end == b"\r\n\r\n") {
+        let read = stream.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(None);
+        }
+        header.extend_from_slice(&buffer[..read]);
+        if header.len() > MAX_HEADER_BYTES {
+            return Ok(None);
+        }
+    }
+
+    let header = String::from_utf8_lossy(&header);
+    let mut parts = header.lines().next().unwrap_or_default().split_whitespace();
+    let (Some(method), Some(target), Some(version)) = (parts.next(), parts.next(), parts.next()) else {
+        return Ok(None);
+    };
+    if !version.starts_with("HTTP/") {
+        return Ok(None);
+    }
+    let path = target.split(['?', '#']).next().unwrap_or_default();
+    Ok(Some(Request { method: method.to_string(), path: path.to_string() }))
+}
+
+/// Writes a complete response and asks the client to close the connection.
+pub fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> Result<()> {
+    write!(stream, "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        status, content_type, body.len())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+    Ok(())
+}
+
+/// Starts a `multipart/x-mixed-replace` response, which browsers show as each
+/// part replacing the one before.
+pub fn start_multipart(stream: &mut TcpStream, boundary: &str) -> Result<()> {
+    write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: multipart/x-mixed-replace; boundary={}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+        boundary)?;
+    Ok(())
+}
+
+pub fn write_part(stream: &mut TcpStream, boundary: &str, content_type: &str, body: &[u8]) -> Result<()> {
+    write!(stream, "--{}\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n", boundary, content_type, body.len())?;
+    stream.write_all(body)?;
+    stream.write_all(b"\r\n")?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+    use super::*;
+
+    /// Both ends of a connection, the client's first.
+    fn connect() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (client, server)
+    }
+
+    fn request(text: &[u8]) -> Option<Request> {
+        let (mut client, mut server) = connect();
+        client.write_all(text).unwrap();
+        drop(client);
+        read_request(&mut server).unwrap()
+    }
+
+    #[test]
+    fn requests_are_read_without_the_query() {
+        let request = request(b"GET /snapshot.jpg?t=123#top HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/snapshot.jpg");
+    }
+
+    #[test]
+    fn incomplete_and_oversized_requests_are_dropped() {
+        assert!(request(b"GET / HTTP/1.1\r\nHost: localhost\r\n").is_none());
+        assert!(request(b"GET /\r\n\r\n").is_none());
+        assert!(request(b"GET / SMTP\r\n\r\n").is_none());
+        let long = format!("GET / HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "x".repeat(MAX_HEADER_BYTES));
+        assert!(request(long.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn parts_follow_the_multipart_header() {
+        let (mut client, mut server) = connect();
+        start_multipart(&mut server, "frame").unwrap();
+        write_part(&mut server, "frame", "image/jpeg", b"first").unwrap();
+        write_part(&mut server, "frame", "image/jpeg", b"second!").unwrap();
+        drop(server);
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(response, concat!(
+            "HTTP/1.1 200 OK\r\n",
+            "Content-Type: multipart/x-mixed-replace; boundary=frame\r\n",
+            "Cache-Control: no-store\r\n",
+            "Connection: close\r\n",
+            "\r\n",
+            "--frame\r\nContent-Type: image/jpeg\r\nContent-Length: 5\r\n\r\nfirst\r\n",
+            "--frame\r\nContent-Type: image/jpeg\r\nContent-Length: 7\r\n\r\nsecond!\r\n"));
+    }
+}
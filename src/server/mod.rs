@@ -0,0 +1,15 @@
+mod error;
+pub use error::{
+    Result,
+    ServerError
+};
+
+mod frames;
+
+mod http;
+
+mod preview;
+pub use preview::{
+    serve,
+    ServeOptions
+};
@@ -0,0 +1,324 @@
+//! Serves a live preview of one device, and the list of devices, over HTTP.
+use std::{
+    net::{
+        TcpListener,
+        TcpStream
+    },
+    sync::Arc,
+    thread::spawn,
+    time::Duration
+};
+use opencv::core::Rect;
+use crate::{
+    capture::fourcc_to_string,
+    json::{
+        array,
+        quote,
+        JsonObject
+    },
+    video_devices::{
+        redact_url,
+        CropBounds,
+        Device,
+        FrameSizeType,
+        VideoFormat,
+        VideoInput
+    }
+};
+use super::{
+    frames::{
+        start_capture,
+        LatestFrame
+    },
+    http::{
+        read_request,
+        respond,
+        start_multipart,
+        write_part,
+        Request
+    },
+    Result,
+    ServerError
+};
+
+const BOUNDARY: &str = "frame";
+
+/// Clients that stop reading for this long are dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct ServeOptions {
+    /// Such as `0.0.0.0:8080` to listen on every interface.
+    pub address: String,
+    pub width: u32,
+    pub height: u32,
+    /// JPEG quality 0 to 100.
+    pub quality: u8,
+    /// How long a client waits for a frame before being told there aren't any.
+    pub frame_timeout: Duration
+}
+
+impl Default for ServeOptions {
+    fn default() -> Self {
+        ServeOptions {
+            address: String::from("0.0.0.0:8080"),
+            width: 640,
+            height: 480,
+            quality: 80,
+            frame_timeout: Duration::from_secs(5)
+        }
+    }
+}
+
+/// Whatever each connection needs, shared between their threads.
+struct Shared {
+    device: Device,
+    /// Listed when the server started, rather than opening every device again for each request.
+    devices: Vec<Device>,
+    latest: Arc<LatestFrame>,
+    frame_timeout: Duration
+}
+
+/// Captures from the device and answers requests until the program is stopped,
+/// listing the devices given at `/devices`. Each client is served on its own thread.
+pub fn serve(device: Device, devices: Vec<Device>, options: &ServeOptions) -> Result<()> {
+    let listener = TcpListener::bind(&options.address)
+        .map_err(|source| ServerError::Bind { address: options.address.clone(), source })?;
+    let shared = Arc::new(Shared {
+        latest: start_capture(device.clone(), options.width, options.height, options.quality),
+        device,
+        devices,
+        frame_timeout: options.frame_timeout
+    });
+    accept(listener, shared);
+    Ok(())
+}
+
+/// Serves each connection on its own thread.
+fn accept(listener: TcpListener, shared: Arc<Shared>) {
+    // A failed connection is the client's problem, not a reason to stop serving.
+    for stream in listener.incoming().flatten() {
+        let shared = shared.clone();
+        spawn(move || {
+            let _ = handle_connection(stream, &shared);
+        });
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, shared: &Shared) -> Result<()> {
+    stream.set_read_timeout(Some(WRITE_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let Some(Request { method, path }) = read_request(&mut stream)? else {
+        return Ok(());
+    };
+    if method != "GET" {
+        return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"Only GET is supported\n");
+    }
+    match path.as_str() {
+        "/" => respond(&mut stream, "200 OK", "text/html; charset=utf-8", index_page(&shared.device).as_bytes()),
+        "/stream.mjpg" => stream_frames(&mut stream, shared),
+        "/snapshot.jpg" => match shared.latest.wait_after(0, shared.frame_timeout) {
+            Ok(frame) => respond(&mut stream, "200 OK", "image/jpeg", &frame.jpeg),
+            Err(reason) => unavailable(&mut stream, &reason)
+        },
+        "/devices" => respond(&mut stream, "200 OK", "application/json",
+            (array(shared.devices.iter().map(device_json)) + "\n").as_bytes()),
+        _ => respond(&mut stream, "404 Not Found", "text/plain", b"Try /, /stream.mjpg, /snapshot.jpg or /devices\n")
+    }
+}
+
+/// Sends each new frame until the client goes away.
+fn stream_frames(stream: &mut TcpStream, shared: &Shared) -> Result<()> {
+    let mut frame = match shared.latest.wait_after(0, shared.frame_timeout) {
+        Ok(frame) => frame,
+        Err(reason) => return unavailable(stream, &reason)
+    };
+    start_multipart(stream, BOUNDARY)?;
+    loop {
+        write_part(stream, BOUNDARY, "image/jpeg", &frame.jpeg)?;
+        // While the device is being reopened the last frame is sent again now and then,
+        // rather than ending the stream, which the browser would only give up on.
+        // Writing it is also how we find out the client has gone.
+        if let Ok(next) = shared.latest.wait_after(frame.sequence, shared.frame_timeout) {
+            frame = next;
+        }
+    }
+}
+
+fn unavailable(stream: &mut TcpStream, reason: &str) -> Result<()> {
+    respond(stream, "503 Service Unavailable", "text/plain", format!("{}\n", reason).as_bytes())
+}
+
+fn index_page(device: &Device) -> String {
+    let name = device.name.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    format!("<!DOCTYPE html>
+<html>
+<head><title>{name}</title></head>
+<body style=\"margin: 0; background: #202020; color: #e0e0e0; font-family: sans-serif\">
+<p style=\"margin: 8px\">{name} &middot; <a href=\"/snapshot.jpg\" style=\"color: inherit\">snapshot</a> &middot; <a href=\"/devices\" style=\"color: inherit\">devices</a></p>
+<img src=\"/stream.mjpg\" style=\"max-width: 100%\" alt=\"Live preview\">
+</body>
+</html>
+")
+}
+
+fn device_json(device: &Device) -> String {
+    JsonObject::new()
+        .num("opencv_id", device.opencv_id as f64)
+        .str("unique_id", &device.unique_id)
+        .str("name", &device.name)
+        .opt_str("source", device.source.as_deref().map(redact_url).as_deref())
+        .raw("frame_sizes", array(device.frame_size_types.iter().map(frame_size_json)))
+        .raw("formats", array(device.formats.iter().map(format_json)))
+        .raw("inputs", array(device.inputs.iter().map(input_json)))
+        .opt_str("standard", device.standard.as_ref().map(|standard| standard.name.as_str()))
+        .opt_str("dv_timings", device.dv_timings.as_ref().map(|timings| timings.to_string()).as_deref())
+        .raw("crop_bounds", device.crop_bounds.as_ref().map_or_else(|| String::from("null"), crop_bounds_json))
+        .build()
+}
+
+fn crop_bounds_json(crop: &CropBounds) -> String {
+    JsonObject::new()
+        .raw("bounds", rect_json(&crop.bounds))
+        .raw("default", rect_json(&crop.default))
+        .build()
+}
+
+fn rect_json(rect: &Rect) -> String {
+    JsonObject::new()
+        .num("x", rect.x as f64)
+        .num("y", rect.y as f64)
+        .num("width", rect.width as f64)
+        .num("height", rect.height as f64)
+        .build()
+}
+
+fn input_json(input: &VideoInput) -> String {
+    JsonObject::new()
+        .num("index", input.index as f64)
+        .str("name", &input.name)
+        .bool("tuner", input.tuner)
+        .bool("selected", input.selected)
+        .raw("status", array(input.status.iter().map(|status| quote(status.name()))))
+        .raw("standards", array(input.standards.iter().map(|standard| quote(&standard.name))))
+        .build()
+}
+
+fn format_json(format: &VideoFormat) -> String {
+    JsonObject::new()
+        .str("fourcc", &fourcc_to_string(format.fourcc))
+        .raw("frame_size", frame_size_json(&format.frame_size_type))
+        .raw("frame_rates", array(format.frame_rates.iter().map(|fps| fps.to_string())))
+        .build()
+}
+
+fn frame_size_json(frame_size: &FrameSizeType) -> String {
+    match *frame_size {
+        FrameSizeType::Discrete { width, height } => JsonObject::new()
+            .str("type", "discrete")
+            .num("width", width as f64)
+            .num("height", height as f64)
+            .build(),
+        FrameSizeType::Stepwise { min_width, max_width, step_width, min_height, max_height, step_height } => JsonObject::new()
+            .str("type", "stepwise")
+            .num("min_width", min_width as f64)
+            .num("max_width", max_width as f64)
+            .num("step_width", step_width as f64)
+            .num("min_height", min_height as f64)
+            .num("max_height", max_height as f64)
+            .num("step_height", step_height as f64)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{
+            Read,
+            Write
+        },
+        net::SocketAddr
+    };
+    use crate::video_devices::source_device;
+    use super::*;
+
+    /// Serves the first test pattern, without capturing from it, on a port of its own.
+    fn start_server() -> SocketAddr {
+        let device = source_device("test://1", None);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let shared = Arc::new(Shared {
+            devices: vec![device.clone()],
+            device,
+            latest: Arc::new(LatestFrame::default()),
+            frame_timeout: Duration::from_millis(100)
+        });
+        spawn(move || accept(listener, shared));
+        address
+    }
+
+    fn send(address: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    fn get(address: SocketAddr, path: &str) -> String {
+        send(address, &format!("GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", path))
+    }
+
+    #[test]
+    fn unknown_paths_are_not_found() {
+        let address = start_server();
+        let response = get(address, "/nothing-here");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"), "{}", response);
+        assert!(response.ends_with("Try /, /stream.mjpg, /snapshot.jpg or /devices\n"), "{}", response);
+    }
+
+    #[test]
+    fn only_get_is_allowed() {
+        let address = start_server();
+        let response = send(address, "POST /devices HTTP/1.1\r\nContent-Length: 0\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"), "{}", response);
+    }
+
+    #[test]
+    fn query_strings_are_ignored() {
+        let address = start_server();
+        let response = get(address, "/?refresh=1");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert!(response.contains("<title>Test pattern 1</title>"), "{}", response);
+    }
+
+    #[test]
+    fn requests_that_are_not_http_are_dropped() {
+        let address = start_server();
+        assert_eq!(send(address, "HELLO\r\n\r\n"), "");
+    }
+
+    #[test]
+    fn devices_are_listed_as_json() {
+        let address = start_server();
+        let response = get(address, "/devices");
+        let (header, body) = response.split_once("\r\n\r\n").unwrap();
+        assert!(header.starts_with("HTTP/1.1 200 OK\r\n"), "{}", header);
+        assert!(header.contains("\r\nContent-Type: application/json\r\n"), "{}", header);
+        assert!(header.contains(&format!("\r\nContent-Length: {}\r\n", body.len())), "{}", header);
+        assert!(body.starts_with('[') && body.ends_with("]\n"), "{}", body);
+        assert!(body.contains(r#"{"opencv_id": -1, "unique_id": "test-pattern-1", "name": "Test pattern 1", "source": "test://1", "#), "{}", body);
+        assert!(body.contains(r#"{"type": "discrete", "width": 1920, "height": 1080}"#), "{}", body);
+        assert!(body.contains(r#"{"fourcc": "BGR3", "frame_size": {"type": "discrete", "width": 640, "height": 480}, "frame_rates": [60, 30, 15]}"#), "{}", body);
+    }
+
+    #[test]
+    fn without_frames_the_stream_and_snapshot_are_unavailable() {
+        let address = start_server();
+        for path in ["/stream.mjpg", "/snapshot.jpg"] {
+            let response = get(address, path);
+            assert!(response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"), "{}", response);
+            assert!(response.ends_with("\r\n\r\nno frames yet\n"), "{}", response);
+        }
+    }
+}
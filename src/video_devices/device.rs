@@ -1,15 +1,142 @@
 use std::vec::Vec;
 use std::cmp::PartialEq;
+use std::fmt;
+use opencv::core::Rect;
 
+#[derive(Clone)]
 pub struct Device {
     pub opencv_id: i32,
     pub unique_id: String,
     pub name: String,
-    pub frame_size_types: Vec<FrameSizeType>
+    pub frame_size_types: Vec<FrameSizeType>,
+    /// Each pixel format with each frame size it's offered at.
+    pub formats: Vec<VideoFormat>,
+    /// For network cameras, video files and image sequences, the URL or path
+    /// OpenCV opens instead of `opencv_id`. For test patterns, a `test://` URL.
+    pub source: Option<String>,
+    /// The inputs of capture cards and TV tuners, such as composite, S-Video
+    /// and HDMI. Webcams have just the one. Only listed for v4l2 devices.
+    pub inputs: Vec<VideoInput>,
+    /// The analogue TV standard the selected input is set to, if it has one.
+    pub standard: Option<VideoStandard>,
+    /// The timings of the signal on the selected input of HDMI and DVI
+    /// receivers, if there is a stable one.
+    pub dv_timings: Option<DvTimings>,
+    /// The area of the sensor that can be cropped to, for devices that can crop.
+    pub crop_bounds: Option<CropBounds>
 }
 
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct CropBounds {
+    /// All of the picture that can be captured, in the sensor's pixels.
+    pub bounds: Rect,
+    /// The area captured when not cropping, usually all of it.
+    pub default: Rect
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct VideoInput {
+    /// The driver's number for the input, starting at 0.
+    pub index: u32,
+    pub name: String,
+    pub tuner: bool,
+    /// Whether it's the input being captured from.
+    pub selected: bool,
+    /// What's wrong with the signal. Drivers only say for the selected input.
+    pub status: Vec<InputStatus>,
+    /// The analogue standards it can receive. Empty for digital inputs.
+    pub standards: Vec<VideoStandard>
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct VideoStandard {
+    /// The v4l2 standard id, a set of bits for the variants it covers.
+    pub id: u64,
+    pub name: String,
+    pub frame_rate: f64,
+    pub lines: u32
+}
+
+/// The resolution and rate of a digital video signal, such as HDMI.
 #[derive(PartialEq)]
 #[derive(Debug)]
+#[derive(Clone)]
+pub struct DvTimings {
+    pub width: u32,
+    pub height: u32,
+    pub interlaced: bool,
+    /// Pixels per second, counting the blanking around the picture.
+    pub pixel_clock: u64,
+    pub frame_rate: f64
+}
+
+impl fmt::Display for DvTimings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}x{}{}{:.2}", self.width, self.height, if self.interlaced { 'i' } else { 'p' }, self.frame_rate)
+    }
+}
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum InputStatus {
+    NoPower,
+    NoSignal,
+    NoColour,
+    NoHorizontalLock,
+    ColourKill,
+    NoVerticalLock,
+    NoStandardLock,
+    NoSync,
+    NoCarrier,
+    Macrovision,
+    NoAccess,
+    /// The signal is from a VCR, so its timing wanders.
+    Vcr
+}
+
+impl InputStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InputStatus::NoPower => "no power",
+            InputStatus::NoSignal => "no signal",
+            InputStatus::NoColour => "no colour",
+            InputStatus::NoHorizontalLock => "no horizontal lock",
+            InputStatus::ColourKill => "colour kill",
+            InputStatus::NoVerticalLock => "no vertical lock",
+            InputStatus::NoStandardLock => "no standard lock",
+            InputStatus::NoSync => "no sync",
+            InputStatus::NoCarrier => "no carrier",
+            InputStatus::Macrovision => "macrovision",
+            InputStatus::NoAccess => "no access",
+            InputStatus::Vcr => "vcr"
+        }
+    }
+}
+
+impl fmt::Display for InputStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct VideoFormat {
+    /// The four character code as OpenCV's `CAP_PROP_FOURCC` gives it.
+    pub fourcc: i32,
+    pub frame_size_type: FrameSizeType,
+    /// Frames per second the device offers at this size, fastest first.
+    /// Empty if it doesn't say.
+    pub frame_rates: Vec<f64>
+}
+
+#[derive(PartialEq)]
+#[derive(Debug)]
+#[derive(Clone)]
 pub enum FrameSizeType {
     Discrete {
         width: u32,
@@ -25,6 +152,28 @@ pub enum FrameSizeType {
     }
 }
 
+impl Device {
+    /// The frame sizes worth trying: every discrete size, and the largest
+    /// and smallest of each stepwise range. Sources opened by URL or path
+    /// have the one mode 0x0, whatever size they deliver.
+    pub fn modes(&self) -> Vec<(u32, u32)> {
+        if self.source.is_some() && self.frame_size_types.is_empty() {
+            return vec![(0, 0)];
+        }
+        let mut modes = Vec::new();
+        for fst in &self.frame_size_types {
+            match *fst {
+                FrameSizeType::Discrete { width, height } => modes.push((width, height)),
+                FrameSizeType::Stepwise { max_width, max_height, min_width, min_height, .. } => {
+                    modes.push((max_width, max_height));
+                    modes.push((min_width, min_height));
+                }
+            }
+        }
+        modes
+    }
+}
+
 pub fn sort_devices(devices: &mut Vec<Device>) {
     devices.sort_by(
         |d1, d2| d1.unique_id
@@ -32,6 +181,18 @@ pub fn sort_devices(devices: &mut Vec<Device>) {
         );
 }
 
+/// The distinct frame sizes of all the formats, largest first.
+pub fn to_frame_size_types(formats: &[VideoFormat]) -> Vec<FrameSizeType> {
+    let mut types = Vec::<FrameSizeType>::new();
+    for format in formats {
+        if !types.contains(&format.frame_size_type) {
+            types.push(format.frame_size_type.clone());
+        }
+    }
+    sort_frame_size_types(&mut types);
+    types
+}
+
 pub fn sort_frame_size_types(frame_size_types: &mut Vec<FrameSizeType>) {
     frame_size_types.sort_by(
         |fst1, fst2| frame_area(fst1)
@@ -0,0 +1,143 @@
+//! The errors returned while finding video devices and streaming from them.
+use std::{
+    io,
+    path::PathBuf
+};
+use thiserror::Error;
+#[cfg(target_os = "linux")]
+use nix::errno::Errno;
+
+pub type Result<T> = std::result::Result<T, VideoDeviceError>;
+
+#[derive(Debug, Error)]
+pub enum VideoDeviceError {
+    /// Reading a directory or opening a device file failed.
+    #[error("{path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: io::Error
+    },
+
+    /// A /dev/v4l symlink, or the device file it points at, is not in the expected form.
+    #[error("unexpected device path {0}")]
+    InvalidPath(PathBuf),
+
+    /// An ioctl on a v4l2 device failed.
+    #[cfg(target_os = "linux")]
+    #[error("{ioctl} failed: {errno}")]
+    Ioctl {
+        ioctl: &'static str,
+        errno: Errno
+    },
+
+    /// A Media Foundation or COM call failed with an HRESULT.
+    #[cfg(target_os = "windows")]
+    #[error("{0}")]
+    Windows(#[from] windows::core::Error),
+
+    /// Media Foundation reported success without returning the object asked for.
+    #[cfg(target_os = "windows")]
+    #[error("Media Foundation did not return {0}")]
+    MissingObject(&'static str),
+
+    /// Media Foundation returned a string that isn't valid UTF-16.
+    #[cfg(target_os = "windows")]
+    #[error("{0}")]
+    InvalidString(#[from] std::string::FromUtf16Error),
+
+    #[error("unknown frame size type {0}")]
+    UnknownFrameSizeType(u32),
+
+    /// There's no longer a device file for the device's unique id, usually because
+    /// it has been unplugged.
+    #[cfg(target_os = "linux")]
+    #[error("device {0} not found")]
+    NotFound(String),
+
+    /// The device doesn't support streaming with memory mapped buffers, or
+    /// being read from where that was tried instead.
+    #[cfg(target_os = "linux")]
+    #[error("{0} does not support streaming")]
+    StreamingUnsupported(PathBuf),
+
+    /// The source on an HDMI or similar input changed resolution while streaming.
+    /// The timings and format need setting again before streaming will work.
+    #[cfg(target_os = "linux")]
+    #[error("the source on {0} changed")]
+    SourceChanged(PathBuf),
+
+    /// None of the device's pixel formats can be converted to an image.
+    #[cfg(target_os = "linux")]
+    #[error("{0} has no pixel format that can be converted")]
+    NoSupportedFormat(PathBuf),
+
+    /// A line of a recorded v4l2 fixture file isn't in the expected form.
+    #[cfg(target_os = "linux")]
+    #[error("{path} line {line}: {reason}")]
+    InvalidFixture {
+        path: PathBuf,
+        line: usize,
+        reason: &'static str
+    },
+
+    /// An encoder's pixel format isn't one images can be converted to.
+    #[cfg(target_os = "linux")]
+    #[error("images can't be converted to {0}")]
+    UnencodablePixelFormat(String),
+
+    /// A codec device doesn't encode to, or decode from, the format asked for.
+    #[cfg(target_os = "linux")]
+    #[error("{0} does not code {1}")]
+    UnsupportedCodecFormat(PathBuf, String),
+
+    /// A frame given to a codec device is bigger than its buffers.
+    #[cfg(target_os = "linux")]
+    #[error("a {0} byte frame is too big for {1}")]
+    FrameTooLarge(usize, PathBuf),
+
+    /// A frame couldn't be decoded, such as a truncated JPEG.
+    #[cfg(target_os = "linux")]
+    #[error("failed to decode {0} frame")]
+    UndecodableFrame(String),
+
+    #[cfg(target_os = "linux")]
+    #[error("{0}")]
+    OpenCv(#[from] opencv::Error)
+}
+
+impl VideoDeviceError {
+    /// The device is being used by something else, so trying again later may work.
+    pub fn is_busy(&self) -> bool {
+        match self {
+            #[cfg(target_os = "linux")]
+            VideoDeviceError::Io { source, .. } => source.raw_os_error() == Some(Errno::EBUSY as i32),
+            #[cfg(target_os = "linux")]
+            VideoDeviceError::Ioctl { errno, .. } => *errno == Errno::EBUSY,
+            #[cfg(target_os = "windows")]
+            VideoDeviceError::Windows(err) =>
+                err.code() == windows::Win32::Media::MediaFoundation::MF_E_VIDEO_RECORDING_DEVICE_LOCKED,
+            _ => false
+        }
+    }
+
+    /// We aren't allowed to open the device. On Linux this usually means the user
+    /// isn't in the `video` group, on Windows that camera access is turned off in
+    /// the privacy settings.
+    pub fn is_permission_denied(&self) -> bool {
+        match self {
+            VideoDeviceError::Io { source, .. } => source.kind() == io::ErrorKind::PermissionDenied,
+            #[cfg(target_os = "linux")]
+            VideoDeviceError::Ioctl { errno, .. } => *errno == Errno::EACCES || *errno == Errno::EPERM,
+            #[cfg(target_os = "windows")]
+            VideoDeviceError::Windows(err) =>
+                err.code() == windows::Win32::Foundation::E_ACCESSDENIED,
+            _ => false
+        }
+    }
+
+    /// The driver doesn't have the ioctl at all.
+    #[cfg(target_os = "linux")]
+    pub fn is_unsupported(&self) -> bool {
+        matches!(self, VideoDeviceError::Ioctl { errno: Errno::ENOTTY, .. })
+    }
+}
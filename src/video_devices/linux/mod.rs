@@ -1,15 +1,31 @@
-use anyhow::Result;
 use std::vec::Vec;
 use super::Device;
 use super::FrameSizeType;
+use super::Result;
+use super::VideoDeviceError;
+use super::VideoFormat;
 use super::device::sort_devices;
+use super::sources::add_source_devices;
+use super::test_pattern::add_test_pattern_devices;
 
 mod v4l2;
+pub use v4l2::{
+    allocate_dmabufs,
+    find_codec_devices,
+    CodecKind,
+    CodecSession,
+    Control,
+    DeviceEvent,
+    EventKind,
+    V4l2Device
+};
 
 pub fn find_capture_devices() -> Result<Vec<Device>> {
     let mut devices = Vec::<Device>::new();
 
     v4l2::add_capture_devices(&mut devices)?;
+    add_source_devices(&mut devices)?;
+    add_test_pattern_devices(&mut devices);
 
     // gphoto2 - would be good for the supported cameras
     // see http://gphoto.org/proj/libgphoto2/support.php
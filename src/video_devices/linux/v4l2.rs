@@ -13,14 +13,14 @@
  * https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/dev-capture.html
  * https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/user-func.html
  */
-use anyhow::{
-    Result,
-    Error
-};
 use nix::errno::Errno;
 use std::{
+    env,
     mem,
-    path::PathBuf,
+    path::{
+        Path,
+        PathBuf
+    },
     fs::{
         read_dir,
         ReadDir,
@@ -28,23 +28,86 @@ use std::{
         File
     },
     os::fd::{
-        IntoRawFd,
+        AsRawFd,
         RawFd
     },
     ffi::CStr,
     vec::Vec,
-    io::ErrorKind
+    io::ErrorKind,
+    thread::sleep,
+    time::Duration
 };
 use opencv::videoio::CAP_V4L;
 use super::{
     Device,
-    FrameSizeType
+    FrameSizeType,
+    Result,
+    VideoDeviceError,
+    VideoFormat
+};
+use super::super::device::{
+    to_frame_size_types,
+    CropBounds,
+    DvTimings,
+    InputStatus,
+    VideoInput,
+    VideoStandard
 };
-use super::super::device::sort_frame_size_types;
 
 mod ioctl;
 use ioctl::*;
 
+mod frame;
+use frame::{
+    Format,
+    ImageFrame,
+    RawFrame,
+    SUPPORTED_PIXEL_FORMATS
+};
+
+mod device;
+pub use device::V4l2Device;
+
+mod input;
+use input::find_inputs;
+
+mod timings;
+use timings::find_dv_timings;
+
+mod selection;
+use selection::find_crop_bounds;
+
+mod parameters;
+
+mod dmabuf;
+pub use dmabuf::allocate_dmabufs;
+
+mod userptr;
+
+mod codec;
+pub use codec::{
+    find_codec_devices,
+    CodecKind,
+    CodecSession
+};
+
+mod event;
+pub use event::{
+    DeviceEvent,
+    EventKind
+};
+
+mod control;
+pub use control::Control;
+
+mod fixture;
+use fixture::{
+    Fixture,
+    Ioctls,
+    RECORD_VARIABLE,
+    REPLAY_VARIABLE
+};
+
 // pub fn add_capture_devices(devices: &mut Vec<Device>) -> Result<()> {
 //     for dir_entry_result in read_dir("/dev/v4l/by-id")? {
 //         let dir_entry = dir_entry_result?;
@@ -57,33 +120,63 @@ use ioctl::*;
 //     Ok(())
 // }
 
+/// How many times to try opening a device that another process has busy.
+const OPEN_ATTEMPTS: u32 = 3;
+const OPEN_RETRY_DELAY: Duration = Duration::from_millis(200);
+
 pub fn add_capture_devices(devices: &mut Vec<Device>) -> Result<()> {
+    if let Some(fixture_path) = env::var_os(REPLAY_VARIABLE) {
+        return add_replayed_devices(devices, &Fixture::load(Path::new(&fixture_path))?);
+    }
+    let record_path = env::var_os(RECORD_VARIABLE).map(PathBuf::from);
+    let mut recording = record_path.as_ref().map(|_| Fixture::default());
+
     // by-id gives us an id that should be consistent, even when
     // device plugged into another port (say USB).
-    match read_dir("/dev/v4l/by-id") {
-        Ok(dir) => add_capture_devices_for_dir(devices, dir)?,
-        Err(err) if err.kind() == ErrorKind::NotFound => {},
-        Err(err) => { return Err(err.into()); }
-    };
+    add_capture_devices_for_dir_path(devices, Path::new("/dev/v4l/by-id"), &mut recording)?;
     // Raspberry Pi doesn't create or populate by-id for PiCam, so we need to
     // add any extra found in by-path.
-    match read_dir("/dev/v4l/by-path") {
-        Ok(dir) => add_capture_devices_for_dir(devices, dir)?,
-        Err(err) if err.kind() == ErrorKind::NotFound => {},
-        Err(err) => { return Err(err.into()); }
-    };
+    add_capture_devices_for_dir_path(devices, Path::new("/dev/v4l/by-path"), &mut recording)?;
+
+    if let (Some(path), Some(fixture)) = (record_path, recording) {
+        fixture.save(&path)?;
+    }
     Ok(())
 }
 
-fn add_capture_devices_for_dir(devices: &mut Vec<Device>, dir: ReadDir) -> Result<()> {
+/// Lists the devices in a recorded fixture as they were found when it was recorded.
+/// Their OpenCV ids are for the device files on the machine it was recorded on.
+fn add_replayed_devices(devices: &mut Vec<Device>, fixture: &Fixture) -> Result<()> {
+    for recorded in &fixture.devices {
+        if device_is_not_list(devices, &recorded.path) {
+            let mut ioctls = Ioctls::Replaying(recorded);
+            if let Some(device) = describe_device(&mut ioctls, &recorded.path, recorded.unique_id.clone())? {
+                devices.push(device);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn add_capture_devices_for_dir_path(devices: &mut Vec<Device>, dir_path: &Path, recording: &mut Option<Fixture>) -> Result<()> {
+    match read_dir(dir_path) {
+        Ok(dir) => add_capture_devices_for_dir(devices, dir_path, dir, recording),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(io_error(dir_path, err))
+    }
+}
+
+fn add_capture_devices_for_dir(devices: &mut Vec<Device>, dir_path: &Path, dir: ReadDir, recording: &mut Option<Fixture>) -> Result<()> {
     for dir_entry_result in dir {
-        let dir_entry = dir_entry_result?;
+        let dir_entry = dir_entry_result.map_err(|err| io_error(dir_path, err))?;
         if is_symlink(&dir_entry) {
             let symlink_path: PathBuf = dir_entry.path();
-            let device_filepath: String = to_device_filepath(&symlink_path);
-            if device_is_not_list(devices, device_filepath) {
-                if let Some(device) = to_capture_device(&symlink_path) {
-                    devices.push(device);
+            // A symlink left dangling by a device being unplugged isn't worth failing over.
+            if let Ok(device_filepath) = to_device_filepath(&symlink_path) {
+                if device_is_not_list(devices, &device_filepath) {
+                    if let Some(device) = to_capture_device(&symlink_path, recording)? {
+                        devices.push(device);
+                    }
                 }
             }
         }
@@ -96,74 +189,136 @@ fn is_symlink(dir_entry: &DirEntry) -> bool {
     ft.is_ok() && ft.unwrap().is_symlink()
 }
 
-fn device_is_not_list(devices: &Vec<Device>, device_filepath: String) -> bool {
-    let opencv_id = to_opencv_id(&device_filepath);
-    !devices.iter().any(|d| d.opencv_id == opencv_id)
+fn device_is_not_list(devices: &Vec<Device>, device_filepath: &String) -> bool {
+    // udev also links the /dev/vbiN nodes of tuner cards, which OpenCV can't open,
+    // so those are left out rather than failing the whole list.
+    match to_opencv_id(device_filepath) {
+        Ok(opencv_id) => !devices.iter().any(|d| d.opencv_id == opencv_id),
+        Err(_) => false
+    }
+}
+
+fn to_capture_device(symlink_path: &PathBuf, recording: &mut Option<Fixture>) -> Result<Option<Device>> {
+    let device_filepath: String = to_device_filepath(&symlink_path)?;
+    let unique_id = to_unique_id(&symlink_path)?;
+
+    match open_device_file(Path::new(&device_filepath)) {
+        Ok(file) => {
+            let device_fd = file.as_raw_fd();
+            let mut ioctls = match recording {
+                Some(fixture) => Ioctls::Recording(device_fd, fixture.add_device(&device_filepath, &unique_id)),
+                None => Ioctls::Device(device_fd)
+            };
+            describe_device(&mut ioctls, &device_filepath, unique_id)
+        },
+        // Without access we won't be able to open any of the devices,
+        // so let the caller explain that.
+        Err(err) if err.is_permission_denied() => Err(err),
+        // Still locked after retrying, or unplugged since we listed the directory.
+        // We just won't list it.
+        Err(_) => Ok(None)
+    }
 }
 
-fn to_capture_device(symlink_path: &PathBuf) -> Option<Device> {
-    let device_filepath: String = to_device_filepath(&symlink_path);
-
-    match open_device_fd(&device_filepath) {
-        Ok(device_fd) => {
-            if let Ok(capabilities) = query_capabilities(&device_fd) {
-                if is_capture_device(&capabilities) {
-                        if let Ok(frame_size_types) = find_frame_size_types(&device_fd) {
-                        return Some(Device{
-                            opencv_id: to_opencv_id(&device_filepath),
-                            unique_id: to_unique_id(&symlink_path),
-                            name: to_device_name(&capabilities),
-                            frame_size_types
-                        });
-                    }
-                }
+fn describe_device(ioctls: &mut Ioctls, device_filepath: &String, unique_id: String) -> Result<Option<Device>> {
+    if let Ok(capabilities) = ioctls.query_capabilities() {
+        if is_capture_device(&capabilities) {
+            if let Ok(formats) = find_formats(ioctls) {
+                // Not being able to list the inputs is no reason to leave the device out.
+                let (inputs, standard) = find_inputs(ioctls).unwrap_or_default();
+                let dv_timings = find_dv_timings(ioctls);
+                let crop_bounds = find_crop_bounds(ioctls);
+                return Ok(Some(Device{
+                    opencv_id: to_opencv_id(device_filepath)?,
+                    unique_id,
+                    name: to_device_name(&capabilities),
+                    frame_size_types: to_frame_size_types(&formats),
+                    formats,
+                    source: None,
+                    inputs,
+                    standard,
+                    dv_timings,
+                    crop_bounds
+                }));
             }
-            None
-        },
-        // Perhaps it is locked, or we don't have access.
-        // We just won't list it, but we should be a bit more selective.
-        Err(_) => None
+        }
     }
+    Ok(None)
 }
 
-fn to_unique_id(entry_path: &PathBuf) -> String {
+fn to_unique_id(entry_path: &PathBuf) -> Result<String> {
     let id_osstr = entry_path.file_name()
-        .expect("/dev/v4l/by-id symlinks do not end in '..'");
-    id_osstr.to_string_lossy().to_string()
+        .ok_or_else(|| VideoDeviceError::InvalidPath(entry_path.clone()))?;
+    Ok(id_osstr.to_string_lossy().to_string())
 }
 
-fn to_device_filepath(symlink_path: &PathBuf) -> String {
+fn to_device_filepath(symlink_path: &PathBuf) -> Result<String> {
     // device_path will be from "/dev/video0" to "/dev/video63",
     // depending on device probe response order.
     let resolved_path = symlink_path.canonicalize()
-        .expect("/dev/v4l/by-id or /dev/v4l/by-path symlinks resolve to /dev/video paths");
+        .map_err(|err| io_error(symlink_path, err))?;
     let path_str = resolved_path.to_str()
-        .expect("/dev/v4l/by-id or /dev/v4l/by-path  symlinks resolve to utf8 paths");
-    path_str.to_string()
+        .ok_or_else(|| VideoDeviceError::InvalidPath(resolved_path.clone()))?;
+    Ok(path_str.to_string())
 }
 
-fn to_opencv_id(device_filepath: &String) -> i32 {
+fn to_opencv_id(device_filepath: &String) -> Result<i32> {
     // Integer id following "/dev/video" in devive_path.
     // This is the id expected by OpenCV when opening device.
+    let invalid_path = || VideoDeviceError::InvalidPath(PathBuf::from(device_filepath));
     let index_of_o = device_filepath.rfind('o')
-        .expect("device paths will be /dev/video0 to /dev/video63");
+        .ok_or_else(invalid_path)?;
     let dev_video_int = device_filepath[index_of_o+1 ..].parse::<i32>()
-        .expect("/dev/video paths to end in an integer");
-    dev_video_int | CAP_V4L
+        .map_err(|_| invalid_path())?;
+    Ok(dev_video_int | CAP_V4L)
+}
+
+/// The device file for a device found earlier. It's looked up again by the unique id,
+/// as the /dev/video number can change when devices are plugged in.
+pub fn device_path(device: &Device) -> Result<PathBuf> {
+    for dir_path in ["/dev/v4l/by-id", "/dev/v4l/by-path"] {
+        let symlink_path = Path::new(dir_path).join(&device.unique_id);
+        if symlink_path.exists() {
+            return Ok(PathBuf::from(to_device_filepath(&symlink_path)?));
+        }
+    }
+    Err(VideoDeviceError::NotFound(device.unique_id.clone()))
 }
 
-fn open_device_fd(device_filepath: &String) -> Result<RawFd> {
-    let file = File::options()
-        .read(true)
-        .write(true)
-        .open(device_filepath)?;
-    Ok(file.into_raw_fd())
+fn open_device_file(device_filepath: &Path) -> Result<File> {
+    let mut attempt = 1;
+    loop {
+        match File::options().read(true).write(true).open(device_filepath) {
+            Ok(file) => return Ok(file),
+            Err(err) => {
+                let err = io_error(device_filepath, err);
+                if !err.is_busy() || attempt == OPEN_ATTEMPTS {
+                    return Err(err);
+                }
+            }
+        }
+        sleep(OPEN_RETRY_DELAY);
+        attempt += 1;
+    }
+}
+
+fn io_error(path: &Path, source: std::io::Error) -> VideoDeviceError {
+    VideoDeviceError::Io { path: path.to_path_buf(), source }
+}
+
+fn ioctl_error(ioctl: &'static str) -> impl Fn(Errno) -> VideoDeviceError {
+    move |errno| VideoDeviceError::Ioctl { ioctl, errno }
 }
 
 fn query_capabilities(fd: &RawFd) -> Result<ioctl::V4l2Capability> {
+    read_capabilities(fd.clone())
+        .map_err(ioctl_error("VIDIOC_QUERYCAP"))
+}
+
+fn read_capabilities(fd: RawFd) -> nix::Result<V4l2Capability> {
     unsafe {
         let mut cap: V4l2Capability = mem::zeroed();
-        vidioc_querycap(fd.clone(), &mut cap)?;
+        vidioc_querycap(fd, &mut cap)?;
         Ok(cap)
     }
 }
@@ -172,6 +327,12 @@ fn is_capture_device(cap: &V4l2Capability) -> bool {
     (cap.device_caps & ( V4L2_CAP_VIDEO_CAPTURE | V4L2_CAP_VIDEO_CAPTURE_MPLANE )) != 0
 }
 
+/// Memory to memory devices, such as hardware codecs, which take frames
+/// rather than capturing them.
+fn is_codec_device(cap: &V4l2Capability) -> bool {
+    (cap.device_caps & ( V4L2_CAP_VIDEO_M2M | V4L2_CAP_VIDEO_M2M_MPLANE )) != 0
+}
+
 fn to_device_name(cap: &V4l2Capability) -> String {
     if let Ok(name) = CStr::from_bytes_until_nul(&cap.card) {
         return name.to_string_lossy().to_string();
@@ -179,35 +340,37 @@ fn to_device_name(cap: &V4l2Capability) -> String {
     String::from("UNKNOWN")
 }
 
-fn find_frame_size_types(fd: &RawFd) -> Result<Vec<FrameSizeType>> {
-    let mut types = Vec::<FrameSizeType>::new();
+fn find_formats(ioctls: &mut Ioctls) -> Result<Vec<VideoFormat>> {
+    let mut formats = Vec::<VideoFormat>::new();
 
-    add_frame_size_types(&mut types, fd, V4L2_BUF_TYPE_VIDEO_CAPTURE)?;
-    add_frame_size_types(&mut types, fd, V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE)?;
-    
-    sort_frame_size_types(&mut types);
-    Ok(types)
+    add_formats(&mut formats, ioctls, V4L2_BUF_TYPE_VIDEO_CAPTURE)?;
+    add_formats(&mut formats, ioctls, V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE)?;
+
+    Ok(formats)
 }
 
-fn add_frame_size_types(types: &mut Vec<FrameSizeType>, fd: &RawFd, fmt_type: u32) -> Result<()> {
+fn add_formats(formats: &mut Vec<VideoFormat>, ioctls: &mut Ioctls, fmt_type: u32) -> Result<()> {
     for format_index in 0.. {
-        match query_format(fd.clone(), fmt_type, format_index) {
+        match ioctls.query_format(fmt_type, format_index) {
             Ok(fmt) => {
-                for t in find_frame_size_types_for_format(fd, fmt.pixel_format)? {
-                    if !types.contains(&t) {
-                        types.push(t);
-                    }
+                for frame_size_type in find_frame_size_types_for_format(ioctls, fmt.pixel_format)? {
+                    let frame_rates = find_frame_rates(ioctls, fmt.pixel_format, &frame_size_type);
+                    formats.push(VideoFormat {
+                        fourcc: fmt.pixel_format as i32,
+                        frame_size_type,
+                        frame_rates
+                    });
                 }
             },
             // EINVAL returned after last index
             Err(Errno::EINVAL) => break,
-            Err(errno) => Err(errno)?
+            Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_ENUM_FMT", errno })?
         }
     }
     Ok(())
 }
 
-fn query_format(fd: RawFd, fmt_type: u32, index: u32) -> Result<V4l2Fmtdesc,Errno> {
+fn query_format(fd: RawFd, fmt_type: u32, index: u32) -> nix::Result<V4l2Fmtdesc> {
     unsafe {
         let mut fmt: V4l2Fmtdesc = mem::zeroed();
         fmt.index = index;
@@ -217,15 +380,15 @@ fn query_format(fd: RawFd, fmt_type: u32, index: u32) -> Result<V4l2Fmtdesc,Errn
     }
 }
 
-fn find_frame_size_types_for_format(fd: &RawFd, pixel_format: u32) -> Result<Vec<FrameSizeType>> {
+fn find_frame_size_types_for_format(ioctls: &mut Ioctls, pixel_format: u32) -> Result<Vec<FrameSizeType>> {
     let mut types = Vec::<FrameSizeType>::new();
 
     for frame_size_index in 0.. {
-        match query_frame_sizes(fd.clone(), pixel_format, frame_size_index) {
+        match ioctls.query_frame_sizes(pixel_format, frame_size_index) {
             Ok(fsz)  => types.push(to_frame_size_type(&fsz)?),
             // EINVAL returned after last index
             Err(Errno::EINVAL) => break,
-            Err(errno) => Err(errno)?
+            Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_ENUM_FRAMESIZES", errno })?
         }
     }
     Ok(types)
@@ -247,11 +410,11 @@ fn to_frame_size_type(fsz: &V4l2Frmsizeenum) -> Result<FrameSizeType> {
                 max_height: fsz.frmsize.stepwise.max_height,
                 step_height: fsz.frmsize.stepwise.step_height
             }}),
-        _ => Err(Error::msg("Unknown frame size type"))
+        typ => Err(VideoDeviceError::UnknownFrameSizeType(typ))
     }
 }
 
-fn query_frame_sizes(fd: RawFd, pixel_format:u32, index: u32) -> Result<V4l2Frmsizeenum,Errno> {
+fn query_frame_sizes(fd: RawFd, pixel_format:u32, index: u32) -> nix::Result<V4l2Frmsizeenum> {
     unsafe {
         let mut frmsize: V4l2Frmsizeenum = mem::zeroed();
         frmsize.pixel_format = pixel_format;
@@ -259,4 +422,161 @@ fn query_frame_sizes(fd: RawFd, pixel_format:u32, index: u32) -> Result<V4l2Frms
         vidioc_enum_framesizes(fd, &mut frmsize)?;
         Ok(frmsize)
     }
-}
\ No newline at end of file
+}
+
+fn find_frame_rates(ioctls: &mut Ioctls, pixel_format: u32, frame_size_type: &FrameSizeType) -> Vec<f64> {
+    // Ranges of sizes are asked about at their largest.
+    let (width, height) = match *frame_size_type {
+        FrameSizeType::Discrete { width, height } => (width, height),
+        FrameSizeType::Stepwise { max_width, max_height, .. } => (max_width, max_height)
+    };
+    let mut rates = Vec::<f64>::new();
+    for index in 0.. {
+        match ioctls.query_frame_intervals(pixel_format, width, height, index) {
+            Ok(ival) if ival.typ == V4L2_FRMIVAL_TYPE_DISCRETE => rates.push(to_fps(unsafe { ival.frmival.discrete })),
+            // A range comes as a single entry, so just take its ends.
+            Ok(ival) if ival.typ == V4L2_FRMIVAL_TYPE_STEPWISE || ival.typ == V4L2_FRMIVAL_TYPE_CONTINUOUS => {
+                let stepwise = unsafe { ival.frmival.stepwise };
+                rates.push(to_fps(stepwise.min));
+                rates.push(to_fps(stepwise.max));
+                break;
+            },
+            Ok(_) => break,
+            // EINVAL returned after last index. Some drivers don't support
+            // enumerating intervals at all.
+            Err(Errno::EINVAL) | Err(Errno::ENOTTY) => break,
+            // Anything else and the rates so far can't be trusted, but the
+            // size still can, so the mode is listed without them.
+            Err(_) => return Vec::new()
+        }
+    }
+    rates.retain(|fps| fps.is_finite() && *fps > 0.0);
+    rates.sort_by(|a, b| b.total_cmp(a));
+    rates.dedup();
+    rates
+}
+
+fn to_fps(interval: V4l2Fract) -> f64 {
+    interval.denominator as f64 / interval.numerator as f64
+}
+
+fn query_frame_intervals(fd: RawFd, pixel_format: u32, width: u32, height: u32, index: u32) -> nix::Result<V4l2Frmivalenum> {
+    unsafe {
+        let mut frmival: V4l2Frmivalenum = mem::zeroed();
+        frmival.pixel_format = pixel_format;
+        frmival.width = width;
+        frmival.height = height;
+        frmival.index = index;
+        vidioc_enum_frameintervals(fd, &mut frmival)?;
+        Ok(frmival)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opencv::core::Rect;
+    use super::*;
+
+    fn fixture_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(name)
+    }
+
+    fn replay_file(path: &Path) -> Vec<Device> {
+        let mut devices = Vec::new();
+        add_replayed_devices(&mut devices, &Fixture::load(path).unwrap()).unwrap();
+        devices
+    }
+
+    fn replay(name: &str) -> Vec<Device> {
+        replay_file(&fixture_path(name))
+    }
+
+    fn to_modes(device: &Device) -> Vec<(String, FrameSizeType, Vec<f64>)> {
+        device.formats.iter()
+            .map(|format| (String::from_utf8_lossy(&format.fourcc.to_le_bytes()).to_string(), format.frame_size_type.clone(), format.frame_rates.clone()))
+            .collect()
+    }
+
+    fn discrete(width: u32, height: u32) -> FrameSizeType {
+        FrameSizeType::Discrete { width, height }
+    }
+
+    #[test]
+    fn only_capture_devices_are_listed() {
+        let devices = replay("v4l2-webcam-and-picam.txt");
+        let listed: Vec<(i32, &str, &str)> = devices.iter()
+            .map(|device| (device.opencv_id, device.unique_id.as_str(), device.name.as_str()))
+            .collect();
+        // The webcam's metadata node and the codec are left out.
+        assert_eq!(listed, [
+            (CAP_V4L, "usb-046d_HD_Pro_Webcam_C920_8A3B2C1F-video-index0", "HD Pro Webcam C920"),
+            (2 | CAP_V4L, "platform-bcm2835-v4l2-video-index0", "mmal service 16.1")
+        ]);
+    }
+
+    #[test]
+    fn webcam_modes_are_listed_with_their_frame_rates() {
+        let webcam = &replay("v4l2-webcam-and-picam.txt")[0];
+        assert_eq!(to_modes(webcam), [
+            (String::from("YUYV"), discrete(640, 480), vec![30.0, 15.0]),
+            (String::from("YUYV"), discrete(1280, 720), vec![10.0, 5.0]),
+            (String::from("MJPG"), discrete(640, 480), vec![30.0, 15.0]),
+            (String::from("MJPG"), discrete(1920, 1080), vec![30.0])
+        ]);
+        assert_eq!(webcam.frame_size_types, [discrete(1920, 1080), discrete(1280, 720), discrete(640, 480)]);
+
+        let [input] = webcam.inputs.as_slice() else {
+            panic!("expected one input");
+        };
+        assert_eq!((input.index, input.name.as_str(), input.tuner, input.selected), (0, "Camera 1", false, true));
+        assert!(input.status.is_empty() && input.standards.is_empty());
+        assert!(webcam.standard.is_none());
+        assert!(webcam.dv_timings.is_none());
+        assert!(webcam.crop_bounds.is_none());
+    }
+
+    #[test]
+    fn picam_sizes_are_ranges_it_can_crop_within() {
+        let picam = &replay("v4l2-webcam-and-picam.txt")[1];
+        let range = FrameSizeType::Stepwise {
+            min_width: 32,
+            max_width: 2592,
+            step_width: 2,
+            min_height: 32,
+            max_height: 1944,
+            step_height: 2
+        };
+        // A range of intervals comes as its fastest and slowest.
+        assert_eq!(to_modes(picam), [
+            (String::from("YU12"), range.clone(), vec![15.0, 1.0]),
+            (String::from("JPEG"), range.clone(), vec![15.0, 1.0])
+        ]);
+        assert_eq!(picam.frame_size_types, [range]);
+        assert_eq!(picam.inputs.iter().map(|input| input.name.as_str()).collect::<Vec<_>>(), ["Camera 0"]);
+
+        let crop_bounds = picam.crop_bounds.as_ref().unwrap();
+        assert_eq!(crop_bounds.bounds, Rect::new(0, 0, 2592, 1944));
+        assert_eq!(crop_bounds.default, Rect::new(0, 0, 2592, 1944));
+    }
+
+    #[test]
+    fn modes_whose_rates_fail_are_listed_without_them() {
+        let text = std::fs::read_to_string(fixture_path("v4l2-webcam-and-picam.txt")).unwrap();
+        let failing: String = text.lines()
+            .map(|line| match line.starts_with("ENUM_FRAMEINTERVALS 1448695129 1280 720 1 ") {
+                true => String::from("ENUM_FRAMEINTERVALS 1448695129 1280 720 1 errno:5\n"),
+                false => format!("{}\n", line)
+            })
+            .collect();
+        let path = env::temp_dir().join(format!("opencv-example-rs-failing-rates-{}.txt", std::process::id()));
+        std::fs::write(&path, failing).unwrap();
+        let webcam = &replay_file(&path)[0];
+        let _ = std::fs::remove_file(path);
+
+        // The 10fps it got before the error goes too, and the other modes keep theirs.
+        let modes = to_modes(webcam);
+        assert_eq!(modes[1], (String::from("YUYV"), discrete(1280, 720), vec![]));
+        assert_eq!(modes[0].2, [30.0, 15.0]);
+        assert_eq!(modes.len(), 4);
+    }
+}
@@ -0,0 +1,637 @@
+//! Hardware encoders and decoders, which SoCs such as the Pi's have as memory
+//! to memory devices: frames go in on the OUTPUT queue and come out coded, or
+//! decoded, on the CAPTURE queue. They aren't capture devices, and don't appear
+//! in /dev/v4l, so are found separately.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/dev-encoder.html
+//! and https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/dev-decoder.html
+use std::{
+    ffi::{
+        c_int,
+        c_void
+    },
+    fs::{
+        read_dir,
+        File
+    },
+    mem,
+    num::NonZeroUsize,
+    os::fd::{
+        AsRawFd,
+        RawFd
+    },
+    path::{
+        Path,
+        PathBuf
+    },
+    slice,
+    time::{
+        Duration,
+        Instant
+    }
+};
+use nix::{
+    errno::Errno,
+    fcntl::{
+        fcntl,
+        FcntlArg,
+        OFlag
+    },
+    poll::{
+        poll,
+        PollFd,
+        PollFlags
+    }
+};
+use opencv::core::Mat;
+use super::{
+    control::{
+        get_control,
+        set_control
+    },
+    device::{
+        to_format,
+        MappedBuffer
+    },
+    event::{
+        dequeue_event,
+        subscribe,
+        EventKind
+    },
+    frame::{
+        from_mat,
+        pixel_format_name,
+        to_mat,
+        ENCODABLE_PIXEL_FORMATS,
+        V4L2_PIX_FMT_BGR24,
+        V4L2_PIX_FMT_JPEG,
+        V4L2_PIX_FMT_MJPEG
+    },
+    ioctl::*,
+    io_error,
+    ioctl_error,
+    is_codec_device,
+    open_device_file,
+    query_capabilities,
+    query_format,
+    to_device_name,
+    DeviceEvent,
+    Format,
+    Result,
+    VideoDeviceError,
+    SUPPORTED_PIXEL_FORMATS
+};
+
+/// Raw frames going into an encoder, so one can be filled while another is coded.
+const INPUT_BUFFERS: u32 = 2;
+const CODED_BUFFERS: u32 = 4;
+/// Room asked for each coded frame going into a decoder. The driver may give more.
+const CODED_FRAME_SIZE: u32 = 2 << 20;
+/// Decoded frames beyond the fewest the decoder needs, so it can carry on
+/// while one is being converted.
+const SPARE_DECODED_BUFFERS: u32 = 2;
+/// How long to wait for the codec to finish with an earlier frame and take another.
+const INPUT_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(PartialEq)]
+pub enum CodecKind {
+    /// Makes compressed frames from raw ones.
+    Encoder,
+    /// Makes raw frames from compressed ones.
+    Decoder,
+    /// Raw frames in and out, such as a scaler or the Pi's ISP.
+    Converter
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct CodecDevice {
+    pub path: PathBuf,
+    pub name: String,
+    pub kind: CodecKind,
+    /// The pixel formats frames go in as, on the OUTPUT queue.
+    pub input_formats: Vec<u32>,
+    /// The pixel formats frames come out as, on the CAPTURE queue.
+    pub output_formats: Vec<u32>,
+    /// The device uses the multiplanar API, as most SoC codecs do.
+    planar: bool
+}
+
+/// Every memory to memory device, looked for among every /dev/video file.
+pub fn find_codec_devices() -> Result<Vec<CodecDevice>> {
+    let dev_path = Path::new("/dev");
+    let mut devices = Vec::<CodecDevice>::new();
+    for dir_entry_result in read_dir(dev_path).map_err(|err| io_error(dev_path, err))? {
+        let path = dir_entry_result.map_err(|err| io_error(dev_path, err))?.path();
+        if !path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("video")) {
+            continue;
+        }
+        let file = match open_device_file(&path) {
+            Ok(file) => file,
+            Err(err) if err.is_permission_denied() => return Err(err),
+            // Busy, or gone since the directory was listed.
+            Err(_) => continue
+        };
+        if let Some(device) = describe_codec(file.as_raw_fd(), &path)? {
+            devices.push(device);
+        }
+    }
+    devices.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(devices)
+}
+
+fn describe_codec(fd: RawFd, path: &Path) -> Result<Option<CodecDevice>> {
+    let capabilities = query_capabilities(&fd)?;
+    if !is_codec_device(&capabilities) {
+        return Ok(None);
+    }
+    let planar = capabilities.device_caps & V4L2_CAP_VIDEO_M2M_MPLANE != 0;
+    let (input_type, output_type) = queue_types(planar);
+    let (input_formats, compressed_input) = find_pixel_formats(fd, input_type)?;
+    let (output_formats, compressed_output) = find_pixel_formats(fd, output_type)?;
+    Ok(Some(CodecDevice {
+        path: path.to_path_buf(),
+        name: to_device_name(&capabilities),
+        kind: match (compressed_input, compressed_output) {
+            (false, true) => CodecKind::Encoder,
+            (true, false) => CodecKind::Decoder,
+            _ => CodecKind::Converter
+        },
+        input_formats,
+        output_formats,
+        planar
+    }))
+}
+
+/// The pixel formats a queue takes, and whether any are compressed.
+fn find_pixel_formats(fd: RawFd, typ: u32) -> Result<(Vec<u32>, bool)> {
+    let mut formats = Vec::<u32>::new();
+    let mut compressed = false;
+    for index in 0.. {
+        match query_format(fd, typ, index) {
+            Ok(fmt) => {
+                formats.push(fmt.pixel_format);
+                compressed |= fmt.flags & V4L2_FMT_FLAG_COMPRESSED != 0;
+            },
+            // EINVAL returned after last index
+            Err(Errno::EINVAL) => break,
+            Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_ENUM_FMT", errno })?
+        }
+    }
+    Ok((formats, compressed))
+}
+
+/// The OUTPUT and CAPTURE buffer types, for frames going in and coming out.
+fn queue_types(planar: bool) -> (u32, u32) {
+    if planar {
+        (V4L2_BUF_TYPE_VIDEO_OUTPUT_MPLANE, V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE)
+    } else {
+        (V4L2_BUF_TYPE_VIDEO_OUTPUT, V4L2_BUF_TYPE_VIDEO_CAPTURE)
+    }
+}
+
+/// Encodes images, or decodes frames into images, with a codec device. Frames
+/// are copied in and out of the driver's buffers, which is quick next to
+/// coding them in software.
+pub struct CodecSession {
+    file: File,
+    path: PathBuf,
+    planar: bool,
+    /// Frames going in, on the OUTPUT queue.
+    input: Queue,
+    /// Frames coming out, on the CAPTURE queue. Decoders only have it once the
+    /// first frame has told them the size.
+    output: Option<Queue>,
+    output_type: u32
+}
+
+/// One of the two queues, with the driver's buffers mapped.
+struct Queue {
+    typ: u32,
+    format: Format,
+    buffers: Vec<MappedBuffer>,
+    /// Buffers the driver doesn't have. Only those for frames going in are
+    /// ever kept back.
+    free: Vec<u32>
+}
+
+impl CodecSession {
+    /// Starts encoding images of `width` by `height` to `coded_format`, such as
+    /// `JPEG` or `H264`. Images go in as BGR where the encoder takes it, and are
+    /// converted to a format it does take otherwise.
+    pub fn encoder(device: &CodecDevice, width: u32, height: u32, coded_format: u32) -> Result<CodecSession> {
+        if !device.output_formats.contains(&coded_format) {
+            return Err(VideoDeviceError::UnsupportedCodecFormat(device.path.clone(), pixel_format_name(coded_format)));
+        }
+        let raw_format = ENCODABLE_PIXEL_FORMATS.into_iter()
+            .find(|pixel_format| device.input_formats.contains(pixel_format))
+            .ok_or_else(|| VideoDeviceError::NoSupportedFormat(device.path.clone()))?;
+        let file = open_codec_file(&device.path)?;
+        let fd = file.as_raw_fd();
+        let (input_type, output_type) = queue_types(device.planar);
+        // The coded format goes first, as encoders work out which raw formats they take from it.
+        let coded = set_format(fd, output_type, device.planar, width, height, coded_format, 0)?;
+        let raw = set_format(fd, input_type, device.planar, width, height, raw_format, 0)?;
+        let mut input = Queue::request(fd, &device.path, input_type, device.planar, raw, INPUT_BUFFERS)?;
+        let mut output = Queue::request(fd, &device.path, output_type, device.planar, coded, CODED_BUFFERS)?;
+        input.start(fd, device.planar)?;
+        output.start(fd, device.planar)?;
+        Ok(CodecSession {
+            file,
+            path: device.path.clone(),
+            planar: device.planar,
+            input,
+            output: Some(output),
+            output_type
+        })
+    }
+
+    /// Starts decoding frames of `coded_format`. The size comes from the first
+    /// frame, and images come out as BGR whatever the decoder makes.
+    pub fn decoder(device: &CodecDevice, coded_format: u32) -> Result<CodecSession> {
+        if !device.input_formats.contains(&coded_format) {
+            return Err(VideoDeviceError::UnsupportedCodecFormat(device.path.clone(), pixel_format_name(coded_format)));
+        }
+        let file = open_codec_file(&device.path)?;
+        let fd = file.as_raw_fd();
+        let (input_type, output_type) = queue_types(device.planar);
+        // How decoders say they've found the size.
+        subscribe(fd, EventKind::SourceChange(0))
+            .map_err(ioctl_error("VIDIOC_SUBSCRIBE_EVENT"))?;
+        let coded = set_format(fd, input_type, device.planar, 0, 0, coded_format, CODED_FRAME_SIZE)?;
+        let mut input = Queue::request(fd, &device.path, input_type, device.planar, coded, INPUT_BUFFERS)?;
+        input.start(fd, device.planar)?;
+        Ok(CodecSession {
+            file,
+            path: device.path.clone(),
+            planar: device.planar,
+            input,
+            output: None,
+            output_type
+        })
+    }
+
+    /// The format frames go into the codec as.
+    pub fn input_format(&self) -> Format {
+        self.input.format
+    }
+
+    /// Sets the quality of the JPEGs an encoder makes, from 1 to 100.
+    pub fn set_jpeg_quality(&mut self, quality: u8) -> Result<()> {
+        set_control(self.fd(), V4L2_CID_JPEG_COMPRESSION_QUALITY, quality as i32)
+            .map_err(ioctl_error("VIDIOC_S_CTRL"))
+    }
+
+    /// Encodes an 8 bit BGR image, scaled first if it's another size to the one
+    /// the encoder was started with, and waits up to `timeout` for it to come out
+    /// coded. Anything else the encoder has ready comes with it. Encoders that
+    /// hold frames back give them with later ones.
+    pub fn encode(&mut self, image: &Mat, timeout: Duration) -> Result<Vec<u8>> {
+        let raw = from_mat(image, &self.input.format)?;
+        self.queue_input(&raw)?;
+        let mut coded = Vec::<u8>::new();
+        let mut wait = timeout;
+        while self.next_output(wait, |data, _| {
+            coded.extend_from_slice(data);
+            Ok(())
+        })?.is_some() {
+            wait = Duration::ZERO;
+        }
+        Ok(coded)
+    }
+
+    /// Decodes a coded frame, waiting up to `timeout` for an image to come of it,
+    /// and returns every image ready. Decoders that reorder frames, as H.264
+    /// ones may, give images of earlier frames. Images are the size the decoder
+    /// decodes to, which may be padded out to a whole number of blocks.
+    pub fn decode(&mut self, data: &[u8], timeout: Duration) -> Result<Vec<Mat>> {
+        self.queue_input(data)?;
+        let mut images = Vec::<Mat>::new();
+        let mut wait = timeout;
+        while let Some(image) = self.next_output(wait, to_mat)? {
+            images.push(image);
+            wait = Duration::ZERO;
+        }
+        Ok(images)
+    }
+
+    fn fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+
+    /// Copies a frame into a free input buffer and gives it to the codec,
+    /// waiting for the codec to finish with one if none are free.
+    fn queue_input(&mut self, data: &[u8]) -> Result<()> {
+        let deadline = Instant::now() + INPUT_TIMEOUT;
+        let index = loop {
+            self.reclaim_inputs()?;
+            if let Some(index) = self.input.free.pop() {
+                break index;
+            }
+            if !self.wait(PollFlags::POLLOUT, deadline)? {
+                return Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_DQBUF", errno: Errno::ETIMEDOUT });
+            }
+        };
+        let buffer = &self.input.buffers[index as usize];
+        if data.len() > buffer.length {
+            self.input.free.push(index);
+            return Err(VideoDeviceError::FrameTooLarge(data.len(), self.path.clone()));
+        }
+        unsafe {
+            slice::from_raw_parts_mut(buffer.start as *mut u8, data.len()).copy_from_slice(data);
+        }
+        buffer_ioctl(self.fd(), self.input.typ, self.planar, index, data.len() as u32, vidioc_qbuf)
+            .map_err(ioctl_error("VIDIOC_QBUF"))?;
+        Ok(())
+    }
+
+    /// Takes back the input buffers the codec has finished with.
+    fn reclaim_inputs(&mut self) -> Result<()> {
+        while let Some(buf) = dequeue(self.fd(), self.input.typ, self.planar)? {
+            self.input.free.push(buf.index);
+        }
+        Ok(())
+    }
+
+    /// Waits up to `timeout` for the next frame out of the codec, returning
+    /// `None` if none came, and reads it with `read` before its buffer goes back
+    /// to the driver. Damaged frames, and the empty buffer some codecs mark the
+    /// end of the stream with, are skipped.
+    fn next_output<T>(&mut self, timeout: Duration, mut read: impl FnMut(&[u8], &Format) -> Result<T>) -> Result<Option<T>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.reclaim_inputs()?;
+            if let Some(output) = &self.output {
+                if let Some(buf) = dequeue(self.fd(), output.typ, self.planar)? {
+                    let mapped = &output.buffers[buf.index as usize];
+                    let data = unsafe {
+                        slice::from_raw_parts(mapped.start as *const u8, (buf.bytes_used as usize).min(mapped.length))
+                    };
+                    let result = (buf.flags & V4L2_BUF_FLAG_ERROR == 0 && !data.is_empty())
+                        .then(|| read(data, &output.format));
+                    buffer_ioctl(self.fd(), output.typ, self.planar, buf.index, 0, vidioc_qbuf)
+                        .map_err(ioctl_error("VIDIOC_QBUF"))?;
+                    match result {
+                        Some(result) => return result.map(Some),
+                        None => continue
+                    }
+                }
+            }
+            if !self.wait(PollFlags::POLLIN, deadline)? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Waits until `deadline` for the codec to be ready for `flags`, returning
+    /// false if it wasn't, or has nothing queued that could make it so. Events
+    /// that arrive meanwhile are handled, which may be all it was waiting for.
+    fn wait(&mut self, flags: PollFlags, deadline: Instant) -> Result<bool> {
+        loop {
+            let mut fds = [PollFd::new(self.fd(), flags | PollFlags::POLLPRI)];
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match poll(&mut fds, remaining.as_millis().min(i32::MAX as u128) as i32) {
+                Ok(0) => return Ok(false),
+                Ok(_) => {
+                    let revents = fds[0].revents().unwrap_or(PollFlags::empty());
+                    if revents.contains(PollFlags::POLLPRI) {
+                        self.handle_events()?;
+                        return Ok(true);
+                    }
+                    // POLLERR means neither queue has anything queued.
+                    return Ok(revents.intersects(flags));
+                },
+                Err(Errno::EINTR) => continue,
+                Err(errno) => return Err(VideoDeviceError::Ioctl { ioctl: "poll", errno })
+            }
+        }
+    }
+
+    fn handle_events(&mut self) -> Result<()> {
+        while let Some(event) = dequeue_event(self.fd())? {
+            if let DeviceEvent::SourceChange { resolution: true, .. } = event {
+                self.start_output()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets up a decoder's output at the size it found, as BGR where it can make
+    /// that, or else a format that can be converted.
+    fn start_output(&mut self) -> Result<()> {
+        let fd = self.fd();
+        // Unmapped before asking for new buffers, as the driver won't free mapped ones.
+        if let Some(output) = self.output.take() {
+            stream(fd, output.typ, false)?;
+        }
+        let found = get_format(fd, self.output_type, self.planar)?;
+        let (offered, _) = find_pixel_formats(fd, self.output_type)?;
+        let pixel_format = [V4L2_PIX_FMT_BGR24].into_iter()
+            .chain(SUPPORTED_PIXEL_FORMATS)
+            .filter(|&pixel_format| pixel_format != V4L2_PIX_FMT_MJPEG && pixel_format != V4L2_PIX_FMT_JPEG)
+            .find(|pixel_format| offered.contains(pixel_format))
+            .ok_or_else(|| VideoDeviceError::NoSupportedFormat(self.path.clone()))?;
+        let format = set_format(fd, self.output_type, self.planar, found.width, found.height, pixel_format, 0)?;
+        let count = get_control(fd, V4L2_CID_MIN_BUFFERS_FOR_CAPTURE).map_or(1, |min| min.max(1) as u32) + SPARE_DECODED_BUFFERS;
+        let mut output = Queue::request(fd, &self.path, self.output_type, self.planar, format, count)?;
+        output.start(fd, self.planar)?;
+        self.output = Some(output);
+        Ok(())
+    }
+}
+
+/// Both queues are stopped, so the driver lets go of the buffers.
+impl Drop for CodecSession {
+    fn drop(&mut self) {
+        let _ = stream(self.fd(), self.input.typ, false);
+        if let Some(output) = &self.output {
+            let _ = stream(self.fd(), output.typ, false);
+        }
+    }
+}
+
+impl Queue {
+    /// Asks the driver for `count` buffers, and maps those it gives.
+    fn request(fd: RawFd, path: &Path, typ: u32, planar: bool, format: Format, count: u32) -> Result<Queue> {
+        let mut req = V4l2RequestBuffers {
+            count,
+            typ,
+            memory: V4L2_MEMORY_MMAP,
+            capabilities: 0,
+            flags: 0,
+            reserved: [0; 3]
+        };
+        unsafe {
+            vidioc_reqbufs(fd, &mut req)
+                .map_err(ioctl_error("VIDIOC_REQBUFS"))?;
+        }
+        let mut buffers = Vec::<MappedBuffer>::new();
+        for index in 0..req.count {
+            let buf = buffer_ioctl(fd, typ, planar, index, 0, vidioc_querybuf)
+                .map_err(ioctl_error("VIDIOC_QUERYBUF"))?;
+            let length = NonZeroUsize::new(buf.length as usize)
+                .ok_or_else(|| VideoDeviceError::StreamingUnsupported(path.to_path_buf()))?;
+            buffers.push(MappedBuffer::map(fd, buf.offset, length)?);
+        }
+        if buffers.is_empty() {
+            return Err(VideoDeviceError::StreamingUnsupported(path.to_path_buf()));
+        }
+        Ok(Queue {
+            typ,
+            format,
+            free: (0..buffers.len() as u32).collect(),
+            buffers
+        })
+    }
+
+    /// Starts the queue. Buffers for frames coming out all go to the driver to
+    /// fill, while those for frames going in wait to be filled here.
+    fn start(&mut self, fd: RawFd, planar: bool) -> Result<()> {
+        if matches!(self.typ, V4L2_BUF_TYPE_VIDEO_CAPTURE | V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE) {
+            for index in self.free.drain(..) {
+                buffer_ioctl(fd, self.typ, planar, index, 0, vidioc_qbuf)
+                    .map_err(ioctl_error("VIDIOC_QBUF"))?;
+            }
+        }
+        stream(fd, self.typ, true)
+    }
+}
+
+/// Opened so that dequeuing returns straight away when nothing's ready, with
+/// poll to wait.
+fn open_codec_file(path: &Path) -> Result<File> {
+    let file = open_device_file(path)?;
+    fcntl(file.as_raw_fd(), FcntlArg::F_SETFL(OFlag::O_NONBLOCK))
+        .map_err(ioctl_error("fcntl"))?;
+    Ok(file)
+}
+
+fn set_format(fd: RawFd, typ: u32, planar: bool, width: u32, height: u32, pixel_format: u32, size_image: u32) -> Result<Format> {
+    unsafe {
+        let mut fmt: V4l2Format = mem::zeroed();
+        fmt.typ = typ;
+        if planar {
+            fmt.fmt.pix_mp.width = width;
+            fmt.fmt.pix_mp.height = height;
+            fmt.fmt.pix_mp.pixelformat = pixel_format;
+            fmt.fmt.pix_mp.field = V4L2_FIELD_NONE;
+            fmt.fmt.pix_mp.num_planes = 1;
+            fmt.fmt.pix_mp.plane_fmt[0].sizeimage = size_image;
+        } else {
+            fmt.fmt.pix.width = width;
+            fmt.fmt.pix.height = height;
+            fmt.fmt.pix.pixelformat = pixel_format;
+            fmt.fmt.pix.field = V4L2_FIELD_NONE;
+            fmt.fmt.pix.sizeimage = size_image;
+        }
+        vidioc_s_fmt(fd, &mut fmt)
+            .map_err(ioctl_error("VIDIOC_S_FMT"))?;
+        Ok(to_queue_format(&fmt, planar))
+    }
+}
+
+fn get_format(fd: RawFd, typ: u32, planar: bool) -> Result<Format> {
+    unsafe {
+        let mut fmt: V4l2Format = mem::zeroed();
+        fmt.typ = typ;
+        vidioc_g_fmt(fd, &mut fmt)
+            .map_err(ioctl_error("VIDIOC_G_FMT"))?;
+        Ok(to_queue_format(&fmt, planar))
+    }
+}
+
+/// The format of the first plane, which is the only one for the pixel
+/// formats used here.
+fn to_queue_format(fmt: &V4l2Format, planar: bool) -> Format {
+    if !planar {
+        return to_format(unsafe { &fmt.fmt.pix });
+    }
+    let pix = unsafe { fmt.fmt.pix_mp };
+    let plane = pix.plane_fmt[0];
+    Format {
+        width: pix.width,
+        height: pix.height,
+        pixel_format: pix.pixelformat,
+        bytes_per_line: plane.bytesperline,
+        size_image: plane.sizeimage
+    }
+}
+
+fn stream(fd: RawFd, typ: u32, on: bool) -> Result<()> {
+    let typ = typ as c_int;
+    unsafe {
+        if on {
+            vidioc_streamon(fd, &typ)
+                .map_err(ioctl_error("VIDIOC_STREAMON"))?;
+        } else {
+            vidioc_streamoff(fd, &typ)
+                .map_err(ioctl_error("VIDIOC_STREAMOFF"))?;
+        }
+    }
+    Ok(())
+}
+
+/// What a buffer ioctl said of a buffer, from its one plane where the device
+/// is multiplanar.
+struct BufferInfo {
+    index: u32,
+    flags: u32,
+    bytes_used: u32,
+    length: u32,
+    offset: u32
+}
+
+fn buffer_ioctl(
+    fd: RawFd,
+    typ: u32,
+    planar: bool,
+    index: u32,
+    bytes_used: u32,
+    ioctl: unsafe fn(c_int, *mut V4l2Buffer) -> nix::Result<c_int>
+) -> nix::Result<BufferInfo> {
+    let mut plane: V4l2Plane = unsafe { mem::zeroed() };
+    let mut buf: V4l2Buffer = unsafe { mem::zeroed() };
+    buf.index = index;
+    buf.typ = typ;
+    buf.memory = V4L2_MEMORY_MMAP;
+    buf.field = V4L2_FIELD_NONE;
+    if planar {
+        plane.bytesused = bytes_used;
+        buf.m.planes = &mut plane as *mut V4l2Plane as *mut c_void;
+        buf.length = 1;
+    } else {
+        buf.bytesused = bytes_used;
+    }
+    unsafe {
+        ioctl(fd, &mut buf)?;
+    }
+    Ok(if planar {
+        BufferInfo {
+            index: buf.index,
+            flags: buf.flags,
+            bytes_used: plane.bytesused,
+            length: plane.length,
+            offset: unsafe { plane.m.mem_offset }
+        }
+    } else {
+        BufferInfo {
+            index: buf.index,
+            flags: buf.flags,
+            bytes_used: buf.bytesused,
+            length: buf.length,
+            offset: unsafe { buf.m.offset }
+        }
+    })
+}
+
+/// The next buffer the codec has finished with from the queue, if there is one.
+fn dequeue(fd: RawFd, typ: u32, planar: bool) -> Result<Option<BufferInfo>> {
+    match buffer_ioctl(fd, typ, planar, 0, 0, vidioc_dqbuf) {
+        Ok(buf) => Ok(Some(buf)),
+        Err(Errno::EAGAIN) => Ok(None),
+        Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_DQBUF", errno })
+    }
+}
@@ -0,0 +1,72 @@
+//! The controls a device has, such as brightness, exposure and privacy.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/control.html
+use std::{
+    ffi::CStr,
+    mem,
+    os::fd::RawFd
+};
+use nix::errno::Errno;
+use super::{
+    ioctl::*,
+    Result,
+    VideoDeviceError
+};
+
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Control {
+    pub id: u32,
+    pub name: String
+}
+
+/// Every control the device has, other than disabled ones and the headings
+/// that group them into classes.
+pub fn find_controls(fd: RawFd) -> Result<Vec<Control>> {
+    let mut controls = Vec::<Control>::new();
+    let mut id = 0;
+    loop {
+        match query_next_control(fd, id) {
+            Ok(ctrl) => {
+                id = ctrl.id;
+                if ctrl.typ != V4L2_CTRL_TYPE_CTRL_CLASS && ctrl.flags & V4L2_CTRL_FLAG_DISABLED == 0 {
+                    controls.push(Control {
+                        id: ctrl.id,
+                        name: CStr::from_bytes_until_nul(&ctrl.name)
+                            .map_or_else(|_| format!("control {:#x}", ctrl.id), |name| name.to_string_lossy().to_string())
+                    });
+                }
+            },
+            // EINVAL returned after the last control.
+            Err(Errno::EINVAL) | Err(Errno::ENOTTY) => break,
+            Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_QUERYCTRL", errno })?
+        }
+    }
+    Ok(controls)
+}
+
+/// The control with the lowest id above `id`.
+fn query_next_control(fd: RawFd, id: u32) -> nix::Result<V4l2Queryctrl> {
+    unsafe {
+        let mut ctrl: V4l2Queryctrl = mem::zeroed();
+        ctrl.id = id | V4L2_CTRL_FLAG_NEXT_CTRL;
+        vidioc_queryctrl(fd, &mut ctrl)?;
+        Ok(ctrl)
+    }
+}
+
+pub fn get_control(fd: RawFd, id: u32) -> nix::Result<i32> {
+    let mut control = V4l2Control { id, value: 0 };
+    unsafe {
+        vidioc_g_ctrl(fd, &mut control)?;
+    }
+    Ok(control.value)
+}
+
+pub fn set_control(fd: RawFd, id: u32, value: i32) -> nix::Result<()> {
+    let mut control = V4l2Control { id, value };
+    unsafe {
+        vidioc_s_ctrl(fd, &mut control)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,1011 @@
+//! Streams frames straight from a v4l2 device, without going through OpenCV.
+//!
+//! Frames come with the timestamp the driver gave them when it filled the
+//! buffer, which is much closer to when they were captured than anything
+//! measured once OpenCV hands them over. Only single planar capture is
+//! supported, into memory mapped buffers, DMABUFs from elsewhere or OpenCV
+//! images, and the buffers can be shared as DMABUFs rather than copied out.
+//! Devices that can't stream but can be read from are read from instead, with
+//! the frames timed as they arrive.
+use std::{
+    collections::VecDeque,
+    ffi::c_void,
+    fs::File,
+    io::Read,
+    mem,
+    num::NonZeroUsize,
+    os::fd::{
+        AsFd,
+        AsRawFd,
+        BorrowedFd,
+        OwnedFd,
+        RawFd
+    },
+    path::{
+        Path,
+        PathBuf
+    },
+    slice,
+    time::{
+        Duration,
+        Instant
+    }
+};
+use opencv::{
+    prelude::MatTraitConst,
+    core::{
+        Mat,
+        Rect
+    }
+};
+use nix::{
+    errno::Errno,
+    poll::{
+        poll,
+        PollFd,
+        PollFlags
+    },
+    sys::mman::{
+        mmap,
+        munmap,
+        MapFlags,
+        ProtFlags
+    },
+    time::{
+        clock_gettime,
+        ClockId
+    }
+};
+use super::{
+    control::{
+        find_controls,
+        Control
+    },
+    device_path,
+    dmabuf::{
+        dmabuf_size,
+        export_buffer,
+        sync_for_read
+    },
+    event::{
+        dequeue_event,
+        subscribe,
+        EventKind
+    },
+    fixture::Ioctls,
+    frame::{
+        to_mat,
+        V4L2_PIX_FMT_BGR24
+    },
+    input::{
+        find_inputs,
+        read_input
+    },
+    ioctl::*,
+    parameters::{
+        get_parameters,
+        set_parameters,
+        to_stream_parameters,
+        to_time_per_frame,
+        StreamParameters
+    },
+    userptr::{
+        is_native,
+        new_buffer_mat,
+        suits
+    },
+    selection::{
+        get_selection,
+        set_crop_selection,
+        to_rect,
+        to_v4l2_rect
+    },
+    timings::{
+        find_supported_dv_timings,
+        query_dv_timings,
+        to_dv_timings
+    },
+    find_frame_rates,
+    io_error,
+    ioctl_error,
+    open_device_file,
+    query_capabilities,
+    query_format,
+    Device,
+    DeviceEvent,
+    DvTimings,
+    Format,
+    FrameSizeType,
+    ImageFrame,
+    RawFrame,
+    Result,
+    VideoDeviceError,
+    VideoInput,
+    VideoStandard,
+    SUPPORTED_PIXEL_FORMATS
+};
+
+pub struct V4l2Device {
+    file: File,
+    path: PathBuf,
+    device_caps: u32,
+    format: Option<Format>,
+    /// `V4L2_MEMORY_MMAP` for the driver's own buffers, `V4L2_MEMORY_DMABUF`
+    /// for imported ones, or `V4L2_MEMORY_USERPTR` for Mats.
+    memory: u32,
+    buffers: Vec<MappedBuffer>,
+    /// The buffers as DMABUFs, those imported or the driver's own once exported.
+    shared: Vec<OwnedFd>,
+    /// The Mats captured into by address, by buffer index.
+    mat_buffers: Vec<Mat>,
+    /// Mats to capture into in place of those handed out as frames.
+    spare_mats: Vec<Mat>,
+    /// How much of each Mat the driver may fill.
+    mat_length: usize,
+    streaming: bool,
+    /// Frames are read from the device file, as it can't stream.
+    reading: bool,
+    /// The number of the next frame read, as reads don't come numbered.
+    read_sequence: u32,
+    /// Events dequeued while waiting for frames, kept for `wait_event`.
+    events: VecDeque<DeviceEvent>,
+    /// A source change event came since the last frame, so the format the
+    /// buffers were set up for is probably wrong.
+    source_changed: bool
+}
+
+/// How many events to keep for `wait_event` before dropping the oldest.
+const MAX_PENDING_EVENTS: usize = 64;
+
+/// A capture buffer shared with the driver, or an imported one mapped for reading.
+pub struct MappedBuffer {
+    pub start: *mut c_void,
+    pub length: usize
+}
+
+impl MappedBuffer {
+    /// Maps one of the driver's own buffers, at the offset it gave for it.
+    pub fn map(fd: RawFd, offset: u32, length: NonZeroUsize) -> Result<MappedBuffer> {
+        let start = unsafe {
+            mmap(
+                None,
+                length,
+                ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+                MapFlags::MAP_SHARED,
+                fd,
+                offset as nix::libc::off_t
+            ).map_err(ioctl_error("mmap"))?
+        };
+        Ok(MappedBuffer { start, length: length.get() })
+    }
+}
+
+impl Drop for MappedBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = munmap(self.start, self.length);
+        }
+    }
+}
+
+impl V4l2Device {
+    pub fn open(device: &Device) -> Result<Self> {
+        Self::open_path(&device_path(device)?)
+    }
+
+    pub fn open_path(path: &Path) -> Result<Self> {
+        let file = open_device_file(path)?;
+        let capabilities = query_capabilities(&file.as_raw_fd())?;
+        Ok(V4l2Device {
+            file,
+            path: path.to_path_buf(),
+            device_caps: capabilities.device_caps,
+            format: None,
+            memory: V4L2_MEMORY_MMAP,
+            buffers: Vec::new(),
+            shared: Vec::new(),
+            mat_buffers: Vec::new(),
+            spare_mats: Vec::new(),
+            mat_length: 0,
+            streaming: false,
+            reading: false,
+            read_sequence: 0,
+            events: VecDeque::new(),
+            source_changed: false
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn fd(&self) -> RawFd {
+        self.file.as_raw_fd()
+    }
+
+    pub fn can_stream(&self) -> bool {
+        self.device_caps & V4L2_CAP_VIDEO_CAPTURE != 0 && self.device_caps & V4L2_CAP_STREAMING != 0
+    }
+
+    /// Frames can be read from the device file, which is slower than streaming
+    /// as each is copied, and some are dropped while the last is read.
+    fn can_read(&self) -> bool {
+        self.device_caps & V4L2_CAP_VIDEO_CAPTURE != 0 && self.device_caps & V4L2_CAP_READWRITE != 0
+    }
+
+    /// The pixel formats the device offers, in the driver's order.
+    pub fn pixel_formats(&self) -> Result<Vec<u32>> {
+        let mut pixel_formats = Vec::new();
+        for index in 0.. {
+            match query_format(self.fd(), V4L2_BUF_TYPE_VIDEO_CAPTURE, index) {
+                Ok(fmt) => pixel_formats.push(fmt.pixel_format),
+                // EINVAL returned after last index
+                Err(Errno::EINVAL) => break,
+                Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_ENUM_FMT", errno })?
+            }
+        }
+        Ok(pixel_formats)
+    }
+
+    /// The device's inputs, with the status of the selected one as it is now,
+    /// and the standard the selected one is set to.
+    pub fn inputs(&self) -> Result<(Vec<VideoInput>, Option<VideoStandard>)> {
+        find_inputs(&mut Ioctls::Device(self.fd()))
+    }
+
+    /// Switches to capturing from another input. The format may change with it.
+    pub fn set_input(&mut self, index: u32) -> Result<()> {
+        self.prepare_to_switch()?;
+        let mut index = index as i32;
+        unsafe {
+            vidioc_s_input(self.fd(), &mut index)
+                .map_err(ioctl_error("VIDIOC_S_INPUT"))?;
+        }
+        Ok(())
+    }
+
+    /// Sets the analogue standard of the selected input, such as PAL or NTSC,
+    /// by its id. The frame size changes with it.
+    pub fn set_standard(&mut self, id: u64) -> Result<()> {
+        self.prepare_to_switch()?;
+        unsafe {
+            vidioc_s_std(self.fd(), &id)
+                .map_err(ioctl_error("VIDIOC_S_STD"))?;
+        }
+        Ok(())
+    }
+
+    /// All the timings an HDMI or DVI receiver can be set to.
+    pub fn supported_dv_timings(&self) -> Result<Vec<DvTimings>> {
+        find_supported_dv_timings(self.fd())
+    }
+
+    /// Sets the timings to those of the signal on the selected input, as HDMI
+    /// receivers need before a format can be set. `None` if there's no signal,
+    /// it isn't stable yet, or the receiver can't take it.
+    pub fn apply_detected_timings(&mut self) -> Result<Option<DvTimings>> {
+        let mut timings = match query_dv_timings(self.fd()) {
+            Ok(timings) => timings,
+            Err(Errno::ENOLINK) | Err(Errno::ENOLCK) | Err(Errno::ERANGE) => return Ok(None),
+            Err(errno) => return Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_QUERY_DV_TIMINGS", errno })
+        };
+        self.prepare_to_switch()?;
+        unsafe {
+            vidioc_s_dv_timings(self.fd(), &mut timings)
+                .map_err(ioctl_error("VIDIOC_S_DV_TIMINGS"))?;
+        }
+        self.source_changed = false;
+        Ok(to_dv_timings(&timings))
+    }
+
+    /// The controls the device has, such as brightness and exposure.
+    pub fn controls(&self) -> Result<Vec<Control>> {
+        find_controls(self.fd())
+    }
+
+    /// Asks to be told about events of this kind. Streaming subscribes to
+    /// source changes itself, so that `read_frame` can report them.
+    pub fn subscribe(&mut self, kind: EventKind) -> Result<()> {
+        subscribe(self.fd(), kind)
+            .map_err(ioctl_error("VIDIOC_SUBSCRIBE_EVENT"))
+    }
+
+    /// Asks to be told when the source on the selected input changes.
+    pub fn subscribe_source_changes(&mut self) -> Result<()> {
+        let input = read_input(self.fd()).unwrap_or(0) as u32;
+        self.subscribe(EventKind::SourceChange(input))
+    }
+
+    /// Waits up to `timeout` for the next event from the device, returning
+    /// `None` if none came. Events that arrived while waiting for frames are
+    /// returned first. A zero timeout just checks for one.
+    pub fn wait_event(&mut self, timeout: Duration) -> Result<Option<DeviceEvent>> {
+        let deadline = Instant::now() + timeout;
+        while self.events.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if !self.poll(PollFlags::POLLPRI, remaining)? {
+                break;
+            }
+        }
+        Ok(self.events.pop_front())
+    }
+
+    /// The area of the sensor being captured.
+    pub fn crop(&self) -> Result<Rect> {
+        get_selection(self.fd(), V4L2_SEL_TGT_CROP)
+            .map(|rect| to_rect(&rect))
+            .map_err(ioctl_error("VIDIOC_G_SELECTION"))
+    }
+
+    /// Captures just this area of the sensor, within the device's crop bounds.
+    /// Devices with a scaler deliver it at the size of the format, so it makes a
+    /// digital zoom, others change the format to match. Returns the area the
+    /// driver settled on, which may have been rounded.
+    pub fn set_crop(&mut self, crop: Rect) -> Result<Rect> {
+        self.prepare_to_switch()?;
+        set_crop_selection(self.fd(), to_v4l2_rect(&crop))
+            .map(|rect| to_rect(&rect))
+            .map_err(ioctl_error("VIDIOC_S_SELECTION"))
+    }
+
+    /// The frame rate and capture mode set.
+    pub fn stream_parameters(&self) -> Result<StreamParameters> {
+        get_parameters(self.fd())
+            .map(|parm| to_stream_parameters(&parm))
+            .map_err(ioctl_error("VIDIOC_G_PARM"))
+    }
+
+    /// The frame rates the device lists for the format set, fastest first.
+    pub fn frame_rates(&mut self) -> Result<Vec<f64>> {
+        let format = self.format()?;
+        let frame_size_type = FrameSizeType::Discrete { width: format.width, height: format.height };
+        Ok(find_frame_rates(&mut Ioctls::Device(self.fd()), format.pixel_format, &frame_size_type))
+    }
+
+    /// Asks for a frame rate, which the driver rounds to the nearest it can do
+    /// for the format, so set the format first and check the rate returned.
+    /// Drivers that can't set a rate leave it as it was.
+    pub fn set_frame_rate(&mut self, fps: f64) -> Result<StreamParameters> {
+        let mut parm = get_parameters(self.fd())
+            .map_err(ioctl_error("VIDIOC_G_PARM"))?;
+        if !to_stream_parameters(&parm).can_set_frame_rate {
+            return Ok(to_stream_parameters(&parm));
+        }
+        parm.parm.capture.timeperframe = to_time_per_frame(fps);
+        self.set_parameters(parm)
+    }
+
+    /// Turns the driver's high quality mode for stills on or off. Few drivers
+    /// have one, and those leave it off.
+    pub fn set_high_quality(&mut self, on: bool) -> Result<StreamParameters> {
+        let mut parm = get_parameters(self.fd())
+            .map_err(ioctl_error("VIDIOC_G_PARM"))?;
+        let capture = unsafe { &mut parm.parm.capture };
+        if on {
+            capture.capturemode |= V4L2_MODE_HIGHQUALITY;
+        } else {
+            capture.capturemode &= !V4L2_MODE_HIGHQUALITY;
+        }
+        self.set_parameters(parm)
+    }
+
+    /// Drivers refuse to change parameters while streaming.
+    fn set_parameters(&mut self, mut parm: V4l2Streamparm) -> Result<StreamParameters> {
+        if self.streaming {
+            self.stop_streaming()?;
+        }
+        set_parameters(self.fd(), &mut parm)
+            .map_err(ioctl_error("VIDIOC_S_PARM"))?;
+        Ok(to_stream_parameters(&parm))
+    }
+
+    /// Drivers refuse to switch inputs or standards while buffers are allocated.
+    fn prepare_to_switch(&mut self) -> Result<()> {
+        if self.streaming {
+            self.stop_streaming()?;
+        }
+        self.release_buffers()?;
+        self.format = None;
+        Ok(())
+    }
+
+    /// Asks for the frame size in the best pixel format the device has that can be
+    /// converted. The driver picks the nearest size it supports if it doesn't have
+    /// this one, so check the format returned.
+    pub fn set_format(&mut self, width: u32, height: u32) -> Result<Format> {
+        let offered = self.pixel_formats()?;
+        let mut nearest: Option<Format> = None;
+        for pixel_format in SUPPORTED_PIXEL_FORMATS.iter().filter(|f| offered.contains(f)) {
+            let format = self.set_pixel_format(width, height, *pixel_format)?;
+            if format.width == width && format.height == height {
+                return Ok(format);
+            }
+            nearest.get_or_insert(format);
+        }
+        let format = nearest.ok_or_else(|| VideoDeviceError::NoSupportedFormat(self.path.clone()))?;
+        // Later attempts replaced the nearest one with the driver.
+        self.set_pixel_format(format.width, format.height, format.pixel_format)
+    }
+
+    /// As `set_format`, but asking for BGR first where the device has it at this
+    /// size, as frames in BGR need no converting and can be captured straight
+    /// into images with `start_streaming_into_mats`.
+    pub fn set_native_format(&mut self, width: u32, height: u32) -> Result<Format> {
+        if self.pixel_formats()?.contains(&V4L2_PIX_FMT_BGR24) {
+            let format = self.set_pixel_format(width, height, V4L2_PIX_FMT_BGR24)?;
+            if format.width == width && format.height == height {
+                return Ok(format);
+            }
+        }
+        self.set_format(width, height)
+    }
+
+    pub fn set_pixel_format(&mut self, width: u32, height: u32, pixel_format: u32) -> Result<Format> {
+        if self.streaming {
+            self.stop_streaming()?;
+        }
+        // The buffers were sized for the old format.
+        self.release_buffers()?;
+        let format = unsafe {
+            let mut fmt: V4l2Format = mem::zeroed();
+            fmt.typ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+            fmt.fmt.pix.width = width;
+            fmt.fmt.pix.height = height;
+            fmt.fmt.pix.pixelformat = pixel_format;
+            fmt.fmt.pix.field = V4L2_FIELD_NONE;
+            vidioc_s_fmt(self.fd(), &mut fmt)
+                .map_err(ioctl_error("VIDIOC_S_FMT"))?;
+            to_format(&fmt.fmt.pix)
+        };
+        self.format = Some(format);
+        Ok(format)
+    }
+
+    /// The format set last, or the one the device was left in.
+    pub fn format(&mut self) -> Result<Format> {
+        if let Some(format) = self.format {
+            return Ok(format);
+        }
+        let format = unsafe {
+            let mut fmt: V4l2Format = mem::zeroed();
+            fmt.typ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+            vidioc_g_fmt(self.fd(), &mut fmt)
+                .map_err(ioctl_error("VIDIOC_G_FMT"))?;
+            to_format(&fmt.fmt.pix)
+        };
+        self.format = Some(format);
+        Ok(format)
+    }
+
+    /// Starts capturing into `buffer_count` buffers. The driver may use more or fewer.
+    /// More buffers means fewer dropped frames when the caller is slow to read them.
+    /// Devices that can't stream but can be read from are read from instead, and
+    /// the driver keeps its own buffers.
+    pub fn start_streaming(&mut self, buffer_count: u32) -> Result<()> {
+        if self.streaming {
+            return Ok(());
+        }
+        if !self.can_stream() && self.can_read() {
+            return self.start_reading();
+        }
+        if !self.can_stream() {
+            return Err(VideoDeviceError::StreamingUnsupported(self.path.clone()));
+        }
+        self.format()?;
+        self.release_buffers()?;
+        self.request_buffers(buffer_count)?;
+        self.stream_buffers()
+    }
+
+    /// As `start_streaming`, but capturing into DMABUFs from elsewhere, such as
+    /// `allocate_dmabufs`, an encoder or a display, rather than the driver's own
+    /// buffers. Each must be big enough for a frame of the format set.
+    pub fn start_streaming_imported(&mut self, dmabufs: Vec<OwnedFd>) -> Result<()> {
+        if self.streaming {
+            self.stop_streaming()?;
+        }
+        if !self.can_stream() {
+            return Err(VideoDeviceError::StreamingUnsupported(self.path.clone()));
+        }
+        self.format()?;
+        self.release_buffers()?;
+        self.import_buffers(dmabufs)?;
+        self.stream_buffers()
+    }
+
+    /// As `start_streaming`, but capturing into Mats, so that frames in a native
+    /// format, as `set_native_format` asks for, come from `read_image` without
+    /// being copied. Mats from `pool` are used first where they suit the format,
+    /// and must not be shared with anything else, as the driver writes to them.
+    /// More are allocated as needed. Drivers that won't capture into memory they
+    /// didn't allocate, or not into this memory, stream into their own buffers.
+    pub fn start_streaming_into_mats(&mut self, buffer_count: u32, pool: Vec<Mat>) -> Result<()> {
+        if self.streaming {
+            return Ok(());
+        }
+        if !self.can_stream() {
+            return self.start_streaming(buffer_count);
+        }
+        let format = self.format()?;
+        self.release_buffers()?;
+        self.spare_mats = pool.into_iter()
+            .filter(|mat| suits(mat, &format))
+            .collect();
+        match self.request_mat_buffers(buffer_count, &format).and_then(|_| self.stream_buffers()) {
+            Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_REQBUFS" | "VIDIOC_QBUF", errno: Errno::EINVAL | Errno::EFAULT }) => {
+                self.release_buffers()?;
+                self.start_streaming(buffer_count)
+            },
+            result => result
+        }
+    }
+
+    /// The driver starts capturing at the first read.
+    fn start_reading(&mut self) -> Result<()> {
+        self.format()?;
+        let _ = self.subscribe_source_changes();
+        self.read_sequence = 0;
+        self.reading = true;
+        self.streaming = true;
+        Ok(())
+    }
+
+    /// Queues every buffer and starts the stream.
+    fn stream_buffers(&mut self) -> Result<()> {
+        for index in 0..self.buffer_count() as u32 {
+            self.queue_buffer(index)?;
+        }
+        // Most devices never change source, and don't support the event.
+        let _ = self.subscribe_source_changes();
+        let typ = V4L2_BUF_TYPE_VIDEO_CAPTURE as i32;
+        unsafe {
+            vidioc_streamon(self.fd(), &typ)
+                .map_err(ioctl_error("VIDIOC_STREAMON"))?;
+        }
+        self.streaming = true;
+        Ok(())
+    }
+
+    pub fn stop_streaming(&mut self) -> Result<()> {
+        if !self.streaming {
+            return Ok(());
+        }
+        // Drivers stop capturing for reads when they aren't read from for a while.
+        if self.reading {
+            self.reading = false;
+            self.streaming = false;
+            return Ok(());
+        }
+        // Also takes back every buffer from the driver.
+        let typ = V4L2_BUF_TYPE_VIDEO_CAPTURE as i32;
+        unsafe {
+            vidioc_streamoff(self.fd(), &typ)
+                .map_err(ioctl_error("VIDIOC_STREAMOFF"))?;
+        }
+        self.streaming = false;
+        Ok(())
+    }
+
+    /// Waits up to `timeout` for the next frame, returning `None` if none came.
+    /// Frames the driver marks as damaged are skipped. Fails with `SourceChanged`
+    /// if the source changed resolution, after which the timings and format need
+    /// setting again.
+    pub fn read_frame(&mut self, timeout: Duration) -> Result<Option<RawFrame>> {
+        let format = self.format()?;
+        if self.reading {
+            return self.read_device(format, timeout);
+        }
+        let Some(buf) = self.next_buffer(timeout)? else {
+            return Ok(None);
+        };
+        let data = self.copy_buffer(&buf);
+        self.queue_buffer(buf.index)?;
+        Ok(Some(RawFrame {
+            data: data?,
+            format,
+            sequence: buf.sequence,
+            timestamp: to_timestamp(&buf)
+        }))
+    }
+
+    /// As `read_frame`, but converted to an image. Frames in a native format
+    /// captured into Mats come as the Mat they were captured into, with another
+    /// given to the driver in its place, so aren't copied at all.
+    pub fn read_image(&mut self, timeout: Duration) -> Result<Option<ImageFrame>> {
+        if self.memory != V4L2_MEMORY_USERPTR {
+            let Some(frame) = self.read_frame(timeout)? else {
+                return Ok(None);
+            };
+            return Ok(Some(ImageFrame {
+                image: frame.to_mat()?,
+                sequence: frame.sequence,
+                timestamp: frame.timestamp
+            }));
+        }
+        let format = self.format()?;
+        let Some(buf) = self.next_buffer(timeout)? else {
+            return Ok(None);
+        };
+        let image = if is_native(&format) {
+            self.take_mat_buffer(buf.index as usize, &format)
+        } else {
+            let data = unsafe { slice::from_raw_parts(self.buffer_start(buf.index as usize), self.used_length(&buf)) };
+            to_mat(data, &format)
+        };
+        // With the new Mat in place, or the same one where there wasn't one to be had.
+        self.queue_buffer(buf.index)?;
+        Ok(Some(ImageFrame {
+            image: image?,
+            sequence: buf.sequence,
+            timestamp: to_timestamp(&buf)
+        }))
+    }
+
+    /// Swaps a spare Mat, or a new one, for the one the driver filled.
+    fn take_mat_buffer(&mut self, index: usize, format: &Format) -> Result<Mat> {
+        let replacement = match self.spare_mats.pop() {
+            Some(mat) => mat,
+            None => new_buffer_mat(format)?
+        };
+        Ok(mem::replace(&mut self.mat_buffers[index], replacement))
+    }
+
+    /// As `read_frame`, but rather than copying the frame out, shares the buffer
+    /// it's in as a DMABUF, which is much quicker for large frames. The buffer
+    /// goes back to the driver when the frame is dropped, so keep it only for as
+    /// long as whatever it's passed to needs it, or the driver runs out.
+    pub fn read_shared_frame(&mut self, timeout: Duration) -> Result<Option<SharedFrame<'_>>> {
+        // Frames read from the device are only ever copied, and Mats can't be shared.
+        if self.reading || self.memory == V4L2_MEMORY_USERPTR {
+            return Err(VideoDeviceError::StreamingUnsupported(self.path.clone()));
+        }
+        // The driver's own buffers are exported once, when first shared.
+        if self.shared.is_empty() {
+            for index in 0..self.buffers.len() as u32 {
+                let dmabuf = export_buffer(self.fd(), index)
+                    .map_err(ioctl_error("VIDIOC_EXPBUF"))?;
+                self.shared.push(dmabuf);
+            }
+        }
+        let Some(buf) = self.next_buffer(timeout)? else {
+            return Ok(None);
+        };
+        Ok(Some(SharedFrame {
+            length: self.used_length(&buf),
+            sequence: buf.sequence,
+            timestamp: to_timestamp(&buf),
+            index: buf.index,
+            device: self
+        }))
+    }
+
+    /// Waits up to `timeout` for a frame to read from the device file, and reads it.
+    fn read_device(&mut self, format: Format, timeout: Duration) -> Result<Option<RawFrame>> {
+        if !self.poll(PollFlags::POLLIN, timeout)? {
+            return Ok(None);
+        }
+        if self.source_changed {
+            self.source_changed = false;
+            return Err(VideoDeviceError::SourceChanged(self.path.clone()));
+        }
+        // Compressed frames come in less than the most they could take.
+        let mut data = vec![0; format.size_image as usize];
+        let length = (&self.file).read(&mut data)
+            .map_err(|err| io_error(&self.path, err))?;
+        data.truncate(length);
+        // Read when it arrived, on the same clock drivers stamp buffers with.
+        let timestamp = clock_gettime(ClockId::CLOCK_MONOTONIC)
+            .map(Duration::from)
+            .map_err(ioctl_error("clock_gettime"))?;
+        let sequence = self.read_sequence;
+        self.read_sequence = self.read_sequence.wrapping_add(1);
+        Ok(Some(RawFrame {
+            data,
+            format,
+            sequence,
+            timestamp
+        }))
+    }
+
+    /// Waits up to `timeout` for the next buffer the driver filled, returning
+    /// `None` if none came. Damaged frames go straight back to the driver.
+    fn next_buffer(&mut self, timeout: Duration) -> Result<Option<V4l2Buffer>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if !self.poll(PollFlags::POLLIN, deadline.saturating_duration_since(Instant::now()))? {
+                return Ok(None);
+            }
+            if self.source_changed {
+                self.source_changed = false;
+                return Err(VideoDeviceError::SourceChanged(self.path.clone()));
+            }
+            let buf = self.dequeue_buffer()?;
+            if buf.flags & V4L2_BUF_FLAG_ERROR == 0 {
+                return Ok(Some(buf));
+            }
+            self.queue_buffer(buf.index)?;
+        }
+    }
+
+    fn buffer_count(&self) -> usize {
+        match self.memory {
+            V4L2_MEMORY_USERPTR => self.mat_buffers.len(),
+            _ => self.buffers.len()
+        }
+    }
+
+    fn buffer_start(&self, index: usize) -> *const u8 {
+        match self.memory {
+            V4L2_MEMORY_USERPTR => self.mat_buffers[index].data(),
+            _ => self.buffers[index].start as *const u8
+        }
+    }
+
+    fn buffer_length(&self, index: usize) -> usize {
+        match self.memory {
+            V4L2_MEMORY_USERPTR => self.mat_length,
+            _ => self.buffers[index].length
+        }
+    }
+
+    fn used_length(&self, buf: &V4l2Buffer) -> usize {
+        let length = self.buffer_length(buf.index as usize);
+        // Some drivers leave bytesused at 0 for uncompressed formats.
+        match buf.bytesused as usize {
+            0 => length,
+            used => used.min(length)
+        }
+    }
+
+    fn copy_buffer(&self, buf: &V4l2Buffer) -> Result<Vec<u8>> {
+        let start = self.buffer_start(buf.index as usize);
+        let length = self.used_length(buf);
+        if self.memory != V4L2_MEMORY_DMABUF {
+            return Ok(unsafe { slice::from_raw_parts(start, length).to_vec() });
+        }
+        // Whatever wrote an imported buffer may have left it in a cache the mapping doesn't see.
+        let dmabuf = self.shared[buf.index as usize].as_raw_fd();
+        sync_for_read(dmabuf, true)
+            .map_err(ioctl_error("DMA_BUF_IOCTL_SYNC"))?;
+        let data = unsafe { slice::from_raw_parts(start, length).to_vec() };
+        sync_for_read(dmabuf, false)
+            .map_err(ioctl_error("DMA_BUF_IOCTL_SYNC"))?;
+        Ok(data)
+    }
+
+    /// Waits up to `timeout` for the device to be ready for `flags`, returning
+    /// false if it wasn't. Events that arrive meanwhile are dequeued, and a
+    /// source change stops the wait.
+    fn poll(&mut self, flags: PollFlags, timeout: Duration) -> Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut fds = [PollFd::new(self.fd(), flags | PollFlags::POLLPRI)];
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match poll(&mut fds, remaining.as_millis().min(i32::MAX as u128) as i32) {
+                Ok(0) => return Ok(false),
+                Ok(_) => {
+                    let revents = fds[0].revents().unwrap_or(PollFlags::empty());
+                    if revents.intersects(PollFlags::POLLHUP | PollFlags::POLLNVAL) {
+                        return Err(VideoDeviceError::Ioctl { ioctl: "poll", errno: Errno::ENODEV });
+                    }
+                    if revents.contains(PollFlags::POLLPRI) {
+                        self.dequeue_events()?;
+                    }
+                    // Errors are left for the ioctl that follows to report.
+                    if self.source_changed || !revents.difference(PollFlags::POLLPRI).is_empty() || flags == PollFlags::POLLPRI {
+                        return Ok(true);
+                    }
+                },
+                Err(Errno::EINTR) => continue,
+                Err(errno) => return Err(VideoDeviceError::Ioctl { ioctl: "poll", errno })
+            }
+        }
+    }
+
+    fn dequeue_events(&mut self) -> Result<()> {
+        while let Some(event) = dequeue_event(self.fd())? {
+            if let DeviceEvent::SourceChange { resolution: true, .. } = event {
+                self.source_changed = true;
+            }
+            if self.events.len() == MAX_PENDING_EVENTS {
+                self.events.pop_front();
+            }
+            self.events.push_back(event);
+        }
+        Ok(())
+    }
+
+    /// Asks the driver for `count` buffers of the memory type set, returning
+    /// how many it gave.
+    fn reqbufs(&self, count: u32) -> Result<u32> {
+        let mut req = V4l2RequestBuffers {
+            count,
+            typ: V4L2_BUF_TYPE_VIDEO_CAPTURE,
+            memory: self.memory,
+            capabilities: 0,
+            flags: 0,
+            reserved: [0; 3]
+        };
+        unsafe {
+            vidioc_reqbufs(self.fd(), &mut req)
+                .map_err(ioctl_error("VIDIOC_REQBUFS"))?;
+        }
+        Ok(req.count)
+    }
+
+    fn request_buffers(&mut self, count: u32) -> Result<()> {
+        self.memory = V4L2_MEMORY_MMAP;
+        let count = self.reqbufs(count)?;
+        if count == 0 {
+            return Err(VideoDeviceError::StreamingUnsupported(self.path.clone()));
+        }
+
+        for index in 0..count {
+            let buf = unsafe {
+                let mut buf = self.new_buffer(index);
+                vidioc_querybuf(self.fd(), &mut buf)
+                    .map_err(ioctl_error("VIDIOC_QUERYBUF"))?;
+                buf
+            };
+            let length = NonZeroUsize::new(buf.length as usize)
+                .ok_or_else(|| VideoDeviceError::StreamingUnsupported(self.path.clone()))?;
+            self.buffers.push(MappedBuffer::map(self.fd(), unsafe { buf.m.offset }, length)?);
+        }
+        Ok(())
+    }
+
+    /// Has the driver capture into the DMABUFs, mapping each to read frames from.
+    /// Drivers that need fewer are given the first few.
+    fn import_buffers(&mut self, dmabufs: Vec<OwnedFd>) -> Result<()> {
+        self.memory = V4L2_MEMORY_DMABUF;
+        let count = self.reqbufs(dmabufs.len() as u32)?;
+        for dmabuf in dmabufs.into_iter().take(count as usize) {
+            let length = dmabuf_size(dmabuf.as_raw_fd())
+                .ok()
+                .and_then(NonZeroUsize::new)
+                .ok_or_else(|| VideoDeviceError::StreamingUnsupported(self.path.clone()))?;
+            let start = unsafe {
+                mmap(
+                    None,
+                    length,
+                    ProtFlags::PROT_READ,
+                    MapFlags::MAP_SHARED,
+                    dmabuf.as_raw_fd(),
+                    0
+                ).map_err(ioctl_error("mmap"))?
+            };
+            self.buffers.push(MappedBuffer { start, length: length.get() });
+            self.shared.push(dmabuf);
+        }
+        if self.buffers.is_empty() {
+            return Err(VideoDeviceError::StreamingUnsupported(self.path.clone()));
+        }
+        Ok(())
+    }
+
+    /// Has the driver capture into Mats, those spare first. Drivers that need
+    /// fewer than `count` are given fewer.
+    fn request_mat_buffers(&mut self, count: u32, format: &Format) -> Result<()> {
+        self.memory = V4L2_MEMORY_USERPTR;
+        let count = self.reqbufs(count)?;
+        if count == 0 {
+            return Err(VideoDeviceError::StreamingUnsupported(self.path.clone()));
+        }
+        self.mat_length = format.size_image as usize;
+        for _ in 0..count {
+            let mat = match self.spare_mats.pop() {
+                Some(mat) => mat,
+                None => new_buffer_mat(format)?
+            };
+            self.mat_buffers.push(mat);
+        }
+        Ok(())
+    }
+
+    /// Unmaps the buffers and lets the driver free them. Buffers exported as
+    /// DMABUFs live on until whatever they were passed to closes them.
+    fn release_buffers(&mut self) -> Result<()> {
+        self.spare_mats.clear();
+        if self.buffers.is_empty() && self.mat_buffers.is_empty() {
+            return Ok(());
+        }
+        self.buffers.clear();
+        self.shared.clear();
+        // Mats are only freed once the driver has let go of them, and leaked
+        // rather than freed if it might still write to them.
+        if let Err(err) = self.reqbufs(0) {
+            mem::forget(mem::take(&mut self.mat_buffers));
+            return Err(err);
+        }
+        self.mat_buffers.clear();
+        self.memory = V4L2_MEMORY_MMAP;
+        Ok(())
+    }
+
+    fn new_buffer(&self, index: u32) -> V4l2Buffer {
+        let mut buf: V4l2Buffer = unsafe { mem::zeroed() };
+        buf.index = index;
+        buf.typ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        buf.memory = self.memory;
+        buf
+    }
+
+    fn queue_buffer(&self, index: u32) -> Result<()> {
+        let mut buf = self.new_buffer(index);
+        match self.memory {
+            V4L2_MEMORY_DMABUF => buf.m.fd = self.shared[index as usize].as_raw_fd(),
+            V4L2_MEMORY_USERPTR => buf.m.userptr = self.mat_buffers[index as usize].data() as nix::libc::c_ulong,
+            _ => {}
+        }
+        if self.memory != V4L2_MEMORY_MMAP {
+            buf.length = self.buffer_length(index as usize) as u32;
+        }
+        unsafe {
+            vidioc_qbuf(self.fd(), &mut buf)
+                .map_err(ioctl_error("VIDIOC_QBUF"))?;
+        }
+        Ok(())
+    }
+
+    fn dequeue_buffer(&self) -> Result<V4l2Buffer> {
+        let mut buf = self.new_buffer(0);
+        unsafe {
+            vidioc_dqbuf(self.fd(), &mut buf)
+                .map_err(ioctl_error("VIDIOC_DQBUF"))?;
+        }
+        Ok(buf)
+    }
+}
+
+/// For polling the device along with other files. It's readable when a frame
+/// is ready, and has priority data when an event is.
+impl AsRawFd for V4l2Device {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd()
+    }
+}
+
+impl Drop for V4l2Device {
+    fn drop(&mut self) {
+        let _ = self.stop_streaming();
+    }
+}
+
+/// A frame left in the buffer the driver filled, shared as a DMABUF. The
+/// buffer goes back to the driver when this is dropped.
+pub struct SharedFrame<'a> {
+    device: &'a V4l2Device,
+    index: u32,
+    /// How many bytes of the buffer the frame takes.
+    pub length: usize,
+    pub sequence: u32,
+    pub timestamp: Duration
+}
+
+impl SharedFrame<'_> {
+    /// The DMABUF holding the frame. It can be passed to another process over
+    /// a Unix socket, but the frame in it is only good until this is dropped.
+    pub fn dmabuf(&self) -> BorrowedFd<'_> {
+        self.device.shared[self.index as usize].as_fd()
+    }
+}
+
+impl Drop for SharedFrame<'_> {
+    fn drop(&mut self) {
+        let _ = self.device.queue_buffer(self.index);
+    }
+}
+
+fn to_timestamp(buf: &V4l2Buffer) -> Duration {
+    Duration::new(buf.timestamp.tv_sec as u64, buf.timestamp.tv_usec as u32 * 1000)
+}
+
+pub fn to_format(pix: &V4l2PixFormat) -> Format {
+    Format {
+        width: pix.width,
+        height: pix.height,
+        pixel_format: pix.pixelformat,
+        bytes_per_line: pix.bytesperline,
+        size_image: pix.sizeimage
+    }
+}
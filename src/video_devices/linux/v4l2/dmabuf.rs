@@ -0,0 +1,110 @@
+//! Sharing capture buffers as DMABUF file descriptors, which encoders, displays
+//! and other processes can use in place without the frame being copied.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/dmabuf.html
+use std::{
+    fs::File,
+    os::fd::{
+        AsRawFd,
+        FromRawFd,
+        OwnedFd,
+        RawFd
+    },
+    path::Path
+};
+use nix::{
+    fcntl::{
+        fcntl,
+        FcntlArg,
+        SealFlag
+    },
+    sys::memfd::{
+        memfd_create,
+        MemFdCreateFlag
+    },
+    unistd::{
+        ftruncate,
+        lseek,
+        Whence
+    }
+};
+use super::{
+    ioctl::*,
+    userptr::page_size,
+    ioctl_error,
+    io_error,
+    Result
+};
+
+const UDMABUF_PATH: &str = "/dev/udmabuf";
+
+/// Exports a memory mapped capture buffer. The buffer lives on for as long as
+/// the descriptor is open, even after the device has let it go.
+pub fn export_buffer(fd: RawFd, index: u32) -> nix::Result<OwnedFd> {
+    let mut export = V4l2Exportbuffer {
+        typ: V4L2_BUF_TYPE_VIDEO_CAPTURE,
+        index,
+        plane: 0,
+        flags: (nix::libc::O_CLOEXEC | nix::libc::O_RDONLY) as u32,
+        fd: -1,
+        reserved: [0; 11]
+    };
+    unsafe {
+        vidioc_expbuf(fd, &mut export)?;
+        Ok(OwnedFd::from_raw_fd(export.fd))
+    }
+}
+
+/// Allocates `count` buffers of at least `size` bytes from ordinary memory
+/// with the udmabuf driver, for devices to capture into where nothing else
+/// provides DMABUFs. Needs access to /dev/udmabuf, which is often root only.
+pub fn allocate_dmabufs(count: usize, size: usize) -> Result<Vec<OwnedFd>> {
+    let udmabuf_path = Path::new(UDMABUF_PATH);
+    let udmabuf = File::open(udmabuf_path)
+        .map_err(|err| io_error(udmabuf_path, err))?;
+    // udmabuf takes whole pages.
+    let size = size.div_ceil(page_size()) * page_size();
+    let mut buffers = Vec::with_capacity(count);
+    for _ in 0..count {
+        let memfd = unsafe {
+            OwnedFd::from_raw_fd(memfd_create(c"opencv-example-rs", MemFdCreateFlag::MFD_CLOEXEC | MemFdCreateFlag::MFD_ALLOW_SEALING)
+                .map_err(ioctl_error("memfd_create"))?)
+        };
+        ftruncate(memfd.as_raw_fd(), size as nix::libc::off_t)
+            .map_err(ioctl_error("ftruncate"))?;
+        // udmabuf only takes memory that can't be shrunk from under it.
+        fcntl(memfd.as_raw_fd(), FcntlArg::F_ADD_SEALS(SealFlag::F_SEAL_SHRINK))
+            .map_err(ioctl_error("F_ADD_SEALS"))?;
+        let create = UdmabufCreate {
+            memfd: memfd.as_raw_fd() as u32,
+            flags: UDMABUF_FLAGS_CLOEXEC,
+            offset: 0,
+            size: size as u64
+        };
+        let dmabuf = unsafe {
+            udmabuf_create(udmabuf.as_raw_fd(), &create)
+                .map_err(ioctl_error("UDMABUF_CREATE"))?
+        };
+        buffers.push(unsafe { OwnedFd::from_raw_fd(dmabuf) });
+    }
+    Ok(buffers)
+}
+
+/// How big a DMABUF is, which seeking to its end gives.
+pub fn dmabuf_size(fd: RawFd) -> nix::Result<usize> {
+    let size = lseek(fd, 0, Whence::SeekEnd)?;
+    lseek(fd, 0, Whence::SeekSet)?;
+    Ok(size as usize)
+}
+
+/// Brackets reading a DMABUF through a mapping, so caches are kept in step
+/// with whatever wrote it.
+pub fn sync_for_read(fd: RawFd, start: bool) -> nix::Result<()> {
+    let sync = DmaBufSync {
+        flags: DMA_BUF_SYNC_READ | if start { DMA_BUF_SYNC_START } else { DMA_BUF_SYNC_END }
+    };
+    unsafe {
+        dma_buf_ioctl_sync(fd, &sync)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,124 @@
+//! Events the driver reports alongside the frames, such as a control changing
+//! value or the source on an HDMI input changing resolution. Only events
+//! subscribed to are reported.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-dqevent.html
+use std::{
+    mem,
+    os::fd::RawFd
+};
+use nix::errno::Errno;
+use super::{
+    ioctl::*,
+    Result,
+    VideoDeviceError
+};
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+pub enum DeviceEvent {
+    /// A control changed, such as the exposure time while auto exposure is on,
+    /// or a privacy shutter being closed. Also sent with the control's value
+    /// when it's subscribed to.
+    Control {
+        id: u32,
+        value: i64,
+        value_changed: bool,
+        flags_changed: bool,
+        /// The control has no effect at the moment, such as exposure time
+        /// while auto exposure is on.
+        inactive: bool
+    },
+    /// The source on an input was plugged in, unplugged or changed. When
+    /// `resolution` is set, the timings and format need setting again before
+    /// streaming will work.
+    SourceChange {
+        input: u32,
+        resolution: bool
+    },
+    /// The last frame has been delivered, as decoders and file readers report.
+    EndOfStream,
+    /// A frame has started arriving, before it's ready to dequeue.
+    FrameSync {
+        sequence: u32
+    }
+}
+
+/// The kinds of events that can be subscribed to.
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub enum EventKind {
+    /// Changes to the control with this id.
+    Control(u32),
+    /// Source changes on this input.
+    SourceChange(u32),
+    EndOfStream,
+    FrameSync
+}
+
+pub fn subscribe(fd: RawFd, kind: EventKind) -> nix::Result<()> {
+    let (typ, id, flags) = match kind {
+        // Sending the current value straight away saves asking for it.
+        EventKind::Control(id) => (V4L2_EVENT_CTRL, id, V4L2_EVENT_SUB_FL_SEND_INITIAL),
+        EventKind::SourceChange(input) => (V4L2_EVENT_SOURCE_CHANGE, input, 0),
+        EventKind::EndOfStream => (V4L2_EVENT_EOS, 0, 0),
+        EventKind::FrameSync => (V4L2_EVENT_FRAME_SYNC, 0, 0)
+    };
+    let subscription = V4l2EventSubscription {
+        typ,
+        id,
+        flags,
+        reserved: [0; 5]
+    };
+    unsafe {
+        vidioc_subscribe_event(fd, &subscription)?;
+    }
+    Ok(())
+}
+
+/// The next event waiting, or `None` if there isn't one. Events of types
+/// that aren't understood are skipped.
+pub fn dequeue_event(fd: RawFd) -> Result<Option<DeviceEvent>> {
+    loop {
+        let event = unsafe {
+            let mut event: V4l2Event = mem::zeroed();
+            match vidioc_dqevent(fd, &mut event) {
+                Ok(_) => event,
+                Err(Errno::ENOENT) => return Ok(None),
+                Err(errno) => return Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_DQEVENT", errno })
+            }
+        };
+        if let Some(event) = to_event(&event) {
+            return Ok(Some(event));
+        }
+    }
+}
+
+fn to_event(event: &V4l2Event) -> Option<DeviceEvent> {
+    match event.typ {
+        V4L2_EVENT_CTRL => {
+            let ctrl = unsafe { event.u.ctrl };
+            Some(DeviceEvent::Control {
+                id: event.id,
+                value: match ctrl.typ {
+                    V4L2_CTRL_TYPE_INTEGER64 => unsafe { ctrl.value.value64 },
+                    _ => unsafe { ctrl.value.value as i64 }
+                },
+                value_changed: ctrl.changes & V4L2_EVENT_CTRL_CH_VALUE != 0,
+                flags_changed: ctrl.changes & V4L2_EVENT_CTRL_CH_FLAGS != 0,
+                inactive: ctrl.flags & V4L2_CTRL_FLAG_INACTIVE != 0
+            })
+        },
+        V4L2_EVENT_SOURCE_CHANGE => Some(DeviceEvent::SourceChange {
+            input: event.id,
+            resolution: unsafe { event.u.src_change.changes } & V4L2_EVENT_SRC_CH_RESOLUTION != 0
+        }),
+        V4L2_EVENT_EOS => Some(DeviceEvent::EndOfStream),
+        V4L2_EVENT_FRAME_SYNC => Some(DeviceEvent::FrameSync {
+            sequence: unsafe { event.u.frame_sync.frame_sequence }
+        }),
+        _ => None
+    }
+}
@@ -0,0 +1,324 @@
+//! Records what v4l2 devices answer while they're being enumerated, and plays
+//! the answers back in place of the devices.
+//!
+//! Setting `OPENCV_EXAMPLE_V4L2_RECORD` to a file name saves what the devices
+//! plugged in said, and setting `OPENCV_EXAMPLE_V4L2_REPLAY` to a saved file
+//! lists the devices it describes instead of the ones plugged in. That way a
+//! discovery problem with an odd driver can be looked at without the hardware.
+//!
+//! Each device starts with a `device` line giving its device file and the name
+//! of its /dev/v4l symlink. Every ioctl made on it follows on a line of its own:
+//! the ioctl, the arguments it was called with, then either the struct the
+//! driver filled in as hex, or the errno it failed with.
+//!
+//! ```text
+//! device /dev/video0 usb-046d_HD_Pro_Webcam_C920-video-index0
+//! QUERYCAP 757663766964656f0000...
+//! ENUM_FMT 1 0 00000000010000000000...
+//! ENUM_FMT 1 2 errno:22
+//! ```
+use std::{
+    ffi::c_int,
+    fmt::Write as _,
+    fs::{
+        read_to_string,
+        write
+    },
+    mem,
+    os::fd::RawFd,
+    path::Path,
+    ptr,
+    slice
+};
+use nix::errno::Errno;
+use super::{
+    input::{
+        query_input,
+        query_standard,
+        read_input,
+        read_standard
+    },
+    ioctl::*,
+    selection::{
+        get_selection,
+        query_crop_capabilities
+    },
+    timings::query_dv_timings,
+    query_frame_intervals,
+    query_frame_sizes,
+    query_format,
+    read_capabilities,
+    Result,
+    VideoDeviceError
+};
+
+pub const RECORD_VARIABLE: &str = "OPENCV_EXAMPLE_V4L2_RECORD";
+pub const REPLAY_VARIABLE: &str = "OPENCV_EXAMPLE_V4L2_REPLAY";
+
+/// The ioctls that are recorded, and the size of the struct each fills in.
+const IOCTLS: [(&str, usize); 11] = [
+    ("QUERYCAP", mem::size_of::<V4l2Capability>()),
+    ("ENUM_FMT", mem::size_of::<V4l2Fmtdesc>()),
+    ("ENUM_FRAMESIZES", mem::size_of::<V4l2Frmsizeenum>()),
+    ("ENUM_FRAMEINTERVALS", mem::size_of::<V4l2Frmivalenum>()),
+    ("ENUMINPUT", mem::size_of::<V4l2Input>()),
+    ("G_INPUT", mem::size_of::<c_int>()),
+    ("ENUMSTD", mem::size_of::<V4l2Standard>()),
+    ("G_STD", mem::size_of::<u64>()),
+    ("QUERY_DV_TIMINGS", mem::size_of::<V4l2DvTimings>()),
+    ("G_SELECTION", mem::size_of::<V4l2Rect>()),
+    ("CROPCAP", mem::size_of::<V4l2Cropcap>())
+];
+
+#[derive(Default)]
+pub struct Fixture {
+    pub devices: Vec<FixtureDevice>
+}
+
+pub struct FixtureDevice {
+    pub path: String,
+    pub unique_id: String,
+    answers: Vec<Answer>
+}
+
+struct Answer {
+    ioctl: &'static str,
+    args: Vec<u32>,
+    result: nix::Result<Vec<u8>>
+}
+
+impl Fixture {
+    pub fn load(path: &Path) -> Result<Fixture> {
+        let text = read_to_string(path)
+            .map_err(|source| VideoDeviceError::Io { path: path.to_path_buf(), source })?;
+        let mut fixture = Fixture::default();
+        for (number, line) in text.lines().enumerate() {
+            let invalid = |reason| VideoDeviceError::InvalidFixture { path: path.to_path_buf(), line: number + 1, reason };
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words.as_slice() {
+                [] => continue,
+                [word, ..] if word.starts_with('#') => continue,
+                ["device", device_path, unique_id] => {
+                    fixture.add_device(device_path, unique_id);
+                },
+                [name, args @ .., response] => {
+                    let (ioctl, size) = IOCTLS.iter()
+                        .find(|(ioctl, _)| ioctl == name)
+                        .copied()
+                        .ok_or_else(|| invalid("unknown ioctl"))?;
+                    let device = fixture.devices.last_mut()
+                        .ok_or_else(|| invalid("ioctl before the first device"))?;
+                    let args = args.iter()
+                        .map(|arg| arg.parse::<u32>())
+                        .collect::<std::result::Result<Vec<u32>, _>>()
+                        .map_err(|_| invalid("bad argument"))?;
+                    let result = match response.strip_prefix("errno:") {
+                        Some(errno) => Err(errno.parse().map(Errno::from_i32).map_err(|_| invalid("bad errno"))?),
+                        None => Ok(from_hex(response).filter(|bytes| bytes.len() == size)
+                            .ok_or_else(|| invalid("bad response"))?)
+                    };
+                    device.answers.push(Answer { ioctl, args, result });
+                },
+                _ => return Err(invalid("expected a device or an ioctl"))
+            }
+        }
+        Ok(fixture)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut text = String::new();
+        for device in &self.devices {
+            let _ = writeln!(text, "device {} {}", device.path, device.unique_id);
+            for answer in &device.answers {
+                text.push_str(answer.ioctl);
+                for arg in &answer.args {
+                    let _ = write!(text, " {}", arg);
+                }
+                match &answer.result {
+                    Ok(bytes) => {
+                        text.push(' ');
+                        for byte in bytes {
+                            let _ = write!(text, "{:02x}", byte);
+                        }
+                    },
+                    Err(errno) => {
+                        let _ = write!(text, " errno:{}", *errno as i32);
+                    }
+                }
+                text.push('\n');
+            }
+        }
+        write(path, text)
+            .map_err(|source| VideoDeviceError::Io { path: path.to_path_buf(), source })
+    }
+
+    pub fn add_device(&mut self, path: &str, unique_id: &str) -> &mut FixtureDevice {
+        self.devices.push(FixtureDevice {
+            path: path.to_string(),
+            unique_id: unique_id.to_string(),
+            answers: Vec::new()
+        });
+        self.devices.last_mut().unwrap()
+    }
+}
+
+impl FixtureDevice {
+    fn answer(&self, ioctl: &str, args: &[u32]) -> nix::Result<&[u8]> {
+        match self.answers.iter().find(|answer| answer.ioctl == ioctl && answer.args == args) {
+            Some(answer) => answer.result.as_deref().map_err(|errno| *errno),
+            // Not asked when it was recorded, so answer as a driver without the ioctl would.
+            None => Err(Errno::ENOTTY)
+        }
+    }
+}
+
+/// Where the ioctls made while enumerating go.
+pub enum Ioctls<'a> {
+    Device(RawFd),
+    /// To the device, noting down what it answered.
+    Recording(RawFd, &'a mut FixtureDevice),
+    /// To the answers a device gave when it was recorded.
+    Replaying(&'a FixtureDevice)
+}
+
+impl Ioctls<'_> {
+    pub fn query_capabilities(&mut self) -> nix::Result<V4l2Capability> {
+        self.call("QUERYCAP", &[], read_capabilities)
+    }
+
+    pub fn query_format(&mut self, fmt_type: u32, index: u32) -> nix::Result<V4l2Fmtdesc> {
+        self.call("ENUM_FMT", &[fmt_type, index], |fd| query_format(fd, fmt_type, index))
+    }
+
+    pub fn query_frame_sizes(&mut self, pixel_format: u32, index: u32) -> nix::Result<V4l2Frmsizeenum> {
+        self.call("ENUM_FRAMESIZES", &[pixel_format, index], |fd| query_frame_sizes(fd, pixel_format, index))
+    }
+
+    pub fn query_frame_intervals(&mut self, pixel_format: u32, width: u32, height: u32, index: u32) -> nix::Result<V4l2Frmivalenum> {
+        self.call("ENUM_FRAMEINTERVALS", &[pixel_format, width, height, index],
+            |fd| query_frame_intervals(fd, pixel_format, width, height, index))
+    }
+
+    pub fn query_input(&mut self, index: u32) -> nix::Result<V4l2Input> {
+        self.call("ENUMINPUT", &[index], |fd| query_input(fd, index))
+    }
+
+    pub fn input(&mut self) -> nix::Result<c_int> {
+        self.call("G_INPUT", &[], read_input)
+    }
+
+    pub fn query_standard(&mut self, index: u32) -> nix::Result<V4l2Standard> {
+        self.call("ENUMSTD", &[index], |fd| query_standard(fd, index))
+    }
+
+    pub fn standard(&mut self) -> nix::Result<u64> {
+        self.call("G_STD", &[], read_standard)
+    }
+
+    pub fn query_dv_timings(&mut self) -> nix::Result<V4l2DvTimings> {
+        self.call("QUERY_DV_TIMINGS", &[], query_dv_timings)
+    }
+
+    pub fn selection(&mut self, target: u32) -> nix::Result<V4l2Rect> {
+        self.call("G_SELECTION", &[V4L2_BUF_TYPE_VIDEO_CAPTURE, target], |fd| get_selection(fd, target))
+    }
+
+    pub fn crop_capabilities(&mut self) -> nix::Result<V4l2Cropcap> {
+        self.call("CROPCAP", &[V4L2_BUF_TYPE_VIDEO_CAPTURE], query_crop_capabilities)
+    }
+
+    fn call<T>(&mut self, ioctl: &'static str, args: &[u32], query: impl FnOnce(RawFd) -> nix::Result<T>) -> nix::Result<T> {
+        match self {
+            Ioctls::Device(fd) => query(*fd),
+            Ioctls::Recording(fd, device) => {
+                let result = query(*fd);
+                device.answers.push(Answer {
+                    ioctl,
+                    args: args.to_vec(),
+                    result: result.as_ref().map(to_bytes).map_err(|errno| *errno)
+                });
+                result
+            },
+            Ioctls::Replaying(device) => device.answer(ioctl, args).and_then(from_bytes)
+        }
+    }
+}
+
+/// Only used on the ioctl structs, which are plain integers and arrays of them.
+/// They start zeroed, so any padding is saved as zeros.
+fn to_bytes<T>(value: &T) -> Vec<u8> {
+    unsafe { slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()).to_vec() }
+}
+
+fn from_bytes<T>(bytes: &[u8]) -> nix::Result<T> {
+    if bytes.len() != mem::size_of::<T>() {
+        return Err(Errno::EINVAL);
+    }
+    Ok(unsafe { ptr::read_unaligned(bytes.as_ptr() as *const T) })
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len()).step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        env,
+        path::PathBuf
+    };
+    use super::*;
+
+    fn fixture_path(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures").join(name)
+    }
+
+    #[test]
+    fn saving_a_loaded_fixture_gives_it_back() {
+        let path = fixture_path("v4l2-webcam-and-picam.txt");
+        let saved = env::temp_dir().join(format!("opencv-example-rs-fixture-{}.txt", std::process::id()));
+        Fixture::load(&path).unwrap().save(&saved).unwrap();
+        let original = read_to_string(&path).unwrap();
+        let without_comments: String = original.lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        assert_eq!(read_to_string(&saved).unwrap(), without_comments);
+        let _ = std::fs::remove_file(saved);
+    }
+
+    #[test]
+    fn answers_are_replayed_by_ioctl_and_arguments() {
+        let fixture = Fixture::load(&fixture_path("v4l2-webcam-and-picam.txt")).unwrap();
+        let mut ioctls = Ioctls::Replaying(&fixture.devices[0]);
+        let format = ioctls.query_format(V4L2_BUF_TYPE_VIDEO_CAPTURE, 1).unwrap();
+        assert_eq!(format.pixel_format, u32::from_le_bytes(*b"MJPG"));
+        assert_eq!(ioctls.query_format(V4L2_BUF_TYPE_VIDEO_CAPTURE, 2).err(), Some(Errno::EINVAL));
+        // Never asked when it was recorded.
+        assert_eq!(ioctls.query_standard(1).err(), Some(Errno::ENOTTY));
+    }
+
+    #[test]
+    fn malformed_lines_say_where_they_are() {
+        let path = env::temp_dir().join(format!("opencv-example-rs-bad-fixture-{}.txt", std::process::id()));
+        for (text, line, expected) in [
+            ("QUERYCAP 00\n", 1, "ioctl before the first device"),
+            ("device /dev/video0 cam\n\nENUM_FMT 1 0 0011\n", 3, "bad response"),
+            ("device /dev/video0 cam\nENUM_FMT one 0 errno:22\n", 2, "bad argument"),
+            ("device /dev/video0 cam\nS_FMT 1 errno:22\n", 2, "unknown ioctl")
+        ] {
+            write(&path, text).unwrap();
+            match Fixture::load(&path) {
+                Err(VideoDeviceError::InvalidFixture { line: at, reason, .. }) => assert_eq!((at, reason), (line, expected)),
+                Err(err) => panic!("{}: {}", text, err),
+                Ok(_) => panic!("{} loaded", text)
+            }
+        }
+        let _ = std::fs::remove_file(path);
+    }
+}
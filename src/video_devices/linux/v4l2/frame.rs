@@ -0,0 +1,277 @@
+//! Frames as the driver delivers them, and their conversion to BGR images.
+use std::time::Duration;
+use opencv::{
+    imgcodecs::{
+        imdecode,
+        IMREAD_COLOR
+    },
+    imgproc::{
+        cvt_color,
+        resize,
+        COLOR_BGR2GRAY,
+        COLOR_BGR2RGB,
+        COLOR_BGR2YUV_I420,
+        COLOR_BGR2YUV_YV12,
+        COLOR_GRAY2BGR,
+        COLOR_RGB2BGR,
+        COLOR_YUV2BGR_I420,
+        COLOR_YUV2BGR_NV12,
+        COLOR_YUV2BGR_NV21,
+        COLOR_YUV2BGR_UYVY,
+        COLOR_YUV2BGR_YUYV,
+        COLOR_YUV2BGR_YV12,
+        INTER_AREA
+    },
+    prelude::{
+        MatTrait,
+        MatTraitConst
+    },
+    core::{
+        Mat,
+        Scalar,
+        Size,
+        Vector,
+        CV_8UC1,
+        CV_8UC2,
+        CV_8UC3
+    }
+};
+use super::{
+    Result,
+    VideoDeviceError
+};
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    u32::from_le_bytes(*code)
+}
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/pixfmt.html
+
+pub const V4L2_PIX_FMT_MJPEG: u32 = fourcc(b"MJPG");
+pub const V4L2_PIX_FMT_JPEG: u32 = fourcc(b"JPEG");
+pub const V4L2_PIX_FMT_YUYV: u32 = fourcc(b"YUYV");
+pub const V4L2_PIX_FMT_UYVY: u32 = fourcc(b"UYVY");
+pub const V4L2_PIX_FMT_NV12: u32 = fourcc(b"NV12");
+pub const V4L2_PIX_FMT_NV21: u32 = fourcc(b"NV21");
+pub const V4L2_PIX_FMT_YUV420: u32 = fourcc(b"YU12");
+pub const V4L2_PIX_FMT_YVU420: u32 = fourcc(b"YV12");
+pub const V4L2_PIX_FMT_RGB24: u32 = fourcc(b"RGB3");
+pub const V4L2_PIX_FMT_BGR24: u32 = fourcc(b"BGR3");
+pub const V4L2_PIX_FMT_GREY: u32 = fourcc(b"GREY");
+
+/// The pixel formats images can be converted to for an encoder, best first.
+pub const ENCODABLE_PIXEL_FORMATS: [u32; 5] = [
+    V4L2_PIX_FMT_BGR24,
+    V4L2_PIX_FMT_RGB24,
+    V4L2_PIX_FMT_YUV420,
+    V4L2_PIX_FMT_YVU420,
+    V4L2_PIX_FMT_GREY
+];
+
+/// The pixel formats that can be converted, best first. The compressed ones come
+/// first as they reach the highest frame rates over USB.
+pub const SUPPORTED_PIXEL_FORMATS: [u32; 11] = [
+    V4L2_PIX_FMT_MJPEG,
+    V4L2_PIX_FMT_JPEG,
+    V4L2_PIX_FMT_YUYV,
+    V4L2_PIX_FMT_UYVY,
+    V4L2_PIX_FMT_NV12,
+    V4L2_PIX_FMT_NV21,
+    V4L2_PIX_FMT_YUV420,
+    V4L2_PIX_FMT_YVU420,
+    V4L2_PIX_FMT_BGR24,
+    V4L2_PIX_FMT_RGB24,
+    V4L2_PIX_FMT_GREY
+];
+
+/// The format negotiated with the driver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Format {
+    pub width: u32,
+    pub height: u32,
+    pub pixel_format: u32,
+    /// Length of a line of the first plane, including any padding.
+    pub bytes_per_line: u32,
+    /// The most bytes a frame can take.
+    pub size_image: u32
+}
+
+pub struct RawFrame {
+    pub data: Vec<u8>,
+    pub format: Format,
+    /// Counted by the driver, so gaps show where it dropped frames.
+    pub sequence: u32,
+    /// When the driver captured the frame, on the monotonic clock.
+    pub timestamp: Duration
+}
+
+impl RawFrame {
+    /// Decodes or converts the frame to 8 bit BGR, as OpenCV would return it.
+    pub fn to_mat(&self) -> Result<Mat> {
+        to_mat(&self.data, &self.format)
+    }
+}
+
+/// A frame as an 8 bit BGR image.
+pub struct ImageFrame {
+    pub image: Mat,
+    pub sequence: u32,
+    pub timestamp: Duration
+}
+
+/// The four characters of a pixel format, such as `YUYV`.
+pub fn pixel_format_name(pixel_format: u32) -> String {
+    pixel_format.to_le_bytes()
+        .iter()
+        .map(|&b| if b.is_ascii_graphic() { b as char } else { '_' })
+        .collect()
+}
+
+/// Rows of one plane of the frame data.
+struct Plane {
+    rows: usize,
+    row_bytes: usize,
+    /// Distance between the starts of rows, which is more than `row_bytes`
+    /// when the driver pads them.
+    stride: usize
+}
+
+fn stride(format: &Format, row_bytes: usize) -> usize {
+    (format.bytes_per_line as usize).max(row_bytes)
+}
+
+fn packed(format: &Format, bytes_per_pixel: usize) -> [Plane; 1] {
+    let row_bytes = format.width as usize * bytes_per_pixel;
+    [Plane { rows: format.height as usize, row_bytes, stride: stride(format, row_bytes) }]
+}
+
+/// Y plane followed by chroma at half the height, as OpenCV expects them in one Mat.
+fn semi_planar(format: &Format) -> [Plane; 2] {
+    let (width, height) = (format.width as usize, format.height as usize);
+    [
+        Plane { rows: height, row_bytes: width, stride: stride(format, width) },
+        Plane { rows: height / 2, row_bytes: width, stride: stride(format, width) }
+    ]
+}
+
+fn planar(format: &Format) -> [Plane; 3] {
+    let (width, height) = (format.width as usize, format.height as usize);
+    [
+        Plane { rows: height, row_bytes: width, stride: stride(format, width) },
+        Plane { rows: height / 2, row_bytes: width / 2, stride: stride(format, width) / 2 },
+        Plane { rows: height / 2, row_bytes: width / 2, stride: stride(format, width) / 2 }
+    ]
+}
+
+pub fn to_mat(data: &[u8], format: &Format) -> Result<Mat> {
+    let height = format.height as usize;
+    match format.pixel_format {
+        V4L2_PIX_FMT_MJPEG | V4L2_PIX_FMT_JPEG => {
+            if !is_complete_jpeg(data) {
+                return Err(undecodable(format));
+            }
+            let mat = imdecode(&Vector::<u8>::from_slice(data), IMREAD_COLOR)?;
+            if mat.empty() {
+                return Err(undecodable(format));
+            }
+            Ok(mat)
+        },
+        V4L2_PIX_FMT_BGR24 => copy_planes(data, format, CV_8UC3, height, &packed(format, 3)),
+        V4L2_PIX_FMT_RGB24 => convert(copy_planes(data, format, CV_8UC3, height, &packed(format, 3))?, COLOR_RGB2BGR),
+        V4L2_PIX_FMT_YUYV => convert(copy_planes(data, format, CV_8UC2, height, &packed(format, 2))?, COLOR_YUV2BGR_YUYV),
+        V4L2_PIX_FMT_UYVY => convert(copy_planes(data, format, CV_8UC2, height, &packed(format, 2))?, COLOR_YUV2BGR_UYVY),
+        V4L2_PIX_FMT_NV12 => convert(copy_planes(data, format, CV_8UC1, height * 3 / 2, &semi_planar(format))?, COLOR_YUV2BGR_NV12),
+        V4L2_PIX_FMT_NV21 => convert(copy_planes(data, format, CV_8UC1, height * 3 / 2, &semi_planar(format))?, COLOR_YUV2BGR_NV21),
+        V4L2_PIX_FMT_YUV420 => convert(copy_planes(data, format, CV_8UC1, height * 3 / 2, &planar(format))?, COLOR_YUV2BGR_I420),
+        V4L2_PIX_FMT_YVU420 => convert(copy_planes(data, format, CV_8UC1, height * 3 / 2, &planar(format))?, COLOR_YUV2BGR_YV12),
+        V4L2_PIX_FMT_GREY => convert(copy_planes(data, format, CV_8UC1, height, &packed(format, 1))?, COLOR_GRAY2BGR),
+        _ => Err(undecodable(format))
+    }
+}
+
+/// Converts an 8 bit BGR image to the format, as encoders take frames, scaling
+/// it first if it's another size.
+pub fn from_mat(image: &Mat, format: &Format) -> Result<Vec<u8>> {
+    let size = Size::new(format.width as i32, format.height as i32);
+    let mut scaled = Mat::default();
+    let image = if image.size()? == size {
+        image
+    } else {
+        resize(image, &mut scaled, size, 0.0, 0.0, INTER_AREA)?;
+        &scaled
+    };
+    let (code, planes): (_, &[Plane]) = match format.pixel_format {
+        V4L2_PIX_FMT_BGR24 => (None, &packed(format, 3)),
+        V4L2_PIX_FMT_RGB24 => (Some(COLOR_BGR2RGB), &packed(format, 3)),
+        V4L2_PIX_FMT_YUV420 => (Some(COLOR_BGR2YUV_I420), &planar(format)),
+        V4L2_PIX_FMT_YVU420 => (Some(COLOR_BGR2YUV_YV12), &planar(format)),
+        V4L2_PIX_FMT_GREY => (Some(COLOR_BGR2GRAY), &packed(format, 1)),
+        _ => return Err(VideoDeviceError::UnencodablePixelFormat(pixel_format_name(format.pixel_format)))
+    };
+    let mut converted = Mat::default();
+    let image = match code {
+        Some(code) => {
+            cvt_color(image, &mut converted, code, 0)?;
+            &converted
+        },
+        None if image.is_continuous() => image,
+        None => {
+            converted = image.try_clone()?;
+            &converted
+        }
+    };
+
+    // The reverse of copy_planes, padding rows out to the stride.
+    let src = image.data_bytes()?;
+    let length = planes.iter().map(|plane| plane.rows * plane.stride).sum::<usize>();
+    let mut data = vec![0; length.max(format.size_image as usize)];
+    let mut src_offset = 0;
+    let mut dest_offset = 0;
+    for plane in planes {
+        for _ in 0..plane.rows {
+            let row = src.get(src_offset..src_offset + plane.row_bytes)
+                .ok_or_else(|| VideoDeviceError::UnencodablePixelFormat(pixel_format_name(format.pixel_format)))?;
+            data[dest_offset..dest_offset + plane.row_bytes].copy_from_slice(row);
+            src_offset += plane.row_bytes;
+            dest_offset += plane.stride;
+        }
+    }
+    Ok(data)
+}
+
+/// Copies the planes into one continuous Mat, dropping any padding at the end of rows.
+fn copy_planes(data: &[u8], format: &Format, typ: i32, rows: usize, planes: &[Plane]) -> Result<Mat> {
+    let mut mat = Mat::new_rows_cols_with_default(rows as i32, format.width as i32, typ, Scalar::all(0.0))?;
+    let dest = mat.data_bytes_mut()?;
+    let mut src_offset = 0;
+    let mut dest_offset = 0;
+    for plane in planes {
+        for _ in 0..plane.rows {
+            // A short frame means the driver gave us less than it promised.
+            let row = data.get(src_offset..src_offset + plane.row_bytes)
+                .ok_or_else(|| undecodable(format))?;
+            dest[dest_offset..dest_offset + plane.row_bytes].copy_from_slice(row);
+            src_offset += plane.stride;
+            dest_offset += plane.row_bytes;
+        }
+    }
+    Ok(mat)
+}
+
+fn convert(frame: Mat, code: i32) -> Result<Mat> {
+    let mut converted = Mat::default();
+    cvt_color(&frame, &mut converted, code, 0)?;
+    Ok(converted)
+}
+
+/// Drivers hand over JPEGs cut short when USB bandwidth runs out, which decode
+/// without complaint with the missing part grey, so check both markers are there.
+fn is_complete_jpeg(data: &[u8]) -> bool {
+    // Some drivers pad the buffer after the end of image marker.
+    let end = data.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    data.starts_with(&[0xFF, 0xD8]) && data[..end].ends_with(&[0xFF, 0xD9])
+}
+
+fn undecodable(format: &Format) -> VideoDeviceError {
+    VideoDeviceError::UndecodableFrame(pixel_format_name(format.pixel_format))
+}
@@ -0,0 +1,145 @@
+//! The inputs of capture cards and TV tuners, and the analogue TV standards
+//! they receive.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/video.html
+//! and https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/standard.html
+use std::{
+    ffi::{
+        c_int,
+        CStr
+    },
+    mem,
+    os::fd::RawFd
+};
+use nix::errno::Errno;
+use super::{
+    fixture::Ioctls,
+    ioctl::*,
+    to_fps,
+    InputStatus,
+    Result,
+    VideoDeviceError,
+    VideoInput,
+    VideoStandard
+};
+
+const STATUS_FLAGS: [(u32, InputStatus); 12] = [
+    (V4L2_IN_ST_NO_POWER, InputStatus::NoPower),
+    (V4L2_IN_ST_NO_SIGNAL, InputStatus::NoSignal),
+    (V4L2_IN_ST_NO_COLOR, InputStatus::NoColour),
+    (V4L2_IN_ST_NO_H_LOCK, InputStatus::NoHorizontalLock),
+    (V4L2_IN_ST_COLOR_KILL, InputStatus::ColourKill),
+    (V4L2_IN_ST_NO_V_LOCK, InputStatus::NoVerticalLock),
+    (V4L2_IN_ST_NO_STD_LOCK, InputStatus::NoStandardLock),
+    (V4L2_IN_ST_NO_SYNC, InputStatus::NoSync),
+    (V4L2_IN_ST_NO_CARRIER, InputStatus::NoCarrier),
+    (V4L2_IN_ST_MACROVISION, InputStatus::Macrovision),
+    (V4L2_IN_ST_NO_ACCESS, InputStatus::NoAccess),
+    (V4L2_IN_ST_VTR, InputStatus::Vcr)
+];
+
+/// The device's inputs, and the standard the selected one is set to.
+pub fn find_inputs(ioctls: &mut Ioctls) -> Result<(Vec<VideoInput>, Option<VideoStandard>)> {
+    let standards = find_standards(ioctls)?;
+    let selected = ioctls.input().ok();
+
+    let mut inputs = Vec::<VideoInput>::new();
+    for index in 0.. {
+        match ioctls.query_input(index) {
+            Ok(input) => {
+                let is_selected = selected == Some(input.index as c_int);
+                inputs.push(VideoInput {
+                    index: input.index,
+                    name: to_name(&input.name),
+                    tuner: input.typ == V4L2_INPUT_TYPE_TUNER,
+                    selected: is_selected,
+                    // Only meaningful for the selected input.
+                    status: if is_selected { to_input_status(input.status) } else { Vec::new() },
+                    standards: standards.iter()
+                        .filter(|standard| standard.id & input.std != 0)
+                        .cloned()
+                        .collect()
+                });
+            },
+            // EINVAL returned after last index. Some drivers don't have inputs at all.
+            Err(Errno::EINVAL) | Err(Errno::ENOTTY) => break,
+            Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_ENUMINPUT", errno })?
+        }
+    }
+
+    let standard = match ioctls.standard() {
+        // G_STD can give a set of variants, such as all of PAL, where the list
+        // has the set and each variant on its own.
+        Ok(id) => standards.iter().find(|standard| standard.id == id)
+            .or_else(|| standards.iter().find(|standard| standard.id & id != 0))
+            .cloned(),
+        Err(_) => None
+    };
+    Ok((inputs, standard))
+}
+
+/// The standards the selected input can receive.
+fn find_standards(ioctls: &mut Ioctls) -> Result<Vec<VideoStandard>> {
+    let mut standards = Vec::<VideoStandard>::new();
+    for index in 0.. {
+        match ioctls.query_standard(index) {
+            Ok(standard) => standards.push(VideoStandard {
+                id: standard.id,
+                name: to_name(&standard.name),
+                frame_rate: to_fps(standard.frameperiod),
+                lines: standard.framelines
+            }),
+            // EINVAL returned after last index, ENODATA when the input has no
+            // standards, such as HDMI.
+            Err(Errno::EINVAL) | Err(Errno::ENODATA) | Err(Errno::ENOTTY) => break,
+            Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_ENUMSTD", errno })?
+        }
+    }
+    Ok(standards)
+}
+
+fn to_input_status(status: u32) -> Vec<InputStatus> {
+    STATUS_FLAGS.iter()
+        .filter(|(flag, _)| status & flag != 0)
+        .map(|(_, status)| status.clone())
+        .collect()
+}
+
+fn to_name(name: &[u8]) -> String {
+    CStr::from_bytes_until_nul(name)
+        .map_or_else(|_| String::from_utf8_lossy(name).to_string(), |name| name.to_string_lossy().to_string())
+}
+
+pub fn query_input(fd: RawFd, index: u32) -> nix::Result<V4l2Input> {
+    unsafe {
+        let mut input: V4l2Input = mem::zeroed();
+        input.index = index;
+        vidioc_enuminput(fd, &mut input)?;
+        Ok(input)
+    }
+}
+
+pub fn read_input(fd: RawFd) -> nix::Result<c_int> {
+    let mut index: c_int = 0;
+    unsafe {
+        vidioc_g_input(fd, &mut index)?;
+    }
+    Ok(index)
+}
+
+pub fn query_standard(fd: RawFd, index: u32) -> nix::Result<V4l2Standard> {
+    unsafe {
+        let mut standard: V4l2Standard = mem::zeroed();
+        standard.index = index;
+        vidioc_enumstd(fd, &mut standard)?;
+        Ok(standard)
+    }
+}
+
+pub fn read_standard(fd: RawFd) -> nix::Result<u64> {
+    let mut id: u64 = 0;
+    unsafe {
+        vidioc_g_std(fd, &mut id)?;
+    }
+    Ok(id)
+}
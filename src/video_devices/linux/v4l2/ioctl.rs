@@ -1,17 +1,104 @@
 //! The ioctl functions for the v4l2 module and the structs they use.
+use std::ffi::{
+    c_int,
+    c_ulong,
+    c_void
+};
+use nix::libc::{
+    timespec,
+    timeval
+};
 
 pub const V4L2_CAP_VIDEO_CAPTURE: u32 = 0x00000001;
 pub const V4L2_CAP_VIDEO_CAPTURE_MPLANE: u32 = 0x00001000;
+pub const V4L2_CAP_VIDEO_M2M_MPLANE: u32 = 0x00004000;
+pub const V4L2_CAP_VIDEO_M2M: u32 = 0x00008000;
+pub const V4L2_CAP_READWRITE: u32 = 0x01000000;
+pub const V4L2_CAP_STREAMING: u32 = 0x04000000;
+pub const V4L2_CAP_TIMEPERFRAME: u32 = 0x00001000;
+pub const V4L2_MODE_HIGHQUALITY: u32 = 0x00000001;
 pub const V4L2_BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+pub const V4L2_BUF_TYPE_VIDEO_OUTPUT: u32 = 2;
 pub const V4L2_BUF_TYPE_VIDEO_CAPTURE_MPLANE: u32 = 9;
+pub const V4L2_BUF_TYPE_VIDEO_OUTPUT_MPLANE: u32 = 10;
+pub const V4L2_FMT_FLAG_COMPRESSED: u32 = 0x0001;
 pub const V4L2_FRMSIZE_TYPE_DISCRETE: u32 = 1;
 pub const V4L2_FRMSIZE_TYPE_CONTINUOUS: u32 = 2;
 pub const V4L2_FRMSIZE_TYPE_STEPWISE: u32 = 3;
+pub const V4L2_FRMIVAL_TYPE_DISCRETE: u32 = 1;
+pub const V4L2_FRMIVAL_TYPE_CONTINUOUS: u32 = 2;
+pub const V4L2_FRMIVAL_TYPE_STEPWISE: u32 = 3;
+pub const V4L2_FIELD_NONE: u32 = 1;
+pub const V4L2_MEMORY_MMAP: u32 = 1;
+pub const V4L2_MEMORY_USERPTR: u32 = 2;
+pub const V4L2_MEMORY_DMABUF: u32 = 4;
+pub const V4L2_BUF_FLAG_ERROR: u32 = 0x00000040;
+pub const V4L2_INPUT_TYPE_TUNER: u32 = 1;
+pub const V4L2_IN_ST_NO_POWER: u32 = 0x00000001;
+pub const V4L2_IN_ST_NO_SIGNAL: u32 = 0x00000002;
+pub const V4L2_IN_ST_NO_COLOR: u32 = 0x00000004;
+pub const V4L2_IN_ST_NO_H_LOCK: u32 = 0x00000100;
+pub const V4L2_IN_ST_COLOR_KILL: u32 = 0x00000200;
+pub const V4L2_IN_ST_NO_V_LOCK: u32 = 0x00000400;
+pub const V4L2_IN_ST_NO_STD_LOCK: u32 = 0x00000800;
+pub const V4L2_IN_ST_NO_SYNC: u32 = 0x00010000;
+pub const V4L2_IN_ST_NO_CARRIER: u32 = 0x00040000;
+pub const V4L2_IN_ST_MACROVISION: u32 = 0x01000000;
+pub const V4L2_IN_ST_NO_ACCESS: u32 = 0x02000000;
+pub const V4L2_IN_ST_VTR: u32 = 0x04000000;
+pub const V4L2_DV_BT_656_1120: u32 = 0;
+pub const V4L2_SEL_TGT_CROP: u32 = 0x0000;
+pub const V4L2_SEL_TGT_CROP_DEFAULT: u32 = 0x0001;
+pub const V4L2_SEL_TGT_CROP_BOUNDS: u32 = 0x0002;
+pub const V4L2_EVENT_EOS: u32 = 2;
+pub const V4L2_EVENT_CTRL: u32 = 3;
+pub const V4L2_EVENT_FRAME_SYNC: u32 = 4;
+pub const V4L2_EVENT_SOURCE_CHANGE: u32 = 5;
+pub const V4L2_EVENT_SRC_CH_RESOLUTION: u32 = 0x0001;
+pub const V4L2_EVENT_CTRL_CH_VALUE: u32 = 0x0001;
+pub const V4L2_EVENT_CTRL_CH_FLAGS: u32 = 0x0002;
+pub const V4L2_EVENT_SUB_FL_SEND_INITIAL: u32 = 0x0001;
+pub const V4L2_CTRL_TYPE_INTEGER64: u32 = 5;
+pub const V4L2_CTRL_TYPE_CTRL_CLASS: u32 = 6;
+pub const V4L2_CTRL_FLAG_DISABLED: u32 = 0x0001;
+pub const V4L2_CTRL_FLAG_INACTIVE: u32 = 0x0010;
+pub const V4L2_CTRL_FLAG_NEXT_CTRL: u32 = 0x80000000;
+pub const V4L2_CID_MIN_BUFFERS_FOR_CAPTURE: u32 = 0x00980927;
+pub const V4L2_CID_JPEG_COMPRESSION_QUALITY: u32 = 0x009d0903;
 
 const VIDIOC: u8 = b'V';
 const VIDIOC_QUERYCAP: u8 = 0;
 const VIDIOC_ENUM_FMT: u8 = 2;
+const VIDIOC_G_FMT: u8 = 4;
+const VIDIOC_S_FMT: u8 = 5;
+const VIDIOC_REQBUFS: u8 = 8;
+const VIDIOC_QUERYBUF: u8 = 9;
+const VIDIOC_QBUF: u8 = 15;
+const VIDIOC_EXPBUF: u8 = 16;
+const VIDIOC_DQBUF: u8 = 17;
+const VIDIOC_STREAMON: u8 = 18;
+const VIDIOC_STREAMOFF: u8 = 19;
+const VIDIOC_G_PARM: u8 = 21;
+const VIDIOC_S_PARM: u8 = 22;
+const VIDIOC_G_STD: u8 = 23;
+const VIDIOC_G_CTRL: u8 = 27;
+const VIDIOC_S_CTRL: u8 = 28;
+const VIDIOC_QUERYCTRL: u8 = 36;
+const VIDIOC_CROPCAP: u8 = 58;
+const VIDIOC_S_STD: u8 = 24;
+const VIDIOC_ENUMSTD: u8 = 25;
+const VIDIOC_ENUMINPUT: u8 = 26;
+const VIDIOC_G_INPUT: u8 = 38;
+const VIDIOC_S_INPUT: u8 = 39;
+const VIDIOC_S_DV_TIMINGS: u8 = 87;
+const VIDIOC_DQEVENT: u8 = 89;
+const VIDIOC_SUBSCRIBE_EVENT: u8 = 90;
+const VIDIOC_G_SELECTION: u8 = 94;
+const VIDIOC_S_SELECTION: u8 = 95;
+const VIDIOC_ENUM_DV_TIMINGS: u8 = 98;
+const VIDIOC_QUERY_DV_TIMINGS: u8 = 99;
 const VIDIOC_ENUM_FRAMESIZES: u8 = 74;
+const VIDIOC_ENUM_FRAMEINTERVALS: u8 = 75;
 
 // https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-querycap.html
 
@@ -78,4 +165,478 @@ pub struct V4l2Frmsizeenum {
     pub reserved: [u32; 2]
 }
 
-nix::ioctl_readwrite!(vidioc_enum_framesizes, VIDIOC, VIDIOC_ENUM_FRAMESIZES, V4l2Frmsizeenum);
\ No newline at end of file
+nix::ioctl_readwrite!(vidioc_enum_framesizes, VIDIOC, VIDIOC_ENUM_FRAMESIZES, V4l2Frmsizeenum);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-enum-frameintervals.html
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2Fract {
+    pub numerator: u32,
+    pub denominator: u32
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2FrmivalStepwise {
+    pub min: V4l2Fract,
+    pub max: V4l2Fract,
+    pub step: V4l2Fract
+}
+
+#[repr(C)]
+pub union V4l2Frmival {
+    pub discrete: V4l2Fract,
+    pub stepwise: V4l2FrmivalStepwise
+}
+
+#[repr(C)]
+pub struct V4l2Frmivalenum {
+    pub index: u32,
+    pub pixel_format: u32,
+    pub width: u32,
+    pub height: u32,
+    pub typ: u32,
+    pub frmival: V4l2Frmival,
+    pub reserved: [u32; 2]
+}
+
+nix::ioctl_readwrite!(vidioc_enum_frameintervals, VIDIOC, VIDIOC_ENUM_FRAMEINTERVALS, V4l2Frmivalenum);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-g-fmt.html
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2PixFormat {
+    pub width: u32,
+    pub height: u32,
+    pub pixelformat: u32,
+    pub field: u32,
+    pub bytesperline: u32,
+    pub sizeimage: u32,
+    pub colorspace: u32,
+    pub private: u32,
+    pub flags: u32,
+    pub ycbcr_enc: u32,
+    pub quantization: u32,
+    pub xfer_func: u32
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct V4l2PlanePixFormat {
+    pub sizeimage: u32,
+    pub bytesperline: u32,
+    pub reserved: [u16; 6]
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct V4l2PixFormatMplane {
+    pub width: u32,
+    pub height: u32,
+    pub pixelformat: u32,
+    pub field: u32,
+    pub colorspace: u32,
+    pub plane_fmt: [V4l2PlanePixFormat; 8],
+    pub num_planes: u8,
+    pub flags: u8,
+    pub ycbcr_enc: u8,
+    pub quantization: u8,
+    pub xfer_func: u8,
+    pub reserved: [u8; 7]
+}
+
+#[repr(C)]
+pub union V4l2FormatUnion {
+    pub pix: V4l2PixFormat,
+    pub pix_mp: V4l2PixFormatMplane,
+    pub raw_data: [u8; 200],
+    // The kernel's union also holds structs with pointers, which sets its alignment.
+    _align: [*mut c_void; 0]
+}
+
+#[repr(C)]
+pub struct V4l2Format {
+    pub typ: u32,
+    pub fmt: V4l2FormatUnion
+}
+
+nix::ioctl_readwrite!(vidioc_g_fmt, VIDIOC, VIDIOC_G_FMT, V4l2Format);
+nix::ioctl_readwrite!(vidioc_s_fmt, VIDIOC, VIDIOC_S_FMT, V4l2Format);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-reqbufs.html
+
+#[repr(C)]
+pub struct V4l2RequestBuffers {
+    pub count: u32,
+    pub typ: u32,
+    pub memory: u32,
+    pub capabilities: u32,
+    pub flags: u8,
+    pub reserved: [u8; 3]
+}
+
+nix::ioctl_readwrite!(vidioc_reqbufs, VIDIOC, VIDIOC_REQBUFS, V4l2RequestBuffers);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/buffer.html
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2Timecode {
+    pub typ: u32,
+    pub flags: u32,
+    pub frames: u8,
+    pub seconds: u8,
+    pub minutes: u8,
+    pub hours: u8,
+    pub userbits: [u8; 4]
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union V4l2BufferM {
+    pub offset: u32,
+    pub userptr: c_ulong,
+    pub planes: *mut c_void,
+    pub fd: i32
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union V4l2PlaneM {
+    pub mem_offset: u32,
+    pub userptr: c_ulong,
+    pub fd: i32
+}
+
+#[repr(C)]
+pub struct V4l2Plane {
+    pub bytesused: u32,
+    pub length: u32,
+    pub m: V4l2PlaneM,
+    pub data_offset: u32,
+    pub reserved: [u32; 11]
+}
+
+#[repr(C)]
+pub struct V4l2Buffer {
+    pub index: u32,
+    pub typ: u32,
+    pub bytesused: u32,
+    pub flags: u32,
+    pub field: u32,
+    pub timestamp: timeval,
+    pub timecode: V4l2Timecode,
+    pub sequence: u32,
+    pub memory: u32,
+    pub m: V4l2BufferM,
+    pub length: u32,
+    pub reserved2: u32,
+    pub request_fd: i32
+}
+
+nix::ioctl_readwrite!(vidioc_querybuf, VIDIOC, VIDIOC_QUERYBUF, V4l2Buffer);
+nix::ioctl_readwrite!(vidioc_qbuf, VIDIOC, VIDIOC_QBUF, V4l2Buffer);
+nix::ioctl_readwrite!(vidioc_dqbuf, VIDIOC, VIDIOC_DQBUF, V4l2Buffer);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-expbuf.html
+
+#[repr(C)]
+pub struct V4l2Exportbuffer {
+    pub typ: u32,
+    pub index: u32,
+    pub plane: u32,
+    pub flags: u32,
+    pub fd: i32,
+    pub reserved: [u32; 11]
+}
+
+nix::ioctl_readwrite!(vidioc_expbuf, VIDIOC, VIDIOC_EXPBUF, V4l2Exportbuffer);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-streamon.html
+
+nix::ioctl_write_ptr!(vidioc_streamon, VIDIOC, VIDIOC_STREAMON, c_int);
+nix::ioctl_write_ptr!(vidioc_streamoff, VIDIOC, VIDIOC_STREAMOFF, c_int);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-enuminput.html
+
+#[repr(C)]
+pub struct V4l2Input {
+    pub index: u32,
+    pub name: [u8; 32],
+    pub typ: u32,
+    pub audioset: u32,
+    pub tuner: u32,
+    pub std: u64,
+    pub status: u32,
+    pub capabilities: u32,
+    pub reserved: [u32; 3]
+}
+
+nix::ioctl_readwrite!(vidioc_enuminput, VIDIOC, VIDIOC_ENUMINPUT, V4l2Input);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-g-input.html
+
+nix::ioctl_read!(vidioc_g_input, VIDIOC, VIDIOC_G_INPUT, c_int);
+nix::ioctl_readwrite!(vidioc_s_input, VIDIOC, VIDIOC_S_INPUT, c_int);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-enumstd.html
+
+#[repr(C)]
+pub struct V4l2Standard {
+    pub index: u32,
+    pub id: u64,
+    pub name: [u8; 24],
+    pub frameperiod: V4l2Fract,
+    pub framelines: u32,
+    pub reserved: [u32; 4]
+}
+
+nix::ioctl_readwrite!(vidioc_enumstd, VIDIOC, VIDIOC_ENUMSTD, V4l2Standard);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-g-std.html
+
+nix::ioctl_read!(vidioc_g_std, VIDIOC, VIDIOC_G_STD, u64);
+nix::ioctl_write_ptr!(vidioc_s_std, VIDIOC, VIDIOC_S_STD, u64);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-g-dv-timings.html
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct V4l2BtTimings {
+    pub width: u32,
+    pub height: u32,
+    pub interlaced: u32,
+    pub polarities: u32,
+    pub pixelclock: u64,
+    pub hfrontporch: u32,
+    pub hsync: u32,
+    pub hbackporch: u32,
+    pub vfrontporch: u32,
+    pub vsync: u32,
+    pub vbackporch: u32,
+    pub il_vfrontporch: u32,
+    pub il_vsync: u32,
+    pub il_vbackporch: u32,
+    pub standards: u32,
+    pub flags: u32,
+    pub picture_aspect: V4l2Fract,
+    pub cea861_vic: u8,
+    pub hdmi_vic: u8,
+    pub reserved: [u8; 46]
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub union V4l2DvTimingsUnion {
+    pub bt: V4l2BtTimings,
+    pub reserved: [u32; 32]
+}
+
+#[repr(C, packed)]
+#[derive(Copy, Clone)]
+pub struct V4l2DvTimings {
+    pub typ: u32,
+    pub timings: V4l2DvTimingsUnion
+}
+
+nix::ioctl_readwrite!(vidioc_s_dv_timings, VIDIOC, VIDIOC_S_DV_TIMINGS, V4l2DvTimings);
+nix::ioctl_read!(vidioc_query_dv_timings, VIDIOC, VIDIOC_QUERY_DV_TIMINGS, V4l2DvTimings);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-enum-dv-timings.html
+
+#[repr(C)]
+pub struct V4l2EnumDvTimings {
+    pub index: u32,
+    pub pad: u32,
+    pub reserved: [u32; 2],
+    pub timings: V4l2DvTimings
+}
+
+nix::ioctl_readwrite!(vidioc_enum_dv_timings, VIDIOC, VIDIOC_ENUM_DV_TIMINGS, V4l2EnumDvTimings);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-dqevent.html
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2EventSrcChange {
+    pub changes: u32
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union V4l2EventCtrlValue {
+    pub value: i32,
+    pub value64: i64
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2EventCtrl {
+    pub changes: u32,
+    pub typ: u32,
+    pub value: V4l2EventCtrlValue,
+    pub flags: u32,
+    pub minimum: i32,
+    pub maximum: i32,
+    pub step: i32,
+    pub default_value: i32
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2EventFrameSync {
+    pub frame_sequence: u32
+}
+
+#[repr(C)]
+pub union V4l2EventUnion {
+    pub ctrl: V4l2EventCtrl,
+    pub frame_sync: V4l2EventFrameSync,
+    pub src_change: V4l2EventSrcChange,
+    pub data: [u8; 64]
+}
+
+#[repr(C)]
+pub struct V4l2Event {
+    pub typ: u32,
+    pub u: V4l2EventUnion,
+    pub pending: u32,
+    pub sequence: u32,
+    pub timestamp: timespec,
+    pub id: u32,
+    pub reserved: [u32; 8]
+}
+
+nix::ioctl_read!(vidioc_dqevent, VIDIOC, VIDIOC_DQEVENT, V4l2Event);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-subscribe-event.html
+
+#[repr(C)]
+pub struct V4l2EventSubscription {
+    pub typ: u32,
+    pub id: u32,
+    pub flags: u32,
+    pub reserved: [u32; 5]
+}
+
+nix::ioctl_write_ptr!(vidioc_subscribe_event, VIDIOC, VIDIOC_SUBSCRIBE_EVENT, V4l2EventSubscription);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-queryctrl.html
+
+#[repr(C)]
+pub struct V4l2Queryctrl {
+    pub id: u32,
+    pub typ: u32,
+    pub name: [u8; 32],
+    pub minimum: i32,
+    pub maximum: i32,
+    pub step: i32,
+    pub default_value: i32,
+    pub flags: u32,
+    pub reserved: [u32; 2]
+}
+
+nix::ioctl_readwrite!(vidioc_queryctrl, VIDIOC, VIDIOC_QUERYCTRL, V4l2Queryctrl);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-g-ctrl.html
+
+#[repr(C)]
+pub struct V4l2Control {
+    pub id: u32,
+    pub value: i32
+}
+
+nix::ioctl_readwrite!(vidioc_g_ctrl, VIDIOC, VIDIOC_G_CTRL, V4l2Control);
+nix::ioctl_readwrite!(vidioc_s_ctrl, VIDIOC, VIDIOC_S_CTRL, V4l2Control);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-g-selection.html
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2Rect {
+    pub left: i32,
+    pub top: i32,
+    pub width: u32,
+    pub height: u32
+}
+
+#[repr(C)]
+pub struct V4l2Selection {
+    pub typ: u32,
+    pub target: u32,
+    pub flags: u32,
+    pub r: V4l2Rect,
+    pub reserved: [u32; 9]
+}
+
+nix::ioctl_readwrite!(vidioc_g_selection, VIDIOC, VIDIOC_G_SELECTION, V4l2Selection);
+nix::ioctl_readwrite!(vidioc_s_selection, VIDIOC, VIDIOC_S_SELECTION, V4l2Selection);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-cropcap.html
+
+#[repr(C)]
+pub struct V4l2Cropcap {
+    pub typ: u32,
+    pub bounds: V4l2Rect,
+    pub defrect: V4l2Rect,
+    pub pixelaspect: V4l2Fract
+}
+
+nix::ioctl_readwrite!(vidioc_cropcap, VIDIOC, VIDIOC_CROPCAP, V4l2Cropcap);
+
+// https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-g-parm.html
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct V4l2Captureparm {
+    pub capability: u32,
+    pub capturemode: u32,
+    pub timeperframe: V4l2Fract,
+    pub extendedmode: u32,
+    pub readbuffers: u32,
+    pub reserved: [u32; 4]
+}
+
+#[repr(C)]
+pub union V4l2StreamparmUnion {
+    pub capture: V4l2Captureparm,
+    pub raw_data: [u8; 200]
+}
+
+#[repr(C)]
+pub struct V4l2Streamparm {
+    pub typ: u32,
+    pub parm: V4l2StreamparmUnion
+}
+
+nix::ioctl_readwrite!(vidioc_g_parm, VIDIOC, VIDIOC_G_PARM, V4l2Streamparm);
+nix::ioctl_readwrite!(vidioc_s_parm, VIDIOC, VIDIOC_S_PARM, V4l2Streamparm);
+
+// https://www.kernel.org/doc/html/latest/driver-api/dma-buf.html#cpu-access-to-dma-buffer-objects
+
+pub const DMA_BUF_SYNC_READ: u64 = 1;
+pub const DMA_BUF_SYNC_START: u64 = 0;
+pub const DMA_BUF_SYNC_END: u64 = 4;
+
+#[repr(C)]
+pub struct DmaBufSync {
+    pub flags: u64
+}
+
+nix::ioctl_write_ptr!(dma_buf_ioctl_sync, b'b', 0, DmaBufSync);
+
+// include/uapi/linux/udmabuf.h
+
+pub const UDMABUF_FLAGS_CLOEXEC: u32 = 0x01;
+
+#[repr(C)]
+pub struct UdmabufCreate {
+    pub memfd: u32,
+    pub flags: u32,
+    pub offset: u64,
+    pub size: u64
+}
+
+nix::ioctl_write_ptr!(udmabuf_create, b'u', 0x42, UdmabufCreate);
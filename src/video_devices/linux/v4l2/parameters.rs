@@ -0,0 +1,71 @@
+//! The frame rate and capture mode a device streams with. Enumerating frame
+//! intervals only says what's possible; this is what's set.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/vidioc-g-parm.html
+use std::{
+    mem,
+    os::fd::RawFd
+};
+use super::{
+    ioctl::*,
+    to_fps
+};
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(Copy)]
+pub struct StreamParameters {
+    /// `None` where the driver doesn't say.
+    pub frame_rate: Option<f64>,
+    /// The driver takes a frame rate, rather than ignoring one.
+    pub can_set_frame_rate: bool,
+    /// The driver's high quality mode for stills is on, which may be slower
+    /// and not work for video.
+    pub high_quality: bool
+}
+
+pub fn get_parameters(fd: RawFd) -> nix::Result<V4l2Streamparm> {
+    unsafe {
+        let mut parm: V4l2Streamparm = mem::zeroed();
+        parm.typ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        vidioc_g_parm(fd, &mut parm)?;
+        Ok(parm)
+    }
+}
+
+/// Sets the parameters, leaving `parm` with those the driver settled on.
+pub fn set_parameters(fd: RawFd, parm: &mut V4l2Streamparm) -> nix::Result<()> {
+    parm.typ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+    unsafe {
+        vidioc_s_parm(fd, parm)?;
+    }
+    Ok(())
+}
+
+pub fn to_stream_parameters(parm: &V4l2Streamparm) -> StreamParameters {
+    let capture = unsafe { parm.parm.capture };
+    let frame_rate = to_fps(capture.timeperframe);
+    StreamParameters {
+        frame_rate: (frame_rate.is_finite() && frame_rate > 0.0).then_some(frame_rate),
+        can_set_frame_rate: capture.capability & V4L2_CAP_TIMEPERFRAME != 0,
+        high_quality: capture.capturemode & V4L2_MODE_HIGHQUALITY != 0
+    }
+}
+
+/// The time per frame for a rate, exact to a thousandth of a frame per
+/// second, so NTSC's 29.97 comes out as 1001/30000 rather than 1/30.
+pub fn to_time_per_frame(fps: f64) -> V4l2Fract {
+    let (numerator, denominator) = match (fps * 1001.0 / 1000.0).round() {
+        ntsc if ntsc > 1.0 && (ntsc * 1000.0 / 1001.0 - fps).abs() < 0.005 => (1001, ntsc as u32 * 1000),
+        _ => (1000, (fps * 1000.0).round().max(1.0) as u32)
+    };
+    let divisor = gcd(numerator, denominator);
+    V4l2Fract {
+        numerator: numerator / divisor,
+        denominator: denominator / divisor
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
@@ -0,0 +1,79 @@
+//! Cropping the sensor, for a region of interest or a digital zoom at the
+//! sensor's full resolution.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/selection-api.html
+use std::{
+    mem,
+    os::fd::RawFd
+};
+use opencv::core::Rect;
+use super::{
+    fixture::Ioctls,
+    ioctl::*,
+    CropBounds
+};
+
+/// Where the device can crop to, asked with the selection API, or the older
+/// crop API for drivers that don't have it.
+pub fn find_crop_bounds(ioctls: &mut Ioctls) -> Option<CropBounds> {
+    let from_selection = ioctls.selection(V4L2_SEL_TGT_CROP_BOUNDS)
+        .and_then(|bounds| Ok((bounds, ioctls.selection(V4L2_SEL_TGT_CROP_DEFAULT)?)));
+    let (bounds, default) = match from_selection {
+        Ok((bounds, default)) => (bounds, default),
+        Err(_) => {
+            let cropcap = ioctls.crop_capabilities().ok()?;
+            (cropcap.bounds, cropcap.defrect)
+        }
+    };
+    if bounds.width == 0 || bounds.height == 0 {
+        return None;
+    }
+    Some(CropBounds {
+        bounds: to_rect(&bounds),
+        default: to_rect(&default)
+    })
+}
+
+pub fn to_rect(rect: &V4l2Rect) -> Rect {
+    Rect::new(rect.left, rect.top, rect.width as i32, rect.height as i32)
+}
+
+pub fn to_v4l2_rect(rect: &Rect) -> V4l2Rect {
+    V4l2Rect {
+        left: rect.x,
+        top: rect.y,
+        width: rect.width.max(0) as u32,
+        height: rect.height.max(0) as u32
+    }
+}
+
+pub fn get_selection(fd: RawFd, target: u32) -> nix::Result<V4l2Rect> {
+    unsafe {
+        let mut selection: V4l2Selection = mem::zeroed();
+        selection.typ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        selection.target = target;
+        vidioc_g_selection(fd, &mut selection)?;
+        Ok(selection.r)
+    }
+}
+
+/// Sets the crop, returning the rectangle the driver adjusted it to.
+pub fn set_crop_selection(fd: RawFd, rect: V4l2Rect) -> nix::Result<V4l2Rect> {
+    unsafe {
+        let mut selection: V4l2Selection = mem::zeroed();
+        selection.typ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        selection.target = V4L2_SEL_TGT_CROP;
+        selection.r = rect;
+        vidioc_s_selection(fd, &mut selection)?;
+        Ok(selection.r)
+    }
+}
+
+pub fn query_crop_capabilities(fd: RawFd) -> nix::Result<V4l2Cropcap> {
+    unsafe {
+        let mut cropcap: V4l2Cropcap = mem::zeroed();
+        cropcap.typ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        vidioc_cropcap(fd, &mut cropcap)?;
+        Ok(cropcap)
+    }
+}
@@ -0,0 +1,80 @@
+//! The digital video timings of HDMI and DVI receivers, such as the TC358743
+//! bridge and USB HDMI grabbers. They need the timings of the signal set before
+//! a format can be.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/dv-timings.html
+use std::{
+    mem,
+    os::fd::RawFd
+};
+use nix::errno::Errno;
+use super::{
+    fixture::Ioctls,
+    ioctl::*,
+    DvTimings,
+    Result,
+    VideoDeviceError
+};
+
+/// The timings of the signal on the selected input, if there's a stable one.
+pub fn find_dv_timings(ioctls: &mut Ioctls) -> Option<DvTimings> {
+    // ENOTTY for devices without DV timings, ENOLINK without a signal,
+    // ENOLCK while it's unstable and ERANGE when it's out of range.
+    ioctls.query_dv_timings().ok().and_then(|timings| to_dv_timings(&timings))
+}
+
+/// All the timings the receiver supports.
+pub fn find_supported_dv_timings(fd: RawFd) -> Result<Vec<DvTimings>> {
+    let mut supported = Vec::<DvTimings>::new();
+    for index in 0.. {
+        match enum_dv_timings(fd, index) {
+            Ok(timings) => supported.extend(to_dv_timings(&timings)),
+            // EINVAL returned after last index.
+            Err(Errno::EINVAL) | Err(Errno::ENODATA) | Err(Errno::ENOTTY) => break,
+            Err(errno) => Err(VideoDeviceError::Ioctl { ioctl: "VIDIOC_ENUM_DV_TIMINGS", errno })?
+        }
+    }
+    Ok(supported)
+}
+
+/// Only the BT.656/1120 timings that HDMI and DVI use are understood.
+pub fn to_dv_timings(timings: &V4l2DvTimings) -> Option<DvTimings> {
+    if { timings.typ } != V4L2_DV_BT_656_1120 {
+        return None;
+    }
+    let bt = unsafe { timings.timings.bt };
+    let interlaced = bt.interlaced != 0;
+    let total_width = bt.width + bt.hfrontporch + bt.hsync + bt.hbackporch;
+    let mut total_height = bt.height + bt.vfrontporch + bt.vsync + bt.vbackporch;
+    if interlaced {
+        total_height += bt.il_vfrontporch + bt.il_vsync + bt.il_vbackporch;
+    }
+    let frame_rate = match total_width as u64 * total_height as u64 {
+        0 => 0.0,
+        pixels => bt.pixelclock as f64 / pixels as f64
+    };
+    Some(DvTimings {
+        width: bt.width,
+        height: bt.height,
+        interlaced,
+        pixel_clock: bt.pixelclock,
+        frame_rate
+    })
+}
+
+pub fn query_dv_timings(fd: RawFd) -> nix::Result<V4l2DvTimings> {
+    unsafe {
+        let mut timings: V4l2DvTimings = mem::zeroed();
+        vidioc_query_dv_timings(fd, &mut timings)?;
+        Ok(timings)
+    }
+}
+
+fn enum_dv_timings(fd: RawFd, index: u32) -> nix::Result<V4l2DvTimings> {
+    unsafe {
+        let mut enum_timings: V4l2EnumDvTimings = mem::zeroed();
+        enum_timings.index = index;
+        vidioc_enum_dv_timings(fd, &mut enum_timings)?;
+        Ok(enum_timings.timings)
+    }
+}
@@ -0,0 +1,57 @@
+//! Capture buffers in memory owned by OpenCV images, which the driver captures
+//! into by address. Frames in BGR land in images OpenCV can use as they are.
+//!
+//! See https://www.kernel.org/doc/html/latest/userspace-api/media/v4l/userp.html
+use opencv::{
+    prelude::MatTraitConst,
+    core::{
+        Mat,
+        Rect,
+        Scalar,
+        CV_8UC1,
+        CV_8UC3
+    }
+};
+use super::{
+    frame::V4L2_PIX_FMT_BGR24,
+    Format,
+    Result
+};
+
+pub fn page_size() -> usize {
+    unsafe { nix::libc::sysconf(nix::libc::_SC_PAGESIZE) }.max(4096) as usize
+}
+
+/// Frames in the format are already images OpenCV understands, with no
+/// padding between rows, so a buffer can be the image itself.
+pub fn is_native(format: &Format) -> bool {
+    format.pixel_format == V4L2_PIX_FMT_BGR24 && format.bytes_per_line == format.width * 3
+}
+
+/// A buffer for a frame of the format, starting on a page boundary as drivers
+/// that capture by DMA need. It's the frame's image for native formats, and a
+/// row of bytes otherwise.
+pub fn new_buffer_mat(format: &Format) -> Result<Mat> {
+    let length = format.size_image as usize;
+    let page_size = page_size();
+    let allocation = Mat::new_rows_cols_with_default(1, (length + page_size) as i32, CV_8UC1, Scalar::all(0.0))?;
+    let offset = (page_size - allocation.data() as usize % page_size) % page_size;
+    if !is_native(format) {
+        return Ok(Mat::roi(&allocation, Rect::new(offset as i32, 0, length as i32, 1))?);
+    }
+    // The driver may fill up to `size_image`, which the allocation leaves room for
+    // past the image. Both views keep the whole allocation alive.
+    let image_length = (format.width * 3 * format.height) as i32;
+    Ok(Mat::roi(&allocation, Rect::new(offset as i32, 0, image_length, 1))?.reshape(3, format.height as i32)?)
+}
+
+/// The Mat can be captured into for the format as it is: it's page aligned,
+/// continuous and big enough, and the frame's image for native formats.
+pub fn suits(mat: &Mat, format: &Format) -> bool {
+    let shaped = !is_native(format) ||
+        (mat.rows() == format.height as i32 && mat.cols() == format.width as i32 && mat.typ() == CV_8UC3);
+    shaped &&
+        mat.is_continuous() &&
+        mat.data() as usize % page_size() == 0 &&
+        mat.total() * mat.elem_size().unwrap_or(0) >= format.size_image as usize
+}
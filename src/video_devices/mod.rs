@@ -2,7 +2,39 @@
 #[cfg_attr(target_os = "windows", path = "windows/mod.rs")]
 mod os;
 pub use os::find_capture_devices;
+#[cfg(target_os = "linux")]
+pub use os::{
+    allocate_dmabufs,
+    find_codec_devices,
+    CodecKind,
+    CodecSession,
+    Control,
+    DeviceEvent,
+    EventKind,
+    V4l2Device
+};
+
+mod error;
+pub use error::{
+    Result,
+    VideoDeviceError
+};
 
 mod device;
+pub use device::CropBounds;
 pub use device::Device;
-pub use device::FrameSizeType;
\ No newline at end of file
+pub use device::DvTimings;
+pub use device::FrameSizeType;
+pub use device::VideoFormat;
+pub use device::VideoInput;
+pub use device::VideoStandard;
+
+mod sources;
+pub use sources::{
+    is_source,
+    redact_url,
+    source_device
+};
+
+mod test_pattern;
+pub use test_pattern::TEST_PATTERN_SCHEME;
@@ -0,0 +1,49 @@
+//! Built-in test pattern cameras, listed with the real devices when the
+//! `OPENCV_EXAMPLE_TEST_PATTERNS` environment variable says how many to add.
+use std::env;
+use super::{
+    device::to_frame_size_types,
+    Device,
+    FrameSizeType,
+    VideoFormat
+};
+
+pub const TEST_PATTERNS_VARIABLE: &str = "OPENCV_EXAMPLE_TEST_PATTERNS";
+
+/// Sources with this scheme, such as `test://1`, are test patterns.
+pub const TEST_PATTERN_SCHEME: &str = "test://";
+
+/// The modes advertised. Test patterns can be opened at any other size and rate too.
+const SIZES: [(u32, u32); 4] = [(1920, 1080), (1280, 720), (640, 480), (320, 240)];
+const FRAME_RATES: [f64; 3] = [60.0, 30.0, 15.0];
+
+pub fn add_test_pattern_devices(devices: &mut Vec<Device>) {
+    let count = env::var(TEST_PATTERNS_VARIABLE).ok()
+        .and_then(|count| count.trim().parse::<u32>().ok())
+        .unwrap_or(0);
+    for number in 1..=count {
+        devices.push(test_pattern_device(number));
+    }
+}
+
+pub fn test_pattern_device(number: u32) -> Device {
+    let formats: Vec<VideoFormat> = SIZES.iter()
+        .map(|&(width, height)| VideoFormat {
+            fourcc: i32::from_le_bytes(*b"BGR3"),
+            frame_size_type: FrameSizeType::Discrete { width, height },
+            frame_rates: FRAME_RATES.to_vec()
+        })
+        .collect();
+    Device {
+        opencv_id: -1,
+        unique_id: format!("test-pattern-{}", number),
+        name: format!("Test pattern {}", number),
+        frame_size_types: to_frame_size_types(&formats),
+        formats,
+        source: Some(format!("{}{}", TEST_PATTERN_SCHEME, number)),
+        inputs: Vec::new(),
+        standard: None,
+        dv_timings: None,
+        crop_bounds: None
+    }
+}
@@ -1,8 +1,12 @@
-use anyhow::Result;
 use std::vec::Vec;
 use super::Device;
 use super::FrameSizeType;
+use super::Result;
+use super::VideoDeviceError;
+use super::VideoFormat;
 use super::device::sort_devices;
+use super::sources::add_source_devices;
+use super::test_pattern::add_test_pattern_devices;
 
 mod msmf;
 
@@ -10,6 +14,8 @@ pub fn find_capture_devices() -> Result<Vec<Device>> {
     let mut devices = Vec::<Device>::new();
 
     msmf::add_capture_devices(&mut devices)?;
+    add_source_devices(&mut devices)?;
+    add_test_pattern_devices(&mut devices);
 
     // Could also add UEYE devices.
 
@@ -7,10 +7,9 @@
  */
 use super::Device;
 use super::FrameSizeType;
-use anyhow::{
-    Result,
-    Error
-};
+use super::Result;
+use super::VideoDeviceError;
+use super::VideoFormat;
 use windows::{
     Win32::{
         Media::MediaFoundation::{
@@ -27,7 +26,9 @@ use windows::{
             MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_GUID,
             MF_DEVSOURCE_ATTRIBUTE_FRIENDLY_NAME,
             MF_DEVSOURCE_ATTRIBUTE_SOURCE_TYPE_VIDCAP_SYMBOLIC_LINK,
-            MF_MT_FRAME_SIZE
+            MF_MT_FRAME_RATE,
+            MF_MT_FRAME_SIZE,
+            MF_MT_SUBTYPE
         },
         Foundation::{
             BOOL,
@@ -46,7 +47,7 @@ use windows::{
 };
 use std::slice;
 use opencv::videoio::CAP_MSMF;
-use super::super::device::sort_frame_size_types;
+use super::super::device::to_frame_size_types;
 
 pub fn add_capture_devices(devices: &mut Vec<Device>) -> Result<()> {
     using_com_thread(|| add_capture_devices_on_com_thread(devices))
@@ -90,7 +91,7 @@ fn mf_create_attributes(guidkey: *const GUID, guidvalue: *const GUID) -> Result<
     unsafe {
         MFCreateAttributes(&mut opt_attribute_store, 1)?;
         let attr: IMFAttributes = opt_attribute_store
-            .ok_or(Error::msg("Failed to create attribute store"))?;
+            .ok_or(VideoDeviceError::MissingObject("attribute store"))?;
         attr.SetGUID(guidkey, guidvalue)?;
         Ok(attr)
     }
@@ -108,13 +109,19 @@ fn mf_enum_device_sources<'a>(attrs: &IMFAttributes) -> Result<&'a[Option<IMFAct
 fn build_device_using_ref(device_index: i32, device_ref: &IMFActivate) -> Result<Device> {
     let friendly_name: String = get_device_friendly_name(device_ref)?;
     let symbolic_link: String = get_device_symbolic_link(device_ref)?;
-    let frame_size_types: Vec<FrameSizeType> = find_frame_size_types(device_ref)?;
+    let formats: Vec<VideoFormat> = find_formats(device_ref)?;
 
     Ok(Device{
         opencv_id: device_index | CAP_MSMF,
         unique_id: symbolic_link,
         name: friendly_name,
-        frame_size_types: frame_size_types
+        frame_size_types: to_frame_size_types(&formats),
+        formats,
+        source: None,
+        inputs: Vec::new(),
+        standard: None,
+        dv_timings: None,
+        crop_bounds: None
     })
 }
 
@@ -135,9 +142,9 @@ fn mf_device_get_allocated_string(device: &IMFActivate, guidkey: *const GUID) ->
     }
 }
 
-fn find_frame_size_types(device_ref: &IMFActivate) -> Result<Vec::<FrameSizeType>> {
+fn find_formats(device_ref: &IMFActivate) -> Result<Vec::<VideoFormat>> {
     with_activated_media_source(device_ref,
-        |media_source: &IMFMediaSource| find_frame_sizes_using_source(media_source))
+        |media_source: &IMFMediaSource| find_formats_using_source(media_source))
 }
 
 /// Activates the device to get a media source, which it passes to the function and shuts down afterwards.
@@ -151,18 +158,17 @@ fn with_activated_media_source<F,T>(device_ref: &IMFActivate, func: F) -> Result
 }
 
 
-fn find_frame_sizes_using_source(media_source: &IMFMediaSource) -> Result<Vec::<FrameSizeType>> {
+fn find_formats_using_source(media_source: &IMFMediaSource) -> Result<Vec::<VideoFormat>> {
     let descriptor: IMFPresentationDescriptor = unsafe { media_source.CreatePresentationDescriptor() }?;
     let desc_count: u32 = unsafe { descriptor.GetStreamDescriptorCount() }?;
 
-    let mut types = Vec::<FrameSizeType>::new();
+    let mut formats = Vec::<VideoFormat>::new();
     for idx in 0..desc_count {
         if let Some(stream) = get_stream_descriptor_by_idx(&descriptor, idx)? {
-            add_frame_size_types_for_stream(&stream, &mut types)?;
+            add_formats_for_stream(&stream, &mut formats)?;
         }
     }
-    sort_frame_size_types(&mut types);
-    Ok(types)
+    Ok(formats)
 }
 
 fn get_stream_descriptor_by_idx(descriptor: &IMFPresentationDescriptor, idx: u32) -> Result<Option<IMFStreamDescriptor>> {
@@ -173,23 +179,54 @@ fn get_stream_descriptor_by_idx(descriptor: &IMFPresentationDescriptor, idx: u32
     Ok(opt_desc)
 }
 
-fn add_frame_size_types_for_stream(stream_descriptor: &IMFStreamDescriptor, types: &mut Vec::<FrameSizeType>) -> Result<()> {
+fn add_formats_for_stream(stream_descriptor: &IMFStreamDescriptor, formats: &mut Vec::<VideoFormat>) -> Result<()> {
     let media_type_handler: IMFMediaTypeHandler = unsafe { stream_descriptor.GetMediaTypeHandler() }?;
     let media_type_count: u32 = unsafe { media_type_handler.GetMediaTypeCount() }?;
 
     for idx in 0..media_type_count {
-        let t = get_media_type_size_by_idx(&media_type_handler, idx)?;
-        if !types.contains(&t) {
-            types.push(t);
+        let media_type: IMFMediaType = unsafe { media_type_handler.GetMediaTypeByIndex(idx) }?;
+        let frame_size_type = get_media_type_size(&media_type)?;
+        let fourcc = get_media_type_fourcc(&media_type)?;
+        let frame_rate = get_media_type_frame_rate(&media_type);
+
+        // There's a media type for each frame rate, which we gather into one format.
+        let existing = formats.iter_mut()
+            .find(|f| f.fourcc == fourcc && f.frame_size_type == frame_size_type);
+        let format = match existing {
+            Some(format) => format,
+            None => {
+                formats.push(VideoFormat { fourcc, frame_size_type, frame_rates: Vec::new() });
+                formats.last_mut().unwrap()
+            }
+        };
+        if let Some(fps) = frame_rate {
+            if !format.frame_rates.contains(&fps) {
+                format.frame_rates.push(fps);
+                format.frame_rates.sort_by(|a, b| b.total_cmp(a));
+            }
         }
     }
     Ok(())
 }
 
-fn get_media_type_size_by_idx(media_type_handler: &IMFMediaTypeHandler, idx: u32) -> Result<FrameSizeType> {
-    let media_type: IMFMediaType = unsafe { media_type_handler.GetMediaTypeByIndex(idx) }?;        
+fn get_media_type_size(media_type: &IMFMediaType) -> Result<FrameSizeType> {
     let fs = unsafe { media_type.GetUINT64(&MF_MT_FRAME_SIZE) }?;
     let height = fs as u32;
     let width = (fs >> 32) as u32;
     Ok(FrameSizeType::Discrete { width, height })
 }
+
+/// Video subtypes carry the four character code in the first part of their GUID,
+/// which is also what OpenCV reports for `CAP_PROP_FOURCC`.
+fn get_media_type_fourcc(media_type: &IMFMediaType) -> Result<i32> {
+    let subtype: GUID = unsafe { media_type.GetGUID(&MF_MT_SUBTYPE) }?;
+    Ok(subtype.data1 as i32)
+}
+
+/// The frame rate is stored as a ratio, numerator in the upper 32 bits.
+fn get_media_type_frame_rate(media_type: &IMFMediaType) -> Option<f64> {
+    let rate = unsafe { media_type.GetUINT64(&MF_MT_FRAME_RATE) }.ok()?;
+    let numerator = (rate >> 32) as u32;
+    let denominator = rate as u32;
+    (numerator > 0 && denominator > 0).then(|| numerator as f64 / denominator as f64)
+}
\ No newline at end of file